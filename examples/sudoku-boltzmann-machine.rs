@@ -14,6 +14,7 @@ use std::io::Write;
 
 use silinapse::SymmetricMatrix;
 use silinapse::BoltzmannMachine;
+use silinapse::encoding::{OneHot, one_hot_groups};
 
 // +-------+-------+-------+
 // | 5 _ _ | 8 _ 6 | _ _ 4 |
@@ -70,6 +71,11 @@ fn display_sudoku(s: &[u8]) {
     }
 }
 
+/// The groups of neurons encoding each emplacement of the sudoku
+fn cell_groups() -> Vec<OneHot> {
+    one_hot_groups(&[9; 81])
+}
+
 /// Set the values of the neutons of the Boltzman Machine
 /// to fix the input sudoku.
 fn set_values(machine: &mut BoltzmannMachine<f32>, vals: &[u8]) {
@@ -77,16 +83,9 @@ fn set_values(machine: &mut BoltzmannMachine<f32>, vals: &[u8]) {
     for v in &mut *slice {
         *v = -0.0;
     }
-
-    for (i, &v) in vals.iter().enumerate() {
+    for (group, &v) in cell_groups().iter().zip(vals) {
         if v > 0 && v < 10 {
-            for k in 1..10 {
-                if v == k {
-                    slice[i*9 + k as usize - 1] = 1.0;
-                } else {
-                    slice[i*9 + k as usize - 1] = 0.0;
-                }
-            }
+            group.encode(v as usize - 1, slice);
         }
     }
 }
@@ -95,11 +94,9 @@ fn set_values(machine: &mut BoltzmannMachine<f32>, vals: &[u8]) {
 /// fixed values.
 fn list_fixed(sudoku: &[u8]) -> Vec<usize> {
     let mut fixed = Vec::new();
-    for (i, &v) in sudoku.iter().enumerate() {
+    for (group, &v) in cell_groups().iter().zip(sudoku) {
         if v > 0 && v < 10 {
-            for j in 0..9 {
-                fixed.push(9*i + j);
-            }
+            fixed.extend(group.units());
         }
     }
     fixed
@@ -108,48 +105,34 @@ fn list_fixed(sudoku: &[u8]) -> Vec<usize> {
 /// Generates the weight matrix associated to the sudoku
 /// for the machine
 fn generate_links() -> SymmetricMatrix<f32> {
+    let groups = cell_groups();
     let mut matrix = SymmetricMatrix::zeros(81*9);
     for i in 0..3 {
     for j in 0..3 {
     for x in 0..3 {
     for y in 0..3 {
-        let me = sudoku_to_index((i,j,x,y));
+        let me = &groups[sudoku_to_index((i,j,x,y))];
         // two neurons in the same emplacement
         // should not be activated at the same time
         // thus put a negative weigth
-        for u in 0..9 {
-        for v in 0..u {
-            matrix[(9*me + u, 9*me + v)] = -100.0;
-        }
-        }
+        me.constrain(&mut matrix, 100.0);
         // two same values in the same square should not be activated
         for u in 0..3 {
         for v in 0..3 {
             if u == x && v == y { continue; }
-            let other = sudoku_to_index((i,j,u,v));
-            for val in 0..9 {
-                matrix[(9*me + val, 9*other + val)] = -100.0;
-            }
-
+            me.exclude_equal(&groups[sudoku_to_index((i,j,u,v))], &mut matrix, 100.0);
         }}
         // two same values in the same column should not be activated
         for u in 0..3 {
         for v in 0..3 {
             if u == i && v == x { continue; }
-            let other = sudoku_to_index((u,j,v,y));
-            for val in 0..9 {
-                matrix[(9*me + val, 9*other + val)] = -100.0;
-            }
-
+            me.exclude_equal(&groups[sudoku_to_index((u,j,v,y))], &mut matrix, 100.0);
         }}
         // two same values in the same row should not be activated
         for u in 0..3 {
         for v in 0..3 {
             if u == j && v == y { continue; }
-            let other = sudoku_to_index((i,u,x,v));
-            for val in 0..9 {
-                matrix[(9*me + val, 9*other + val)] = -100.0;
-            }
+            me.exclude_equal(&groups[sudoku_to_index((i,u,x,v))], &mut matrix, 100.0);
         }}
     }}}}
     matrix
@@ -162,18 +145,13 @@ fn generate_links() -> SymmetricMatrix<f32> {
 /// otherwise, display the number associated to the only activated neuron
 fn display_machine(machine: &BoltzmannMachine<f32>) {
     let vals = machine.values();
-    let mut disp = vec![0u8; 81];
-    for i in 0..81 {
-        for j in 0..9 {
-            if vals[i*9+j] > 0.5 {
-                if disp[i] > 0 {
-                    disp[i] = 10;
-                } else {
-                    disp[i] = (j+1) as u8;
-                }
-            }
+    let disp = cell_groups().iter().map(|group| {
+        match group.decode(vals) {
+            Some(v) => (v+1) as u8,
+            None if group.units().any(|u| vals[u] > 0.5) => 10,
+            None => 0
         }
-    }
+    }).collect::<Vec<_>>();
     display_sudoku(&disp);
 }
 
@@ -1,5 +1,9 @@
 //! A set of classic activation functions.
 
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
 use num::{Float, one, zero};
 
 /// Wraps two functions or closures as an activation function that can be
@@ -15,7 +19,14 @@ pub struct ActivationFunction<F, V, D>
     pub value: V,
     /// Mathematical derivative of the activation function, to be evaluated
     /// at any point.
-    pub derivative: D
+    pub derivative: D,
+    /// The derivative of the activation function, expressed as a function
+    /// of its output rather than of its input, if possible.
+    ///
+    /// For example, the derivative of the sigmoid is `y*(1-y)`. When available
+    /// it is used by the training algorithms, as it is much cheaper than
+    /// `derivative`.
+    pub derivative_from_output: Option<fn(F) -> F>
 }
 
 impl<F, V, D> ActivationFunction<F, V, D>
@@ -28,31 +39,196 @@ impl<F, V, D> ActivationFunction<F, V, D>
         ActivationFunction {
             _marker: ::std::marker::PhantomData,
             value: value,
-            derivative: derivative
+            derivative: derivative,
+            derivative_from_output: None
+        }
+    }
+
+    /// Sets the derivative of this function expressed as a function of its
+    /// output, see `derivative_from_output`.
+    ///
+    /// It must be consistent with `derivative`: `derivative(x)` must be
+    /// equal to `derivative_from_output(value(x))` for all `x`.
+    pub fn with_derivative_from_output(mut self, derivative_from_output: fn(F) -> F)
+        -> ActivationFunction<F, V, D>
+    {
+        self.derivative_from_output = Some(derivative_from_output);
+        self
+    }
+}
+
+/// An activation function made of two plain functions, as returned by most
+/// of the functions of this module.
+pub type FnActivation<F> = ActivationFunction<F, fn(F) -> F, fn(F) -> F>;
+
+/// An activation function operating on the whole vector of pre-activation
+/// values of a layer at once.
+///
+/// This is what networks use internally. Any `ActivationFunction` is a
+/// `VectorActivation` applying itself on each component independently, but
+/// some functions like `softmax()` can only be expressed at this level.
+pub trait VectorActivation<F: Float> {
+    /// Computes the activated values from the pre-activation values `x`.
+    fn activate(&self, x: &[F]) -> Vec<F>;
+    /// Computes the product of the transposed Jacobian matrix of the
+    /// activation evaluated at `x` with the vector `v`.
+    ///
+    /// If `v` is the gradient of some error with regard to the activated
+    /// values, this is the gradient of this error with regard to `x`.
+    fn jacobian_product(&self, x: &[F], v: &[F]) -> Vec<F>;
+    /// Same as `jacobian_product`, but also given the activated values `y`
+    /// matching `x`, which some functions can use to be computed faster.
+    ///
+    /// Training algorithms always have these values at hand, and should use
+    /// this method.
+    fn jacobian_product_with_output(&self, x: &[F], y: &[F], v: &[F]) -> Vec<F> {
+        let _ = y;
+        self.jacobian_product(x, v)
+    }
+}
+
+impl<F, V, D> VectorActivation<F> for ActivationFunction<F, V, D>
+    where F: Float,
+          V: Fn(F) -> F,
+          D: Fn(F) -> F
+{
+    fn activate(&self, x: &[F]) -> Vec<F> {
+        x.iter().map(|&x| (self.value)(x)).collect()
+    }
+
+    fn jacobian_product(&self, x: &[F], v: &[F]) -> Vec<F> {
+        x.iter().zip(v).map(|(&x, &v)| (self.derivative)(x) * v).collect()
+    }
+
+    fn jacobian_product_with_output(&self, x: &[F], y: &[F], v: &[F]) -> Vec<F> {
+        match self.derivative_from_output {
+            Some(d) => y.iter().zip(v).map(|(&y, &v)| d(y) * v).collect(),
+            None => self.jacobian_product(x, v)
         }
     }
 }
 
+/// A vector of activation functions applies each of them to the value of
+/// the same index, allowing each neuron of a layer to have its own
+/// activation function.
+///
+/// Values beyond the number of activation functions are passed unchanged.
+impl<F: Float, A: VectorActivation<F>> VectorActivation<F> for Vec<A> {
+    fn activate(&self, x: &[F]) -> Vec<F> {
+        x.iter().enumerate().map(|(i, &x)| match self.get(i) {
+            Some(a) => a.activate(&[x])[0],
+            None => x
+        }).collect()
+    }
+
+    fn jacobian_product(&self, x: &[F], v: &[F]) -> Vec<F> {
+        x.iter().zip(v).enumerate().map(|(i, (&x, &v))| match self.get(i) {
+            Some(a) => a.jacobian_product(&[x], &[v])[0],
+            None => v
+        }).collect()
+    }
+
+    fn jacobian_product_with_output(&self, x: &[F], y: &[F], v: &[F]) -> Vec<F> {
+        x.iter().zip(y).zip(v).enumerate().map(|(i, ((&x, &y), &v))| match self.get(i) {
+            Some(a) => a.jacobian_product_with_output(&[x], &[y], &[v])[0],
+            None => v
+        }).collect()
+    }
+}
+
+/// The softmax function, normalizing its input into a probability
+/// distribution.
+///
+/// See `softmax()`.
+pub struct Softmax;
+
+/// Softmax function. Its outputs are all positive and sum to `1.0`, which
+/// makes it the usual choice for the output layer of a classifier.
+///
+/// Output `i` is `exp(x_i) / sum_j(exp(x_j))`.
+pub fn softmax() -> Softmax {
+    Softmax
+}
+
+impl<F: Float> VectorActivation<F> for Softmax {
+    fn activate(&self, x: &[F]) -> Vec<F> {
+        // shift by the maximum so that exp() cannot overflow
+        let max = x.iter().fold(F::neg_infinity(), |m, &x| m.max(x));
+        let exps = x.iter().map(|&x| (x - max).exp()).collect::<Vec<_>>();
+        let sum = exps.iter().fold(zero::<F>(), |s, &e| s + e);
+        exps.into_iter().map(|e| e / sum).collect()
+    }
+
+    fn jacobian_product(&self, x: &[F], v: &[F]) -> Vec<F> {
+        // the jacobian is symmetric: J_ij = y_i * (delta_ij - y_j)
+        self.jacobian_product_with_output(x, &self.activate(x), v)
+    }
+
+    fn jacobian_product_with_output(&self, _x: &[F], y: &[F], v: &[F]) -> Vec<F> {
+        let dot = y.iter().zip(v).fold(zero::<F>(), |s, (&y, &v)| s + y * v);
+        y.iter().zip(v).map(|(&y, &v)| y * (v - dot)).collect()
+    }
+}
+
 /// Identity function, do not change its input.
 ///
 /// Very bad for training but can be useful for debugging, or in some
 /// special cases.
 pub fn identity<F: Float>() -> ActivationFunction<F, fn(F) -> F, fn(F) -> F> {
-    ActivationFunction::new(identity_val, identity_der)
+    ActivationFunction {
+        derivative_from_output: Some(identity_der),
+        ..ActivationFunction::new(identity_val, identity_der)
+    }
 }
 
 fn identity_val<F: Float>(x: F) -> F { x }
 fn identity_der<F: Float>(_x: F) -> F { one() }
 
+/// Rectified linear unit, `max(0, x)`.
+///
+/// The usual choice for the hidden layers of deep networks. Its derivative
+/// is taken to be `0.0` at `0`.
+pub fn relu<F: Float>() -> FnActivation<F> {
+    ActivationFunction {
+        derivative_from_output: Some(relu_der),
+        ..ActivationFunction::new(relu_val, relu_der)
+    }
+}
+
+fn relu_val<F: Float>(x: F) -> F { x.max(zero()) }
+// also valid as a function of the output, which has the same sign
+fn relu_der<F: Float>(x: F) -> F { if x > zero() { one() } else { zero() } }
+
 /// Sigmoid function. A classic smooth learning function.
 ///
 /// Its values are `0.0` at `-inf`, `0.5` at `0` and `1.0` at `+inf`
 pub fn sigmoid<F: Float>() -> ActivationFunction<F, fn(F) -> F, fn(F) -> F> {
-    ActivationFunction::new(sigmoid_val, sigmoid_der)
+    ActivationFunction {
+        derivative_from_output: Some(sigmoid_der_out),
+        ..ActivationFunction::new(sigmoid_val, sigmoid_der)
+    }
 }
 
 fn sigmoid_val<F: Float>(x: F) -> F { one::<F>() / ( one::<F>() + (-x).exp() ) }
 fn sigmoid_der<F: Float>(x: F) -> F { x.exp() / ( one::<F>() + x.exp() ).powi(2) }
+fn sigmoid_der_out<F: Float>(y: F) -> F { y * ( one::<F>() - y ) }
+
+/// Sigmoid function with given steepness `k`, that is `sigmoid(k*x)`.
+///
+/// A high steepness makes it closer to the step function, a low one closer
+/// to a constant `0.5`. Lowering the steepness amounts to raising the
+/// temperature in annealing-style training.
+pub fn sigmoid_with_steepness<F: Float>(k: F)
+    -> ActivationFunction<F, impl Fn(F) -> F, impl Fn(F) -> F>
+{
+    ActivationFunction::new(move |x| steep_sigmoid_val(k, x), move |x| steep_sigmoid_der(k, x))
+}
+
+fn steep_sigmoid_val<F: Float>(k: F, x: F) -> F { sigmoid_val(k * x) }
+fn steep_sigmoid_der<F: Float>(k: F, x: F) -> F {
+    let s = sigmoid_val(k * x);
+    k * s * (one::<F>() - s)
+}
 
 /// Step function. Cannot be used for learning, but can be used
 /// to normalize data.
@@ -65,6 +241,17 @@ pub fn step<F: Float>() -> ActivationFunction<F, fn(F) -> F, fn(F) -> F> {
 fn step_val<F: Float>(x: F) -> F { if x.is_sign_positive() { one() } else { zero() } }
 fn step_der<F: Float>(_x: F) -> F { zero() }
 
+/// Step function with given threshold `t`.
+///
+/// It outputs `1.0` if input was greater or equal to `t`, and `0.0` otherwise.
+pub fn step_with_threshold<F: Float>(t: F)
+    -> ActivationFunction<F, impl Fn(F) -> F, impl Fn(F) -> F>
+{
+    ActivationFunction::new(move |x| threshold_step_val(t, x), step_der)
+}
+
+fn threshold_step_val<F: Float>(t: F, x: F) -> F { if x >= t { one() } else { zero() } }
+
 /// Gaussian function. Reaches its maximum `1.0` at `0.0`, and smoothly converges
 /// towards `0.0` on both infinities.
 pub fn gaussian<F: Float>() -> ActivationFunction<F, fn(F) -> F, fn(F) -> F> {
@@ -73,4 +260,445 @@ pub fn gaussian<F: Float>() -> ActivationFunction<F, fn(F) -> F, fn(F) -> F> {
 
 fn gauss_val<F: Float>(x: F) -> F { (-x.powi(2)).exp() }
 // such a terrible way to make a two: v~~~~~~~~~~~~~~~~~~~v
-fn gauss_der<F: Float>(x: F) -> F { -(one::<F>()+one::<F>())*x*(-x.powi(2)).exp() }
\ No newline at end of file
+fn gauss_der<F: Float>(x: F) -> F { -(one::<F>()+one::<F>())*x*(-x.powi(2)).exp() }
+
+/// Softplus function, a smooth approximation of the rectifier.
+///
+/// Its values are `0.0` at `-inf` and `x` at `+inf`. Its derivative is the
+/// sigmoid function.
+pub fn softplus<F: Float>() -> FnActivation<F> {
+    ActivationFunction {
+        derivative_from_output: Some(softplus_der_out),
+        ..ActivationFunction::new(softplus_val, sigmoid_val)
+    }
+}
+
+// written as max(x, 0) + ln(1 + exp(-|x|)) to avoid overflowing exp() for large inputs
+fn softplus_val<F: Float>(x: F) -> F { x.max(zero()) + (-x.abs()).exp().ln_1p() }
+fn softplus_der_out<F: Float>(y: F) -> F { -(-y).exp_m1() }
+
+/// Softsign function. Similar in shape to the hyperbolic tangent, but
+/// converges polynomially rather than exponentially to its asymptotes.
+///
+/// Its values are `-1.0` at `-inf`, `0.0` at `0` and `1.0` at `+inf`.
+pub fn softsign<F: Float>() -> FnActivation<F> {
+    ActivationFunction {
+        derivative_from_output: Some(softsign_der_out),
+        ..ActivationFunction::new(softsign_val, softsign_der)
+    }
+}
+
+fn softsign_val<F: Float>(x: F) -> F { x / ( one::<F>() + x.abs() ) }
+fn softsign_der<F: Float>(x: F) -> F { ( one::<F>() + x.abs() ).powi(2).recip() }
+fn softsign_der_out<F: Float>(y: F) -> F { ( one::<F>() - y.abs() ).powi(2) }
+
+/// Swish function `x * sigmoid(beta * x)`.
+///
+/// With `beta = 1.0` it is also known as SiLU. As `beta` grows it approaches
+/// the rectifier, and with `beta = 0.0` it is the linear function `x / 2`.
+pub fn swish<F: Float>(beta: F) -> ActivationFunction<F, impl Fn(F) -> F, impl Fn(F) -> F> {
+    ActivationFunction::new(move |x| swish_val(beta, x), move |x| swish_der(beta, x))
+}
+
+fn swish_val<F: Float>(beta: F, x: F) -> F { x * sigmoid_val(beta * x) }
+fn swish_der<F: Float>(beta: F, x: F) -> F {
+    let s = sigmoid_val(beta * x);
+    s + beta * x * s * (one::<F>() - s)
+}
+
+/// Gaussian Error Linear Unit, `x * Phi(x)` where `Phi` is the cumulative
+/// distribution function of the standard normal distribution.
+///
+/// Both the value and the derivative use the classic `tanh` approximation
+/// of `Phi`, so that they are consistent with each other.
+pub fn gelu<F: Float>() -> FnActivation<F> {
+    ActivationFunction::new(gelu_val, gelu_der)
+}
+
+// sqrt(2/pi) and the cubic coefficient of the tanh approximation
+fn gelu_consts<F: Float>() -> (F, F) {
+    (F::from(0.7978845608028654).unwrap(), F::from(0.044715).unwrap())
+}
+
+fn gelu_val<F: Float>(x: F) -> F {
+    let (k, c) = gelu_consts::<F>();
+    let half = F::from(0.5).unwrap();
+    half * x * ( one::<F>() + ( k * (x + c * x.powi(3)) ).tanh() )
+}
+
+fn gelu_der<F: Float>(x: F) -> F {
+    let (k, c) = gelu_consts::<F>();
+    let half = F::from(0.5).unwrap();
+    let three = F::from(3.0).unwrap();
+    let t = ( k * (x + c * x.powi(3)) ).tanh();
+    half * ( one::<F>() + t )
+        + half * x * ( one::<F>() - t * t ) * k * ( one::<F>() + three * c * x * x )
+}
+
+/// A serializable description of an activation function.
+///
+/// Unlike `ActivationFunction`, which can wrap arbitrary closures, this enum
+/// lists the built-in functions of this module by name, and can thus be
+/// cloned, compared, printed and stored. Networks using it as activation
+/// can be as well.
+///
+/// It is written with `Display` as the name of the function followed by its
+/// parameter if any, like `relu` or `swish(1.5)`, and read back with
+/// `FromStr`.
+///
+/// Other functions can still be used through the `Custom` variant, at the
+/// cost of these properties being only as meaningful as function pointers:
+/// it is written as `custom` and cannot be read back.
+#[derive(Clone, Copy, Debug)]
+pub enum Activation<F: Float> {
+    /// See `identity()`.
+    Identity,
+    /// See `relu()`.
+    Relu,
+    /// See `sigmoid()`.
+    Sigmoid,
+    /// See `sigmoid_with_steepness(k)`.
+    SigmoidWithSteepness(F),
+    /// See `step()`.
+    Step,
+    /// See `step_with_threshold(t)`.
+    StepWithThreshold(F),
+    /// See `gaussian()`.
+    Gaussian,
+    /// See `softplus()`.
+    Softplus,
+    /// See `softsign()`.
+    Softsign,
+    /// See `swish(beta)`.
+    Swish(F),
+    /// See `gelu()`.
+    Gelu,
+    /// See `softmax()`.
+    Softmax,
+    /// A function given as its value and derivative.
+    Custom(fn(F) -> F, fn(F) -> F)
+}
+
+impl<F: Float> Activation<F> {
+    /// The value of the function at `x`.
+    ///
+    /// `Softmax` depends on the whole vector of values, this is its value for
+    /// a vector of one value: `1.0`.
+    pub fn value(&self, x: F) -> F {
+        match *self {
+            Activation::Identity => identity_val(x),
+            Activation::Relu => relu_val(x),
+            Activation::Sigmoid => sigmoid_val(x),
+            Activation::SigmoidWithSteepness(k) => steep_sigmoid_val(k, x),
+            Activation::Step => step_val(x),
+            Activation::StepWithThreshold(t) => threshold_step_val(t, x),
+            Activation::Gaussian => gauss_val(x),
+            Activation::Softplus => softplus_val(x),
+            Activation::Softsign => softsign_val(x),
+            Activation::Swish(beta) => swish_val(beta, x),
+            Activation::Gelu => gelu_val(x),
+            Activation::Softmax => one(),
+            Activation::Custom(value, _) => value(x)
+        }
+    }
+
+    /// The derivative of the function at `x`.
+    ///
+    /// Like for `value(..)`, the derivative of `Softmax` is the one for a
+    /// vector of one value: `0.0`.
+    pub fn derivative(&self, x: F) -> F {
+        match *self {
+            Activation::Identity => identity_der(x),
+            Activation::Relu => relu_der(x),
+            Activation::Sigmoid => sigmoid_der(x),
+            Activation::SigmoidWithSteepness(k) => steep_sigmoid_der(k, x),
+            Activation::Step | Activation::StepWithThreshold(_) => step_der(x),
+            Activation::Gaussian => gauss_der(x),
+            Activation::Softplus => sigmoid_val(x),
+            Activation::Softsign => softsign_der(x),
+            Activation::Swish(beta) => swish_der(beta, x),
+            Activation::Gelu => gelu_der(x),
+            Activation::Softmax => zero(),
+            Activation::Custom(_, derivative) => derivative(x)
+        }
+    }
+}
+
+impl<F: Float> PartialEq for Activation<F> {
+    fn eq(&self, other: &Activation<F>) -> bool {
+        use self::Activation::*;
+        match (*self, *other) {
+            (SigmoidWithSteepness(a), SigmoidWithSteepness(b)) => a == b,
+            (StepWithThreshold(a), StepWithThreshold(b)) => a == b,
+            (Swish(a), Swish(b)) => a == b,
+            (Custom(v1, d1), Custom(v2, d2)) => {
+                v1 as usize == v2 as usize && d1 as usize == d2 as usize
+            },
+            (a, b) => ::std::mem::discriminant(&a) == ::std::mem::discriminant(&b)
+        }
+    }
+}
+
+impl<F: Float> fmt::Display for Activation<F> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let param = |name: &str, p: F| format!("{}({})", name, p.to_f64().unwrap());
+        let text = match *self {
+            Activation::Identity => "identity".to_owned(),
+            Activation::Relu => "relu".to_owned(),
+            Activation::Sigmoid => "sigmoid".to_owned(),
+            Activation::SigmoidWithSteepness(k) => param("sigmoid_with_steepness", k),
+            Activation::Step => "step".to_owned(),
+            Activation::StepWithThreshold(t) => param("step_with_threshold", t),
+            Activation::Gaussian => "gaussian".to_owned(),
+            Activation::Softplus => "softplus".to_owned(),
+            Activation::Softsign => "softsign".to_owned(),
+            Activation::Swish(beta) => param("swish", beta),
+            Activation::Gelu => "gelu".to_owned(),
+            Activation::Softmax => "softmax".to_owned(),
+            Activation::Custom(..) => "custom".to_owned()
+        };
+        f.write_str(&text)
+    }
+}
+
+/// The error returned when reading an `Activation` from an invalid string.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseActivationError {
+    input: String
+}
+
+impl fmt::Display for ParseActivationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid activation function: {:?}", self.input)
+    }
+}
+
+impl Error for ParseActivationError {}
+
+impl<F: Float> FromStr for Activation<F> {
+    type Err = ParseActivationError;
+
+    fn from_str(s: &str) -> Result<Activation<F>, ParseActivationError> {
+        let error = || ParseActivationError { input: s.to_owned() };
+        let s = s.trim();
+        let (name, param) = match s.find('(') {
+            Some(i) if s.ends_with(')') => {
+                let param = s[i+1..s.len()-1].trim().parse::<f64>().map_err(|_| error())?;
+                (&s[..i], Some(F::from(param).ok_or_else(error)?))
+            },
+            Some(_) => return Err(error()),
+            None => (s, None)
+        };
+        match (name, param) {
+            ("identity", None) => Ok(Activation::Identity),
+            ("relu", None) => Ok(Activation::Relu),
+            ("sigmoid", None) => Ok(Activation::Sigmoid),
+            ("sigmoid_with_steepness", Some(k)) => Ok(Activation::SigmoidWithSteepness(k)),
+            ("step", None) => Ok(Activation::Step),
+            ("step_with_threshold", Some(t)) => Ok(Activation::StepWithThreshold(t)),
+            ("gaussian", None) => Ok(Activation::Gaussian),
+            ("softplus", None) => Ok(Activation::Softplus),
+            ("softsign", None) => Ok(Activation::Softsign),
+            ("swish", Some(beta)) => Ok(Activation::Swish(beta)),
+            ("gelu", None) => Ok(Activation::Gelu),
+            ("softmax", None) => Ok(Activation::Softmax),
+            _ => Err(error())
+        }
+    }
+}
+
+impl<F: Float> VectorActivation<F> for Activation<F> {
+    fn activate(&self, x: &[F]) -> Vec<F> {
+        match *self {
+            Activation::Softmax => Softmax.activate(x),
+            _ => x.iter().map(|&x| self.value(x)).collect()
+        }
+    }
+
+    fn jacobian_product(&self, x: &[F], v: &[F]) -> Vec<F> {
+        match *self {
+            Activation::Softmax => Softmax.jacobian_product(x, v),
+            _ => x.iter().zip(v).map(|(&x, &v)| self.derivative(x) * v).collect()
+        }
+    }
+
+    fn jacobian_product_with_output(&self, x: &[F], y: &[F], v: &[F]) -> Vec<F> {
+        let from_output = |d: &dyn Fn(F) -> F| y.iter().zip(v).map(|(&y, &v)| d(y) * v).collect();
+        match *self {
+            Activation::Identity => v.to_owned(),
+            Activation::Relu => from_output(&relu_der),
+            Activation::Sigmoid => from_output(&sigmoid_der_out),
+            Activation::SigmoidWithSteepness(k) => from_output(&|y| k * sigmoid_der_out(y)),
+            Activation::Softplus => from_output(&softplus_der_out),
+            Activation::Softsign => from_output(&softsign_der_out),
+            Activation::Softmax => Softmax.jacobian_product_with_output(x, y, v),
+            _ => self.jacobian_product(x, v)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{relu, softplus, softsign, swish, gelu, softmax, VectorActivation};
+    use super::{sigmoid, sigmoid_with_steepness, step_with_threshold, Activation};
+
+    #[test]
+    fn softplus_values() {
+        let f = softplus::<f64>();
+        assert!(((f.value)(0.0) - 2.0f64.ln()).abs() < 1e-12);
+        // must neither overflow nor lose the linear asymptote
+        assert_eq!((f.value)(1000.0), 1000.0);
+        assert_eq!((f.value)(-1000.0), 0.0);
+        assert!(((f.derivative)(0.0) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn relu_values() {
+        let f = relu::<f64>();
+        assert_eq!((f.value)(-2.0), 0.0);
+        assert_eq!((f.value)(1.5), 1.5);
+        assert_eq!((f.derivative)(-2.0), 0.0);
+        assert_eq!((f.derivative)(0.0), 0.0);
+        assert_eq!((f.derivative)(1.5), 1.0);
+    }
+
+    #[test]
+    fn softsign_values() {
+        let f = softsign::<f64>();
+        assert_eq!((f.value)(1.0), 0.5);
+        assert_eq!((f.value)(-1.0), -0.5);
+        assert_eq!((f.derivative)(1.0), 0.25);
+        assert_eq!((f.derivative)(-1.0), 0.25);
+    }
+
+    #[test]
+    fn derivatives_match_values() {
+        let h = 1e-6;
+        for &x in &[-3.0f64, -0.5, 0.0, 0.7, 4.0] {
+            let f = softplus::<f64>();
+            let num = ((f.value)(x+h) - (f.value)(x-h)) / (2.0*h);
+            assert!((num - (f.derivative)(x)).abs() < 1e-6);
+            let f = softsign::<f64>();
+            let num = ((f.value)(x+h) - (f.value)(x-h)) / (2.0*h);
+            assert!((num - (f.derivative)(x)).abs() < 1e-6);
+            let f = swish::<f64>(1.5);
+            let num = ((f.value)(x+h) - (f.value)(x-h)) / (2.0*h);
+            assert!((num - (f.derivative)(x)).abs() < 1e-6);
+            let f = gelu::<f64>();
+            let num = ((f.value)(x+h) - (f.value)(x-h)) / (2.0*h);
+            assert!((num - (f.derivative)(x)).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn gelu_values() {
+        let f = gelu::<f64>();
+        assert_eq!((f.value)(0.0), 0.0);
+        assert!(((f.derivative)(0.0) - 0.5).abs() < 1e-12);
+        // close to the exact x * Phi(x) = 0.8413447 at 1.0
+        assert!(((f.value)(1.0) - 0.8413447).abs() < 1e-3);
+    }
+
+    #[test]
+    fn softmax_values() {
+        let y = softmax().activate(&[1.0f64, 2.0, 3.0]);
+        assert!((y.iter().fold(0.0, |s, y| s + y) - 1.0).abs() < 1e-12);
+        assert!(y[0] < y[1] && y[1] < y[2]);
+        // no overflow for large inputs
+        let y = softmax().activate(&[1000.0f64, 1000.0]);
+        assert_eq!(y, [0.5, 0.5]);
+    }
+
+    #[test]
+    fn softmax_jacobian() {
+        let h = 1e-6;
+        let x = [0.3f64, -1.2, 2.0];
+        let v = [1.0f64, 0.5, -2.0];
+        let jv = softmax().jacobian_product(&x, &v);
+        for k in 0..3 {
+            let mut xp = x; xp[k] += h;
+            let mut xm = x; xm[k] -= h;
+            let (yp, ym) = (softmax().activate(&xp), softmax().activate(&xm));
+            let num = (0..3).fold(0.0, |s, i| s + v[i] * (yp[i] - ym[i]) / (2.0*h));
+            assert!((num - jv[k]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn steepness_and_threshold() {
+        let s = sigmoid::<f64>();
+        let f = sigmoid_with_steepness::<f64>(1.0);
+        assert_eq!((f.value)(0.3), (s.value)(0.3));
+        assert!(((f.derivative)(0.3) - (s.derivative)(0.3)).abs() < 1e-12);
+        let f = sigmoid_with_steepness::<f64>(4.0);
+        assert_eq!((f.value)(0.25), (s.value)(1.0));
+        assert!(((f.derivative)(0.0) - 1.0).abs() < 1e-12);
+        let f = step_with_threshold::<f64>(0.5);
+        assert_eq!((f.value)(0.4), 0.0);
+        assert_eq!((f.value)(0.5), 1.0);
+        assert_eq!((f.derivative)(0.5), 0.0);
+    }
+
+    #[test]
+    fn activation_enum() {
+        let x = [-2.0f64, -0.3, 0.0, 0.8, 3.0];
+        let v = [1.0f64, -1.0, 0.5, 2.0, 1.0];
+        let pairs: Vec<(Activation<f64>, Box<dyn VectorActivation<f64>>)> = vec![
+            (Activation::Relu, Box::new(relu())),
+            (Activation::Sigmoid, Box::new(sigmoid())),
+            (Activation::SigmoidWithSteepness(3.0), Box::new(sigmoid_with_steepness(3.0))),
+            (Activation::StepWithThreshold(0.5), Box::new(step_with_threshold(0.5))),
+            (Activation::Softplus, Box::new(softplus())),
+            (Activation::Swish(0.7), Box::new(swish(0.7))),
+            (Activation::Gelu, Box::new(gelu())),
+            (Activation::Softmax, Box::new(softmax())),
+        ];
+        for (a, f) in &pairs {
+            assert_eq!(a.activate(&x), f.activate(&x));
+            assert_eq!(a.jacobian_product(&x, &v), f.jacobian_product(&x, &v));
+        }
+        assert_eq!(Activation::Swish(0.7f64), Activation::Swish(0.7));
+        assert!(Activation::Swish(0.7f64) != Activation::Swish(1.0));
+    }
+
+    #[test]
+    fn activation_round_trip() {
+        let all: Vec<Activation<f32>> = vec![
+            Activation::Identity, Activation::Relu, Activation::Sigmoid,
+            Activation::SigmoidWithSteepness(2.5), Activation::Step,
+            Activation::StepWithThreshold(-0.1), Activation::Gaussian, Activation::Softplus,
+            Activation::Softsign, Activation::Swish(0.7), Activation::Gelu, Activation::Softmax
+        ];
+        for a in all {
+            assert_eq!(a.to_string().parse::<Activation<f32>>(), Ok(a));
+        }
+        assert_eq!(Activation::Swish(1.5f64).to_string(), "swish(1.5)");
+        assert_eq!(" relu ".parse::<Activation<f64>>(), Ok(Activation::Relu));
+        for s in &["custom", "swish", "relu(1)", "swish(x)", "swish(1", "tanh"] {
+            assert!(s.parse::<Activation<f64>>().is_err());
+        }
+        // the element-wise view of softmax is the one of a single value
+        assert_eq!(Activation::Softmax.value(3.0f64), 1.0);
+        assert_eq!(Activation::Softmax.derivative(3.0f64), 0.0);
+    }
+
+    #[test]
+    fn derivative_from_output() {
+        let x = [-2.0f64, -0.3, 0.0, 0.8, 3.0];
+        let v = [1.0f64, -1.0, 0.5, 2.0, 1.0];
+        let funcs: Vec<Box<dyn VectorActivation<f64>>> = vec![
+            Box::new(sigmoid()), Box::new(softplus()), Box::new(softsign()), Box::new(relu()),
+            Box::new(Activation::Identity), Box::new(Activation::Relu), Box::new(Activation::SigmoidWithSteepness(2.0)),
+            Box::new(Activation::Sigmoid), Box::new(Activation::Softplus),
+            Box::new(Activation::Softsign), Box::new(Activation::Softmax)
+        ];
+        for f in &funcs {
+            let y = f.activate(&x);
+            let fast = f.jacobian_product_with_output(&x, &y, &v);
+            let slow = f.jacobian_product(&x, &v);
+            for (a, b) in fast.iter().zip(&slow) {
+                assert!((a - b).abs() < 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! Active learning.
+//!
+//! When labeling data is expensive, the samples to label should be chosen
+//! carefully: the ones a classifier is the least sure about are the most
+//! informative. This module picks them from a pool of unlabeled samples,
+//! given a network whose outputs are class probabilities (typically with a
+//! softmax activation on its last layer).
+
+use std::cmp::Ordering;
+
+use num::{Float, one, zero};
+
+use Compute;
+
+/// A way to measure how informative the label of a sample would be, given
+/// the class probabilities predicted for it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Strategy {
+    /// Uncertainty sampling: prefer the samples whose most probable class
+    /// has the lowest probability.
+    Uncertainty,
+    /// Margin sampling: prefer the samples whose two most probable classes
+    /// have the closest probabilities.
+    Margin,
+    /// Entropy sampling: prefer the samples whose predicted distribution
+    /// has the highest entropy.
+    Entropy
+}
+
+impl Strategy {
+    /// Scores the informativeness of a sample from its predicted class
+    /// probabilities, the highest being the most informative.
+    pub fn score<F: Float>(&self, probabilities: &[F]) -> F {
+        match *self {
+            Strategy::Uncertainty => {
+                one::<F>() - probabilities.iter().fold(zero(), |m: F, &p| m.max(p))
+            },
+            Strategy::Margin => {
+                let (first, second) = probabilities.iter().fold((zero::<F>(), zero::<F>()), |(a, b), &p| {
+                    if p > a { (p, a) } else if p > b { (a, p) } else { (a, b) }
+                });
+                second - first
+            },
+            Strategy::Entropy => {
+                probabilities.iter()
+                             .filter(|&&p| p > zero())
+                             .fold(zero(), |s: F, &p| s - p * p.ln())
+            }
+        }
+    }
+}
+
+/// Selects the `k` most informative samples of `pool` according to
+/// `strategy`, and returns their indices, the most informative first.
+///
+/// If the pool contains less than `k` samples, all of them are returned.
+pub fn select<F, N>(network: &N, pool: &[Vec<F>], k: usize, strategy: Strategy) -> Vec<usize>
+    where F: Float,
+          N: Compute<F>
+{
+    let scores = pool.iter().map(|x| strategy.score(&network.compute(x))).collect::<Vec<_>>();
+    let mut indices = (0..pool.len()).collect::<Vec<_>>();
+    indices.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).unwrap_or(Ordering::Equal));
+    indices.truncate(k);
+    indices
+}
+
+#[cfg(test)]
+mod tests {
+    use util::Identity;
+
+    use super::{Strategy, select};
+
+    #[test]
+    fn strategies() {
+        let confident = [0.9f64, 0.05, 0.05];
+        let split = [0.45, 0.45, 0.1];
+        let flat = [0.4, 0.3, 0.3];
+        for &s in &[Strategy::Uncertainty, Strategy::Margin, Strategy::Entropy] {
+            assert!(s.score(&confident) < s.score(&split));
+            assert!(s.score(&confident) < s.score(&flat));
+        }
+        // the strategies disagree on these two
+        assert!(Strategy::Margin.score(&split) > Strategy::Margin.score(&flat));
+        assert!(Strategy::Entropy.score(&split) < Strategy::Entropy.score(&flat));
+    }
+
+    #[test]
+    fn selection() {
+        let pool = vec![vec![1.0f64, 0.0], vec![0.5, 0.5], vec![0.8, 0.2], vec![0.6, 0.4]];
+        assert_eq!(select(&Identity::new(2), &pool, 2, Strategy::Entropy), [1, 3]);
+        assert_eq!(select(&Identity::new(2), &pool, 10, Strategy::Margin), [1, 3, 2, 0]);
+    }
+}
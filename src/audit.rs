@@ -0,0 +1,178 @@
+//! Auditing of predictions.
+//!
+//! The `Traced` adapter records every computation of the network it wraps
+//! into a user-supplied `Sink`, so that the predictions of a network
+//! embedded in a service can be audited afterwards.
+
+use std::cell::{Ref, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::Hasher;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+use num::Float;
+
+use Compute;
+
+/// The record of one computation of a traced network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call<F> {
+    /// A hash of the input, identifying it without storing it.
+    pub input_hash: u64,
+    /// The output of the network.
+    pub output: Vec<F>,
+    /// The time taken by the computation.
+    pub latency: Duration,
+    /// The version of the network, as given to `Traced`.
+    pub version: u64
+}
+
+/// A trait for the destinations of the records of a `Traced` network.
+///
+/// It is implemented by `Vec<Call<F>>`, which keeps all the records, and by
+/// closures taking a `Call<F>`.
+pub trait Sink<F> {
+    /// Records a computation.
+    fn record(&mut self, call: Call<F>);
+}
+
+impl<F> Sink<F> for Vec<Call<F>> {
+    fn record(&mut self, call: Call<F>) {
+        self.push(call);
+    }
+}
+
+impl<F, T: FnMut(Call<F>)> Sink<F> for T {
+    fn record(&mut self, call: Call<F>) {
+        self(call);
+    }
+}
+
+/// Hashes a vector of values, equal vectors having equal hashes.
+pub fn hash_input<F: Float>(input: &[F]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    for x in input {
+        let (mantissa, exponent, sign) = x.integer_decode();
+        hasher.write_u64(mantissa);
+        hasher.write_i16(exponent);
+        hasher.write_i8(sign);
+    }
+    hasher.finish()
+}
+
+/// An adapter recording each computation of a network into a `Sink`.
+///
+/// Training a traced network is not possible: it is meant to wrap a trained
+/// network for inference.
+pub struct Traced<F, N, S> {
+    _marker: PhantomData<F>,
+    network: N,
+    sink: RefCell<S>,
+    version: u64
+}
+
+impl<F: Float, N: Compute<F>, S: Sink<F>> Traced<F, N, S> {
+    /// Wraps `network`, recording its computations in `sink` along with
+    /// given version number.
+    pub fn new(network: N, sink: S, version: u64) -> Traced<F, N, S> {
+        Traced {
+            _marker: PhantomData,
+            network,
+            sink: RefCell::new(sink),
+            version
+        }
+    }
+
+    /// The wrapped network.
+    pub fn network(&self) -> &N {
+        &self.network
+    }
+
+    /// The sink of the records.
+    pub fn sink<'a>(&'a self) -> Ref<'a, S> {
+        self.sink.borrow()
+    }
+
+    /// The version number of the wrapped network.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Replaces the wrapped network by a new version of it, returning the
+    /// previous one.
+    pub fn replace(&mut self, network: N, version: u64) -> N {
+        self.version = version;
+        ::std::mem::replace(&mut self.network, network)
+    }
+
+    /// Consumes the adapter, returning the network and the sink.
+    pub fn into_parts(self) -> (N, S) {
+        (self.network, self.sink.into_inner())
+    }
+}
+
+impl<F: Float, N: Compute<F>, S: Sink<F>> Compute<F> for Traced<F, N, S> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let start = Instant::now();
+        let output = self.network.compute(input);
+        let latency = start.elapsed();
+        self.sink.borrow_mut().record(Call {
+            input_hash: hash_input(input),
+            output: output.clone(),
+            latency,
+            version: self.version
+        });
+        output
+    }
+
+    fn input_size(&self) -> usize {
+        self.network.input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.network.output_size()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.network.set_training(training);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use Compute;
+    use util::{Dropout, FixedOutput};
+
+    use super::{Call, Traced, hash_input};
+
+    #[test]
+    fn traced() {
+        let mut traced = Traced::new(FixedOutput::new(&[1.0f64, 2.0]), Vec::new(), 1);
+        traced.compute(&[0.5]);
+        traced.replace(FixedOutput::new(&[3.0]), 2);
+        assert_eq!(traced.compute(&[0.5]), [3.0]);
+        assert_eq!(traced.sink().len(), 2);
+        let (_, calls): (_, Vec<Call<f64>>) = traced.into_parts();
+        assert_eq!(calls[0].output, [1.0, 2.0]);
+        assert_eq!((calls[0].version, calls[1].version), (1, 2));
+        assert_eq!(calls[0].input_hash, calls[1].input_hash);
+        assert!(hash_input(&[0.5f64]) != hash_input(&[0.25f64]));
+
+        let mut count = 0;
+        {
+            let traced = Traced::new(FixedOutput::new(&[1.0f64]), |_: Call<f64>| count += 1, 0);
+            traced.compute(&[]);
+            traced.compute(&[1.0]);
+        }
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn training_mode() {
+        let mut traced = Traced::new(Dropout::new(4, 0.5f64), Vec::new(), 0);
+        assert_eq!(traced.compute(&[2.0; 4]), [1.0; 4]);
+        traced.set_training(true);
+        assert!(traced.network().is_training());
+        assert!(traced.compute(&[2.0; 4]).iter().all(|&x| x == 0.0 || x == 2.0));
+    }
+}
@@ -1,7 +1,11 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::thread;
+
 use num::{Float, one, zero};
 
 use rand::{Rand, random, thread_rng};
-use rand::distributions::{IndependentSample, Range};
+use rand::distributions::{IndependentSample, Normal, Range};
 
 use SymmetricMatrix;
 
@@ -19,10 +23,35 @@ use SymmetricMatrix;
 /// to have it deactivated.
 ///
 /// The process is probabilistic and will tend to minimising the unsatisfied constraint.
+///
+/// Neurons can also be made continuous-valued, see `UnitType`.
+#[derive(Clone, Debug, PartialEq)]
 pub struct BoltzmannMachine<F: Float> {
     values: Vec<F>,
     biases: Vec<F>,
-    coeffs: SymmetricMatrix<F>
+    coeffs: SymmetricMatrix<F>,
+    // the non-zero couplings of each unit, so that updating a unit of a
+    // sparsely connected machine does not need to go through all units
+    neighbors: Vec<Vec<(usize, F)>>,
+    clamped: Vec<bool>,
+    // the number of clamped units, to know cheaply whether any is left free
+    clamped_count: usize,
+    groups: HashMap<String, Vec<usize>>,
+    types: Vec<UnitType>
+}
+
+/// The kind of values a neuron of a `BoltzmannMachine` takes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UnitType {
+    /// The neuron is either `0.0` or `1.0`, the former being more likely
+    /// the more negative its input is.
+    Binary,
+    /// The neuron takes real values, drawn from a normal distribution
+    /// centered on its input, with a variance equal to the temperature.
+    ///
+    /// Such neurons add `s_i^2 / 2` to the energy of the machine. Couplings
+    /// between them must remain small for this energy to stay bounded.
+    Gaussian
 }
 
 impl<F: Float> BoltzmannMachine<F> {
@@ -32,6 +61,11 @@ impl<F: Float> BoltzmannMachine<F> {
         BoltzmannMachine {
             values: vec![one(); n],
             biases: vec![zero(); n],
+            neighbors: (0..n).map(|i| weigths.nonzero_neighbors(i)).collect(),
+            clamped: vec![false; n],
+            clamped_count: 0,
+            groups: HashMap::new(),
+            types: vec![UnitType::Binary; n],
             coeffs: weigths
         }
     }
@@ -43,13 +77,19 @@ impl<F: Float> BoltzmannMachine<F> {
         BoltzmannMachine {
             values: vec![one(); n],
             biases: biases,
+            neighbors: (0..n).map(|i| weigths.nonzero_neighbors(i)).collect(),
+            clamped: vec![false; n],
+            clamped_count: 0,
+            groups: HashMap::new(),
+            types: vec![UnitType::Binary; n],
             coeffs: weigths
         }
     }
 
     /// Get access to the values of the neurons.
     ///
-    /// All values generated by the algorithm are either `0.0` or `1.0`.
+    /// All values generated by the algorithm for binary neurons are either
+    /// `0.0` or `1.0`.
     pub fn values(&self) -> &[F] {
         &self.values
     }
@@ -61,6 +101,120 @@ impl<F: Float> BoltzmannMachine<F> {
     pub fn values_mut(&mut self) -> &mut [F] {
         &mut self.values
     }
+
+    /// Names a group of neurons, for example the neurons one-hot encoding
+    /// a variable, so that they can be addressed together.
+    ///
+    /// Defining a group with an existing name replaces it.
+    pub fn define_group(&mut self, name: &str, indices: Vec<usize>) {
+        assert!(indices.iter().all(|&i| i < self.values.len()), "The group indices must be neuron indices.");
+        self.groups.insert(name.to_owned(), indices);
+    }
+
+    /// The indices of the neurons of given group, if it is defined.
+    pub fn group(&self, name: &str) -> Option<&[usize]> {
+        self.groups.get(name).map(|g| &g[..])
+    }
+
+    fn group_indices(&self, name: &str) -> &[usize] {
+        self.group(name).expect("Unknown neuron group.")
+    }
+
+    /// The values of the neurons of given group, in the order of its
+    /// definition.
+    pub fn group_values(&self, name: &str) -> Vec<F> {
+        self.group_indices(name).iter().map(|&i| self.values[i]).collect()
+    }
+
+    /// Sets the values of the neurons of given group, and clamps them: the
+    /// updates of the machine leave them unchanged until `unclamp_group(..)`
+    /// is called.
+    pub fn clamp_group(&mut self, name: &str, values: &[F]) {
+        let indices = self.group_indices(name).to_owned();
+        assert!(values.len() == indices.len(), "There must be one value per neuron of the group.");
+        for (&i, &v) in indices.iter().zip(values) {
+            self.values[i] = v;
+            if !self.clamped[i] {
+                self.clamped[i] = true;
+                self.clamped_count += 1;
+            }
+        }
+    }
+
+    /// Releases the neurons of given group, clamped by `clamp_group(..)`.
+    pub fn unclamp_group(&mut self, name: &str) {
+        let indices = self.group_indices(name).to_owned();
+        for i in indices {
+            if self.clamped[i] {
+                self.clamped[i] = false;
+                self.clamped_count -= 1;
+            }
+        }
+    }
+
+    /// Reads given group as a one-hot encoding: returns the position of its
+    /// only active neuron, or `None` if no neuron or several neurons are
+    /// active.
+    pub fn decode_group(&self, name: &str) -> Option<usize> {
+        let half = F::from(0.5).unwrap();
+        let mut active = self.group_indices(name).iter().enumerate().filter(|&(_, &i)| self.values[i] > half);
+        match (active.next(), active.next()) {
+            (Some((k, _)), None) => Some(k),
+            _ => None
+        }
+    }
+
+    /// Saves the current state of the machine, to be restored later with
+    /// `restore(..)`, for example to backtrack after an unsuccessful run.
+    pub fn snapshot(&self) -> Snapshot<F> {
+        Snapshot { values: self.values.clone() }
+    }
+
+    /// Restores a state previously saved with `snapshot()`.
+    pub fn restore(&mut self, snapshot: &Snapshot<F>) {
+        assert!(snapshot.values.len() == self.values.len(),
+                "The snapshot must come from a machine of the same size.");
+        self.values.copy_from_slice(&snapshot.values);
+    }
+
+    /// The weights of the couplings between the neurons.
+    pub fn weights(&self) -> &SymmetricMatrix<F> {
+        &self.coeffs
+    }
+
+    /// The biases of the neurons.
+    pub fn biases(&self) -> &[F] {
+        &self.biases
+    }
+
+    /// The type of given neuron, all neurons being binary by default.
+    pub fn unit_type(&self, i: usize) -> UnitType {
+        self.types[i]
+    }
+
+    /// Changes the type of given neuron.
+    pub fn set_unit_type(&mut self, i: usize, unit_type: UnitType) {
+        self.types[i] = unit_type;
+    }
+
+    /// The energy of the current state of the machine, which the updates
+    /// tend to minimize:
+    ///
+    /// ```text
+    /// E = - sum_{i<j}( w_ij * s_i * s_j ) - sum_i( b_i * s_i ) + sum_{i gaussian}( s_i^2 / 2 )
+    /// ```
+    pub fn energy(&self) -> F {
+        let couplings = self.coeffs.iter()
+                                   .filter(|&(i, j, _)| i != j)
+                                   .fold(zero::<F>(), |e, (i, j, w)| e - w * self.values[i] * self.values[j]);
+        let two = F::from(2).unwrap();
+        self.biases.iter().zip(&self.values).zip(&self.types).fold(couplings, |e, ((&b, &s), &t)| {
+            match t {
+                UnitType::Binary => e - b * s,
+                UnitType::Gaussian => e - b * s + s * s / two
+            }
+        })
+    }
 }
 
 impl<F: Float + Rand> BoltzmannMachine<F> {
@@ -72,23 +226,7 @@ impl<F: Float + Rand> BoltzmannMachine<F> {
     ///
     /// See `tick_one_random(..)` for explanations on the `temperature` parameter.
     pub fn tick_all_sequential(&mut self, temperature: F,  exclude: &[usize]) {
-        let n = self.values.len();
-        for i in 0..n {
-            if exclude.contains(&i) { continue; }
-            let mut val = self.biases[i];
-            for j in 0..n {
-                if i!=j {
-                    val = val + self.values[j] * self.coeffs[(i,j)];
-                }
-            }
-            val = -val / temperature;
-            if random::<F>() < (one::<F>() + val.exp()).recip() {
-                val = one::<F>();
-            } else {
-                val = zero::<F>();
-            };
-            self.values[i] = val;
-        }
+        self.sweep(temperature, exclude);
     }
 
     /// Updates a random neuron of the network, excluding the indices provided
@@ -106,26 +244,316 @@ impl<F: Float + Rand> BoltzmannMachine<F> {
     ///
     /// Putting a negative temperature would be similar in effect as multiplying all
     /// weigths and biases by `-1.0`.
+    ///
+    /// Does nothing if all the neurons are excluded or clamped.
     pub fn tick_one_random(&mut self, temperature: F, exclude: &[usize]) {
         let n = self.biases.len();
-        let limits = Range::<usize>::new(0, n);
+        // some unit is surely free if there are fewer exclusions than units,
+        // otherwise check before sampling forever
+        if exclude.len() + self.clamped_count >= n
+            && (0..n).all(|i| self.clamped[i] || exclude.contains(&i))
+        {
+            return;
+        }
+        let limits = Range::new(0, n);
         let mut rng = thread_rng();
         let mut idx = limits.ind_sample(&mut rng);
-        while exclude.contains(&idx) {
+        while self.clamped[idx] || exclude.contains(&idx) {
             idx = limits.ind_sample(&mut rng);
         }
-        let mut val = self.biases[idx];
-        for j in 0..n {
-            if idx!=j {
-                val = val + self.values[j] * self.coeffs[(idx,j)];
+        self.update_unit(idx, temperature);
+    }
+
+    /// Runs a simulated annealing: for each of the given temperatures, in
+    /// order, all neurons not in `exclude` are updated sequentially.
+    ///
+    /// If a `trace` is given, the temperature, the energy and the number of
+    /// neurons that changed value are recorded into it after each sweep.
+    pub fn anneal<I>(&mut self, temperatures: I, exclude: &[usize], mut trace: Option<&mut Trace<F>>)
+        where I: IntoIterator<Item = F>
+    {
+        for temperature in temperatures {
+            let flips = self.sweep(temperature, exclude);
+            if let Some(ref mut trace) = trace {
+                let energy = self.energy();
+                trace.push(temperature, energy, flips);
+            }
+        }
+    }
+
+    /// Runs `attempts` independent annealings from the current state (see
+    /// `anneal(..)`), and keeps the state of lowest energy seen during any of
+    /// them, after any sweep.
+    ///
+    /// Returns the trace of each attempt.
+    pub fn anneal_restarts(&mut self, attempts: usize, temperatures: &[F], exclude: &[usize])
+        -> Vec<Trace<F>>
+    {
+        let runs = (0..attempts).map(|_| self.clone().anneal_attempt(temperatures, exclude)).collect();
+        self.keep_best(runs)
+    }
+
+    // anneals this machine, returning the state of lowest energy seen (the
+    // initial one included) along with its energy, and the trace
+    fn anneal_attempt(&mut self, temperatures: &[F], exclude: &[usize]) -> (F, Vec<F>, Trace<F>) {
+        let mut trace = Trace::new();
+        let (mut best_energy, mut best_values) = (self.energy(), self.values.clone());
+        for &temperature in temperatures {
+            let flips = self.sweep(temperature, exclude);
+            let energy = self.energy();
+            trace.push(temperature, energy, flips);
+            if energy < best_energy {
+                best_energy = energy;
+                best_values.copy_from_slice(&self.values);
             }
         }
+        (best_energy, best_values, trace)
+    }
+
+    // sets the values of the best state of all runs, and returns the traces
+    fn keep_best(&mut self, runs: Vec<(F, Vec<F>, Trace<F>)>) -> Vec<Trace<F>> {
+        let mut best_energy = F::infinity();
+        let mut traces = Vec::with_capacity(runs.len());
+        for (energy, values, trace) in runs {
+            if energy < best_energy {
+                best_energy = energy;
+                self.values = values;
+            }
+            traces.push(trace);
+        }
+        traces
+    }
+
+    // updates all neurons not in `exclude` in order, returning the number
+    // of neurons whose value changed
+    fn sweep(&mut self, temperature: F, exclude: &[usize]) -> usize {
+        let mut flips = 0;
+        for i in 0..self.values.len() {
+            if exclude.contains(&i) || self.clamped[i] { continue; }
+            if self.update_unit(i, temperature) { flips += 1; }
+        }
+        flips
+    }
+
+    // samples a new value for neuron `i`, returning whether it changed
+    fn update_unit(&mut self, i: usize, temperature: F) -> bool {
+        let mut val = self.biases[i];
+        for &(j, w) in &self.neighbors[i] {
+            val = val + self.values[j] * w;
+        }
+        if let UnitType::Gaussian = self.types[i] {
+            let normal = Normal::new(val.to_f64().unwrap(), temperature.abs().sqrt().to_f64().unwrap());
+            self.values[i] = F::from(normal.ind_sample(&mut thread_rng())).unwrap();
+            return true;
+        }
         val = -val / temperature;
         if random::<F>() < (one::<F>() + val.exp()).recip() {
             val = one::<F>();
         } else {
             val = zero::<F>();
         };
-        self.values[idx] = val;
+        let changed = val != self.values[i];
+        self.values[i] = val;
+        changed
+    }
+}
+
+impl<F: Float + Rand + Send + Sync + 'static> BoltzmannMachine<F> {
+    /// Same as `anneal_restarts(..)`, but runs the attempts in parallel, each
+    /// in its own thread.
+    pub fn anneal_restarts_parallel(&mut self, attempts: usize, temperatures: &[F], exclude: &[usize])
+        -> Vec<Trace<F>>
+    {
+        let handles = (0..attempts).map(|_| {
+            let mut machine = self.clone();
+            let temperatures = temperatures.to_owned();
+            let exclude = exclude.to_owned();
+            thread::spawn(move || machine.anneal_attempt(&temperatures, &exclude))
+        }).collect::<Vec<_>>();
+        let runs = handles.into_iter().map(|h| h.join().expect("An annealing thread panicked.")).collect();
+        self.keep_best(runs)
+    }
+}
+
+/// A saved state of a `BoltzmannMachine`, see `BoltzmannMachine::snapshot()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot<F: Float> {
+    values: Vec<F>
+}
+
+/// A record of the evolution of a `BoltzmannMachine` during an annealing,
+/// see `BoltzmannMachine::anneal(..)`.
+///
+/// It can be exported as CSV to be plotted, for example to tune the
+/// temperature schedule.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Trace<F: Float> {
+    temperatures: Vec<F>,
+    energies: Vec<F>,
+    flips: Vec<usize>
+}
+
+impl<F: Float> Trace<F> {
+    /// Creates a new empty trace.
+    pub fn new() -> Trace<F> {
+        Trace {
+            temperatures: Vec::new(),
+            energies: Vec::new(),
+            flips: Vec::new()
+        }
+    }
+
+    /// Records a step.
+    pub fn push(&mut self, temperature: F, energy: F, flips: usize) {
+        self.temperatures.push(temperature);
+        self.energies.push(energy);
+        self.flips.push(flips);
+    }
+
+    /// The number of recorded steps.
+    pub fn len(&self) -> usize {
+        self.energies.len()
+    }
+
+    /// Whether no step was recorded.
+    pub fn is_empty(&self) -> bool {
+        self.energies.is_empty()
+    }
+
+    /// The temperature of each step.
+    pub fn temperatures(&self) -> &[F] {
+        &self.temperatures
+    }
+
+    /// The energy of the machine after each step.
+    pub fn energies(&self) -> &[F] {
+        &self.energies
+    }
+
+    /// The number of neurons that changed value during each step.
+    pub fn flips(&self) -> &[usize] {
+        &self.flips
+    }
+
+    /// Clears the trace, so that it can be reused for another run.
+    pub fn clear(&mut self) {
+        self.temperatures.clear();
+        self.energies.clear();
+        self.flips.clear();
+    }
+
+    /// Writes the trace as CSV, with a header line and the columns `step`,
+    /// `temperature`, `energy` and `flips`.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "step,temperature,energy,flips")?;
+        for i in 0..self.len() {
+            writeln!(writer, "{},{},{},{}", i, self.temperatures[i].to_f64().unwrap(),
+                     self.energies[i].to_f64().unwrap(), self.flips[i])?;
+        }
+        Ok(())
+    }
+}
+
+impl<F: Float> Default for Trace<F> {
+    fn default() -> Trace<F> {
+        Trace::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use SymmetricMatrix;
+
+    use super::{BoltzmannMachine, Trace, UnitType};
+
+    #[test]
+    fn energy_trace() {
+        // two units that tend to be active together
+        let mut weights = SymmetricMatrix::zeros(2);
+        weights[(0, 1)] = 1.0f64;
+        let mut machine = BoltzmannMachine::with_biases(weights, vec![2.0, -0.5]);
+        assert_eq!(machine.energy(), -2.5);
+        machine.values_mut()[0] = 0.0;
+        assert_eq!(machine.energy(), 0.5);
+        let mut trace = Trace::new();
+        machine.anneal(vec![1.0, 0.5, 0.01, 0.01, 0.01], &[], Some(&mut trace));
+        assert_eq!(trace.len(), 5);
+        assert_eq!(machine.values(), [1.0, 1.0]);
+        assert_eq!(trace.energies()[4], -2.5);
+        assert_eq!(trace.flips()[4], 0);
+
+        let mut csv = Vec::new();
+        trace.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(csv.lines().next(), Some("step,temperature,energy,flips"));
+        assert_eq!(csv.lines().last(), Some("4,0.01,-2.5,0"));
+    }
+
+    #[test]
+    fn restarts() {
+        // a frustrated triangle with a preferred solution
+        let mut weights = SymmetricMatrix::zeros(3);
+        weights[(0, 1)] = -3.0f64;
+        weights[(1, 2)] = -3.0;
+        weights[(0, 2)] = -3.0;
+        let mut machine = BoltzmannMachine::with_biases(weights, vec![1.0, 1.0, 2.0]);
+        let temperatures = [2.0, 1.0, 0.5, 0.1, 0.01, 0.01];
+        let traces = machine.anneal_restarts(8, &temperatures, &[]);
+        assert_eq!(traces.len(), 8);
+        // the best state seen during any sweep is kept
+        let best = traces.iter().flat_map(|t| t.energies().iter().cloned()).fold(f64::INFINITY, f64::min);
+        assert_eq!(machine.energy(), best);
+        let traces = machine.anneal_restarts_parallel(4, &temperatures, &[2]);
+        assert_eq!(traces.len(), 4);
+        assert!(traces.iter().all(|t| t.len() == 6));
+    }
+
+    #[test]
+    fn snapshot() {
+        let mut machine = BoltzmannMachine::new(SymmetricMatrix::<f32>::zeros(3));
+        machine.values_mut()[1] = 0.0;
+        let snapshot = machine.snapshot();
+        machine.values_mut()[0] = 0.0;
+        machine.values_mut()[1] = 1.0;
+        machine.restore(&snapshot);
+        assert_eq!(machine.values(), [1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn groups() {
+        let mut weights = SymmetricMatrix::zeros(4);
+        weights[(0, 2)] = -10.0f64;
+        weights[(1, 3)] = -10.0;
+        let mut machine = BoltzmannMachine::with_biases(weights, vec![5.0; 4]);
+        machine.define_group("a", vec![0, 1]);
+        machine.define_group("b", vec![2, 3]);
+        assert_eq!(machine.group("b"), Some(&[2, 3][..]));
+        assert_eq!(machine.decode_group("a"), None);
+        machine.clamp_group("a", &[0.0, 1.0]);
+        machine.anneal(vec![0.01; 3], &[], None);
+        assert_eq!(machine.group_values("a"), [0.0, 1.0]);
+        // no free neuron left to update
+        machine.tick_one_random(1.0, &[2, 3]);
+        assert_eq!(machine.group_values("a"), [0.0, 1.0]);
+        assert_eq!(machine.decode_group("a"), Some(1));
+        assert_eq!(machine.decode_group("b"), Some(0));
+    }
+
+    #[test]
+    fn gaussian_units() {
+        // a gaussian unit driven by a binary one
+        let mut weights = SymmetricMatrix::zeros(2);
+        weights[(0, 1)] = 3.0f64;
+        let mut machine = BoltzmannMachine::with_biases(weights, vec![1.0, 10.0]);
+        machine.set_unit_type(0, UnitType::Gaussian);
+        assert_eq!(machine.unit_type(1), UnitType::Binary);
+        machine.values_mut()[0] = 4.0;
+        assert_eq!(machine.energy(), -12.0 - 4.0 - 10.0 + 8.0);
+        let mut sum = 0.0;
+        for _ in 0..1000 {
+            machine.tick_all_sequential(0.01, &[1]);
+            sum += machine.values()[0];
+        }
+        assert!((sum / 1000.0 - 4.0).abs() < 0.05);
     }
 }
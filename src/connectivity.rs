@@ -0,0 +1,128 @@
+//! Generators of structured connectivity.
+//!
+//! These functions build symmetric weight matrices, as used by
+//! `BoltzmannMachine`, a null coefficient meaning that two units are not
+//! connected. The diagonal is always left at `0.0`.
+
+use num::{Float, zero};
+use rand::{Rng, thread_rng};
+
+use SymmetricMatrix;
+
+/// Connects the units of a `width x height` grid according to their
+/// distance.
+///
+/// Unit `(x, y)` of the grid is unit `y * width + x` of the matrix. Two units
+/// at a distance `d` of at most `radius` are connected with a weight of
+/// `exp(-d^2 / (2 * sigma^2))`.
+pub fn gaussian_grid<F: Float>(width: usize, height: usize, sigma: F, radius: F) -> SymmetricMatrix<F> {
+    assert!(sigma > zero(), "The width of the gaussian must be positive.");
+    let n = width * height;
+    let mut matrix = SymmetricMatrix::zeros(n);
+    let two = F::from(2).unwrap();
+    for i in 0..n {
+        for j in (i+1)..n {
+            let dx = F::from(i % width).unwrap() - F::from(j % width).unwrap();
+            let dy = F::from(i / width).unwrap() - F::from(j / width).unwrap();
+            let squared = dx * dx + dy * dy;
+            if squared <= radius * radius {
+                matrix[(i, j)] = (-squared / (two * sigma * sigma)).exp();
+            }
+        }
+    }
+    matrix
+}
+
+/// Generates a Watts-Strogatz small-world network of `n` units.
+///
+/// The units are first placed on a ring, each connected to its `neighbors`
+/// closest units on each side. Then each of these connections is rewired
+/// with probability `rewiring` to a random unit, creating shortcuts across
+/// the ring. All connections have the weight `weight`.
+pub fn small_world<F: Float>(n: usize, neighbors: usize, rewiring: F, weight: F) -> SymmetricMatrix<F> {
+    assert!(n > 2 * neighbors, "A small-world network needs more than 2 * neighbors units.");
+    assert!(rewiring >= zero() && rewiring <= F::one(), "The rewiring probability must be in [0, 1].");
+    let mut matrix = SymmetricMatrix::zeros(n);
+    for i in 0..n {
+        for d in 1..(neighbors+1) {
+            matrix[(i, (i + d) % n)] = weight;
+        }
+    }
+    let mut rng = thread_rng();
+    let rewiring = rewiring.to_f64().unwrap();
+    for d in 1..(neighbors+1) {
+        for i in 0..n {
+            if rng.gen::<f64>() >= rewiring {
+                continue;
+            }
+            let candidates = (0..n).filter(|&j| j != i && matrix[(i, j)] == zero()).collect::<Vec<_>>();
+            if let Some(&j) = rng.choose(&candidates) {
+                matrix[(i, (i + d) % n)] = zero();
+                matrix[(i, j)] = weight;
+            }
+        }
+    }
+    matrix
+}
+
+/// Generates a random sparse network of `n` units, each connected to
+/// `degree` others with the weight `weight`.
+///
+/// Connections being symmetric, an exact degree for all units is not always
+/// possible: every unit has at most `degree` connections, and all but a few
+/// of them exactly `degree`.
+pub fn random_sparse<F: Float>(n: usize, degree: usize, weight: F) -> SymmetricMatrix<F> {
+    assert!(degree < n, "The degree must be lower than the number of units.");
+    let mut matrix = SymmetricMatrix::zeros(n);
+    let mut degrees = vec![0; n];
+    let mut rng = thread_rng();
+    for i in 0..n {
+        let mut candidates = (0..n).filter(|&j| {
+            j != i && degrees[j] < degree && matrix[(i, j)] == zero()
+        }).collect::<Vec<_>>();
+        rng.shuffle(&mut candidates);
+        let missing = degree - degrees[i];
+        for &j in candidates.iter().take(missing) {
+            matrix[(i, j)] = weight;
+            degrees[i] += 1;
+            degrees[j] += 1;
+        }
+    }
+    matrix
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{gaussian_grid, random_sparse, small_world};
+
+    #[test]
+    fn grid() {
+        let matrix = gaussian_grid(3, 3, 1.0f64, 1.5);
+        assert_eq!(matrix[(0, 1)], (-0.5f64).exp());
+        assert_eq!(matrix[(0, 4)], (-1.0f64).exp());
+        assert_eq!(matrix[(0, 2)], 0.0);
+        assert_eq!(matrix[(4, 4)], 0.0);
+        assert_eq!(matrix.nonzero_neighbors(4).len(), 8);
+        assert_eq!(matrix.nonzero_neighbors(8).len(), 3);
+    }
+
+    #[test]
+    fn rewiring() {
+        let ring = small_world(20, 2, 0.0f64, 1.0);
+        assert!((0..20).all(|i| ring.nonzero_neighbors(i).len() == 4));
+        assert_eq!(ring[(0, 19)], 1.0);
+        assert_eq!(ring[(0, 3)], 0.0);
+        let rewired = small_world(20, 2, 1.0f64, 1.0);
+        assert_eq!(rewired.iter().filter(|&(i, j, v)| i != j && v != 0.0).count(), 40);
+        assert!(rewired != ring);
+    }
+
+    #[test]
+    fn sparse() {
+        let matrix = random_sparse(100, 4, 0.5f64);
+        let degrees = (0..100).map(|i| matrix.nonzero_neighbors(i).len()).collect::<Vec<_>>();
+        assert!(degrees.iter().all(|&d| d <= 4));
+        assert!(degrees.iter().filter(|&&d| d == 4).count() >= 95);
+        assert!(matrix.iter().all(|(_, _, v)| v == 0.0 || v == 0.5));
+    }
+}
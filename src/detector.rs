@@ -0,0 +1,249 @@
+//! Anomaly detection.
+//!
+//! The detectors of this module learn what normal samples look like from a
+//! set of clean samples, and then score new samples one at a time, which
+//! makes them usable on streams of data. They share the `Detector` trait.
+
+use std::cmp::Ordering;
+
+use num::{Float, one, zero};
+use rand::{Rng, thread_rng};
+
+use Compute;
+
+/// A trait for anomaly detectors.
+pub trait Detector<F: Float> {
+    /// The anomaly score of `input`, the highest being the most anomalous.
+    fn score(&self, input: &[F]) -> F;
+
+    /// The score above which samples are considered anomalous.
+    fn threshold(&self) -> F;
+
+    /// Whether `input` is considered anomalous.
+    fn is_anomaly(&self, input: &[F]) -> bool {
+        self.score(input) > self.threshold()
+    }
+}
+
+// the value below which a fraction `q` of the sorted values are
+fn quantile<F: Float>(values: &mut [F], q: F) -> F {
+    if values.is_empty() {
+        return zero();
+    }
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let last = values.len() - 1;
+    let i = (q * F::from(last).unwrap()).ceil().to_usize().unwrap_or(last).min(last);
+    values[i]
+}
+
+/*
+ * Autoencoder
+ */
+
+/// Detects anomalies as samples poorly reconstructed by an autoencoder.
+///
+/// The autoencoder is any network trained to reproduce its inputs on clean
+/// data, and the score of a sample is the mean squared error between it and
+/// its reconstruction.
+pub struct AutoencoderDetector<F: Float, N: Compute<F>> {
+    network: N,
+    threshold: F
+}
+
+impl<F: Float, N: Compute<F>> AutoencoderDetector<F, N> {
+    /// Creates a detector using given autoencoder, with a threshold
+    /// calibrated on given clean samples: the fraction `quantile` of them
+    /// (like `0.99`) has a score below it.
+    pub fn fit(network: N, clean: &[Vec<F>], quantile: F) -> AutoencoderDetector<F, N> {
+        let mut detector = AutoencoderDetector::with_threshold(network, zero());
+        let mut scores = clean.iter().map(|x| detector.score(x)).collect::<Vec<_>>();
+        detector.threshold = self::quantile(&mut scores, quantile);
+        detector
+    }
+
+    /// Creates a detector using given autoencoder and threshold.
+    pub fn with_threshold(network: N, threshold: F) -> AutoencoderDetector<F, N> {
+        AutoencoderDetector {
+            network,
+            threshold
+        }
+    }
+
+    /// The autoencoder of this detector.
+    pub fn network(&self) -> &N {
+        &self.network
+    }
+}
+
+impl<F: Float, N: Compute<F>> Detector<F> for AutoencoderDetector<F, N> {
+    fn score(&self, input: &[F]) -> F {
+        let output = self.network.compute(input);
+        let n = output.len().max(1);
+        output.iter().enumerate().fold(zero::<F>(), |s, (i, &o)| {
+            let e = o - input.get(i).cloned().unwrap_or(zero());
+            s + e * e
+        }) / F::from(n).unwrap()
+    }
+
+    fn threshold(&self) -> F {
+        self.threshold
+    }
+}
+
+/*
+ * Isolation forest
+ */
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node<F> {
+    Leaf(usize),
+    Split(usize, F, Box<Node<F>>, Box<Node<F>>)
+}
+
+fn value_at<F: Float>(sample: &[F], feature: usize) -> F {
+    sample.get(feature).cloned().unwrap_or(zero())
+}
+
+// the average path length of an unsuccessful search in a binary search tree
+// of `n` elements, used to normalize path lengths
+fn average_path<F: Float>(n: usize) -> F {
+    if n <= 1 {
+        return zero();
+    }
+    let n = F::from(n).unwrap();
+    let harmonic = (n - one()).ln() + F::from(0.5772156649).unwrap();
+    F::from(2).unwrap() * harmonic - F::from(2).unwrap() * (n - one()) / n
+}
+
+impl<F: Float> Node<F> {
+    fn grow<R: Rng>(rng: &mut R, samples: Vec<&[F]>, features: usize, depth: usize) -> Node<F> {
+        if depth == 0 || samples.len() <= 1 || features == 0 {
+            return Node::Leaf(samples.len());
+        }
+        let feature = rng.gen_range(0, features);
+        let (min, max) = samples.iter().fold((F::infinity(), F::neg_infinity()), |(l, u), x| {
+            let v = value_at(x, feature);
+            (l.min(v), u.max(v))
+        });
+        if min.partial_cmp(&max) != Some(Ordering::Less) {
+            return Node::Leaf(samples.len());
+        }
+        let value = min + (max - min) * F::from(rng.gen::<f64>()).unwrap();
+        let (left, right) = samples.into_iter().partition(|x| value_at(x, feature) < value);
+        Node::Split(feature,
+                    value,
+                    Box::new(Node::grow(rng, left, features, depth - 1)),
+                    Box::new(Node::grow(rng, right, features, depth - 1)))
+    }
+
+    fn path_length(&self, input: &[F]) -> F {
+        match *self {
+            Node::Leaf(size) => average_path(size),
+            Node::Split(feature, value, ref left, ref right) => {
+                let next = if value_at(input, feature) < value { left } else { right };
+                one::<F>() + next.path_length(input)
+            }
+        }
+    }
+}
+
+/// Detects anomalies as samples that are easy to isolate by random splits.
+///
+/// This is a non-neural baseline: each tree of the forest recursively splits
+/// a random subsample of the clean samples on a random feature at a random
+/// value, and anomalies end up alone after fewer splits than normal samples.
+/// The score is in `[0, 1]`, values well above `0.5` denoting anomalies.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IsolationForest<F: Float> {
+    trees: Vec<Node<F>>,
+    sample_size: usize,
+    threshold: F
+}
+
+impl<F: Float> IsolationForest<F> {
+    /// Grows a forest of `trees` trees, each on `sample_size` samples drawn
+    /// from `clean` (`256` is the usual choice), with a threshold calibrated
+    /// on these samples like `AutoencoderDetector::fit(..)`.
+    pub fn fit(clean: &[Vec<F>], trees: usize, sample_size: usize, quantile: F) -> IsolationForest<F> {
+        assert!(!clean.is_empty(), "Cannot fit an isolation forest without samples.");
+        let sample_size = sample_size.max(1).min(clean.len());
+        let features = clean.iter().map(|x| x.len()).max().unwrap_or(0);
+        let depth = (sample_size as f64).log2().ceil() as usize;
+        let mut rng = thread_rng();
+        let trees = (0..trees).map(|_| {
+            let mut indices = (0..clean.len()).collect::<Vec<_>>();
+            rng.shuffle(&mut indices);
+            let samples = indices[..sample_size].iter().map(|&i| &clean[i][..]).collect();
+            Node::grow(&mut rng, samples, features, depth)
+        }).collect();
+        let mut forest = IsolationForest {
+            trees,
+            sample_size,
+            threshold: zero()
+        };
+        let mut scores = clean.iter().map(|x| forest.score(x)).collect::<Vec<_>>();
+        forest.threshold = self::quantile(&mut scores, quantile);
+        forest
+    }
+
+    /// Sets the threshold of this detector.
+    pub fn set_threshold(&mut self, threshold: F) {
+        self.threshold = threshold;
+    }
+
+    /// The number of trees of the forest.
+    pub fn trees(&self) -> usize {
+        self.trees.len()
+    }
+}
+
+impl<F: Float> Detector<F> for IsolationForest<F> {
+    fn score(&self, input: &[F]) -> F {
+        if self.trees.is_empty() {
+            return zero();
+        }
+        let total = self.trees.iter().fold(zero::<F>(), |s, t| s + t.path_length(input));
+        let mean = total / F::from(self.trees.len()).unwrap();
+        let normalization = average_path::<F>(self.sample_size).max(one());
+        F::from(2).unwrap().powf(-mean / normalization)
+    }
+
+    fn threshold(&self) -> F {
+        self.threshold
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {FeedforwardLayer, Matrix};
+    use activations::Activation;
+
+    use super::{Detector, AutoencoderDetector, IsolationForest};
+
+    #[test]
+    fn autoencoder() {
+        // projects onto the diagonal: points with x0 == x1 are reconstructed
+        let network = FeedforwardLayer::from_matrices(
+            Matrix::from_vec(2, 2, vec![0.5f64, 0.5, 0.5, 0.5]), vec![0.0, 0.0], Activation::Identity);
+        let clean = (0..100).map(|i| {
+            let x = i as f64 / 10.0;
+            vec![x, x + if i % 2 == 0 { 0.1 } else { -0.1 }]
+        }).collect::<Vec<_>>();
+        let detector = AutoencoderDetector::fit(network, &clean, 0.99);
+        assert!((detector.threshold() - 0.0025).abs() < 1e-9);
+        assert!(!detector.is_anomaly(&[3.0, 3.05]));
+        assert!(detector.is_anomaly(&[3.0, 1.0]));
+    }
+
+    #[test]
+    fn isolation_forest() {
+        let clean = (0..400).map(|i| vec![(i % 20) as f64 * 0.05, (i / 20) as f64 * 0.05]).collect::<Vec<_>>();
+        let forest = IsolationForest::fit(&clean, 100, 128, 0.9);
+        assert_eq!(forest.trees(), 100);
+        let normal = forest.score(&[0.5, 0.5]);
+        let outlier = forest.score(&[5.0, -4.0]);
+        assert!(outlier > 0.6 && outlier > normal + 0.1);
+        assert!(forest.is_anomaly(&[5.0, -4.0]));
+        assert!(!forest.is_anomaly(&[0.5, 0.5]));
+    }
+}
@@ -0,0 +1,173 @@
+//! Binary encodings of variables, for Boltzmann machines.
+//!
+//! The units of a `BoltzmannMachine` are binary, so problems involving
+//! integer or categorical variables need to encode each of them as a group
+//! of units. With a one-hot encoding, the constraint that exactly one unit
+//! of the group is active is itself expressed by couplings, which the
+//! helpers of this module generate.
+
+use std::ops::Range;
+
+use num::{Float, one, zero};
+
+use SymmetricMatrix;
+
+/// A group of consecutive units one-hot encoding a categorical variable:
+/// value `k` is represented by activating only the `k`-th unit of the group.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OneHot {
+    offset: usize,
+    size: usize
+}
+
+impl OneHot {
+    /// Creates a group of `size` units, starting at unit `offset`.
+    pub fn new(offset: usize, size: usize) -> OneHot {
+        OneHot {
+            offset,
+            size
+        }
+    }
+
+    /// The indices of the units of this group.
+    pub fn units(&self) -> Range<usize> {
+        self.offset..self.offset + self.size
+    }
+
+    /// The index of the unit representing given value.
+    pub fn unit(&self, value: usize) -> usize {
+        assert!(value < self.size, "The value must be smaller than the group size.");
+        self.offset + value
+    }
+
+    /// Writes the encoding of `value` in the units of this group.
+    pub fn encode<F: Float>(&self, value: usize, units: &mut [F]) {
+        for k in 0..self.size {
+            units[self.offset + k] = if k == value { one() } else { zero() };
+        }
+    }
+
+    /// Reads the value encoded in the units of this group.
+    ///
+    /// Returns `None` if no unit or several units are active, units being
+    /// active when above `0.5`.
+    pub fn decode<F: Float>(&self, units: &[F]) -> Option<usize> {
+        let half = F::from(0.5).unwrap();
+        let mut active = (0..self.size).filter(|&k| units[self.offset + k] > half);
+        match (active.next(), active.next()) {
+            (Some(k), None) => Some(k),
+            _ => None
+        }
+    }
+
+    /// Sets the couplings between all the units of this group to `-penalty`,
+    /// so that at most one of them tends to be active.
+    ///
+    /// Combined with positive biases on the units, this makes exactly one
+    /// of them active.
+    pub fn constrain<F: Float>(&self, weights: &mut SymmetricMatrix<F>, penalty: F) {
+        for u in self.units() {
+            for v in self.offset..u {
+                weights[(u, v)] = -penalty;
+            }
+        }
+    }
+
+    /// Sets the couplings between the units representing the same value in
+    /// this group and `other` to `-penalty`, so that both variables tend to
+    /// take different values.
+    pub fn exclude_equal<F: Float>(&self, other: &OneHot, weights: &mut SymmetricMatrix<F>, penalty: F) {
+        for k in 0..self.size.min(other.size) {
+            weights[(self.offset + k, other.offset + k)] = -penalty;
+        }
+    }
+}
+
+/// Lays out one-hot groups of given sizes on consecutive units, the first
+/// one starting at unit `0`.
+pub fn one_hot_groups(sizes: &[usize]) -> Vec<OneHot> {
+    let mut offset = 0;
+    sizes.iter().map(|&size| {
+        let group = OneHot::new(offset, size);
+        offset += size;
+        group
+    }).collect()
+}
+
+/// A group of consecutive units encoding an integer variable in base 2, the
+/// first unit being the least significant bit.
+///
+/// Unlike `OneHot`, every combination of units is a valid value, and no
+/// coupling is needed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Binary {
+    offset: usize,
+    bits: usize
+}
+
+impl Binary {
+    /// Creates a group of `bits` units, starting at unit `offset`.
+    pub fn new(offset: usize, bits: usize) -> Binary {
+        Binary {
+            offset,
+            bits
+        }
+    }
+
+    /// The indices of the units of this group.
+    pub fn units(&self) -> Range<usize> {
+        self.offset..self.offset + self.bits
+    }
+
+    /// Writes the encoding of `value` in the units of this group.
+    pub fn encode<F: Float>(&self, value: usize, units: &mut [F]) {
+        assert!(self.bits >= 64 || value >> self.bits == 0, "The value does not fit in the group.");
+        for b in 0..self.bits {
+            units[self.offset + b] = if (value >> b) & 1 == 1 { one() } else { zero() };
+        }
+    }
+
+    /// Reads the value encoded in the units of this group, units being
+    /// active when above `0.5`.
+    pub fn decode<F: Float>(&self, units: &[F]) -> usize {
+        let half = F::from(0.5).unwrap();
+        (0..self.bits).filter(|&b| units[self.offset + b] > half).fold(0, |v, b| v | 1 << b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use SymmetricMatrix;
+
+    use super::{Binary, one_hot_groups};
+
+    #[test]
+    fn one_hot() {
+        let groups = one_hot_groups(&[3, 2]);
+        assert_eq!(groups[1].units(), 3..5);
+        let mut units = vec![0.0f32; 5];
+        groups[0].encode(2, &mut units);
+        groups[1].encode(0, &mut units);
+        assert_eq!(units, [0.0, 0.0, 1.0, 1.0, 0.0]);
+        assert_eq!(groups[0].decode(&units), Some(2));
+        units[4] = 1.0;
+        assert_eq!(groups[1].decode(&units), None);
+
+        let mut weights = SymmetricMatrix::zeros(5);
+        groups[0].constrain(&mut weights, 10.0f32);
+        groups[0].exclude_equal(&groups[1], &mut weights, 5.0);
+        assert_eq!(weights[(0, 2)], -10.0);
+        assert_eq!(weights[(1, 4)], -5.0);
+        assert_eq!(weights[(0, 0)], 0.0);
+        assert_eq!(weights[(2, 3)], 0.0);
+    }
+
+    #[test]
+    fn binary() {
+        let group = Binary::new(1, 4);
+        let mut units = vec![0.0f64; 5];
+        group.encode(11, &mut units);
+        assert_eq!(units, [0.0, 1.0, 1.0, 0.0, 1.0]);
+        assert_eq!(group.decode(&units), 11);
+    }
+}
@@ -0,0 +1,148 @@
+//! Symbolic export of networks.
+//!
+//! Small trained networks can be rendered as human-readable mathematical
+//! expressions, one per output, for example to audit a tiny control policy
+//! before embedding it somewhere.
+//!
+//! The rendered expressions use the names of the built-in activation
+//! functions (like `sigmoid(..)` or `softplus(..)`), and thus only networks
+//! using `Activation` as activation functions can be exported.
+
+use num::{Float, zero};
+
+use Compute;
+use activations::Activation;
+
+/// A trait for networks that can be rendered as symbolic expressions.
+pub trait Symbolic<F: Float> {
+    /// Renders the outputs of this network as expressions of the given
+    /// input expressions.
+    ///
+    /// Missing inputs are rendered as `0`.
+    fn symbolic(&self, inputs: &[String]) -> Vec<String>;
+}
+
+/// A trait for activation functions that can be rendered as symbolic
+/// expressions.
+pub trait SymbolicActivation<F: Float> {
+    /// Renders the activated values as expressions of the given
+    /// pre-activation expressions.
+    fn symbolic(&self, x: &[String]) -> Vec<String>;
+}
+
+/// Renders the outputs of given network as expressions of its inputs,
+/// named `x0`, `x1`, etc.
+pub fn to_expressions<F, N>(network: &N) -> Vec<String>
+    where F: Float,
+          N: Symbolic<F> + Compute<F>
+{
+    let inputs = (0..network.input_size()).map(|i| format!("x{}", i)).collect::<Vec<_>>();
+    network.symbolic(&inputs)
+}
+
+/// Renders a number for use in an expression.
+pub fn number<F: Float>(x: F) -> String {
+    format!("{}", x.to_f64().unwrap())
+}
+
+/// Wraps an expression in parentheses, unless it is already atomic (a
+/// number, a name or a function call).
+pub fn atom(x: &str) -> String {
+    let mut depth = 0;
+    for c in x.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ' ' if depth == 0 => return format!("({})", x),
+            _ => {}
+        }
+    }
+    x.to_owned()
+}
+
+/// Renders the linear combination `sum_i(coeffs[i] * inputs[i]) + bias`,
+/// omitting null coefficients.
+pub fn linear_combination<F: Float>(coeffs: &[F], inputs: &[String], bias: F) -> String {
+    let mut expr = String::new();
+    for (&c, x) in coeffs.iter().zip(inputs) {
+        if c == zero() { continue; }
+        if expr.is_empty() {
+            expr = format!("{}*{}", number(c), atom(x));
+        } else if c < zero() {
+            expr = format!("{} - {}*{}", expr, number(-c), atom(x));
+        } else {
+            expr = format!("{} + {}*{}", expr, number(c), atom(x));
+        }
+    }
+    if expr.is_empty() {
+        number(bias)
+    } else if bias < zero() {
+        format!("{} - {}", expr, number(-bias))
+    } else if bias > zero() {
+        format!("{} + {}", expr, number(bias))
+    } else {
+        expr
+    }
+}
+
+impl<F: Float> SymbolicActivation<F> for Activation<F> {
+    fn symbolic(&self, x: &[String]) -> Vec<String> {
+        if let Activation::Softmax = *self {
+            let denominator = x.iter().map(|x| format!("exp({})", x)).collect::<Vec<_>>().join(" + ");
+            return x.iter().map(|x| format!("exp({}) / ({})", x, denominator)).collect();
+        }
+        x.iter().map(|x| match *self {
+            Activation::Identity => x.clone(),
+            Activation::Relu => format!("relu({})", x),
+            Activation::Sigmoid => format!("sigmoid({})", x),
+            Activation::SigmoidWithSteepness(k) => format!("sigmoid({} * ({}))", number(k), x),
+            Activation::Step => format!("step({})", x),
+            Activation::StepWithThreshold(t) => format!("step({} - {})", x, number(t)),
+            Activation::Gaussian => format!("exp(-({})^2)", x),
+            Activation::Softplus => format!("softplus({})", x),
+            Activation::Softsign => format!("softsign({})", x),
+            Activation::Swish(beta) => format!("({0}) * sigmoid({1} * ({0}))", x, number(beta)),
+            Activation::Gelu => format!("gelu({})", x),
+            Activation::Softmax => unreachable!(),
+            Activation::Custom(..) => format!("f({})", x)
+        }).collect()
+    }
+}
+
+impl<F: Float, A: SymbolicActivation<F>> SymbolicActivation<F> for Vec<A> {
+    fn symbolic(&self, x: &[String]) -> Vec<String> {
+        x.iter().enumerate().map(|(i, x)| match self.get(i) {
+            Some(a) => a.symbolic(::std::slice::from_ref(x)).remove(0),
+            None => x.clone()
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use activations::Activation;
+    use util::{Chain, Identity};
+    use FeedforwardLayer;
+
+    use super::{to_expressions, linear_combination};
+
+    #[test]
+    fn linear() {
+        let names = ["a".to_owned(), "b".to_owned(), "c".to_owned()];
+        assert_eq!(linear_combination(&[1.5f32, 0.0, -2.0], &names, 0.5), "1.5*a - 2*c + 0.5");
+        assert_eq!(linear_combination(&[0.0f32, 0.0, 0.0], &names, -1.0), "-1");
+    }
+
+    #[test]
+    fn layers() {
+        let mut acc = 0.0;
+        let layer = FeedforwardLayer::new_from(2, 1, Activation::Sigmoid, || { acc += 1.0; acc });
+        assert_eq!(to_expressions(&layer), ["sigmoid(1*x0 + 2*x1 + 3)"]);
+
+        let chain = Chain::new(
+            FeedforwardLayer::new_from(1, 2, vec![Activation::Identity, Activation::Softplus], || 1.0f32),
+            Chain::new(Identity::new(2), FeedforwardLayer::new_from(2, 1, Activation::Step, || -1.0))
+        );
+        assert_eq!(to_expressions(&chain), ["step(-1*(1*x0 + 1) - 1*softplus(1*x0 + 1) - 1)"]);
+    }
+}
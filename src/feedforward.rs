@@ -4,9 +4,14 @@ use std::cmp::min;
 
 use num::{Float, zero};
 
-use {Compute, BackpropTrain, SupervisedTrain};
-use activations::ActivationFunction;
-use training::{PerceptronRule, GradientDescent};
+use {Compute, Differentiable, BackpropTrain, SupervisedTrain, UnsupervisedTrain};
+use activations::VectorActivation;
+use export::{Symbolic, SymbolicActivation, linear_combination};
+use init::Initializer;
+use linalg::Matrix;
+use robustness::{IntervalCompute, IntervalActivation};
+use training::{PerceptronRule, DeltaRule, RewardHebbian, GradientDescent, Momentum, Adam, Adagrad, Adadelta, Regularized};
+use training::{GradientMethod, LearningRate, OptimizerState, EligibilityTraces};
 
 /// A feedforward layer
 ///
@@ -23,30 +28,62 @@ use training::{PerceptronRule, GradientDescent};
 /// ```
 ///
 /// The training of this layer consists on fitting the values of `W` and `B`.
-pub struct FeedforwardLayer<F: Float, V: Fn(F) -> F, D: Fn(F) -> F> {
-    inputs: usize,
-    coeffs: Vec<F>,
+///
+/// The activation function can be any `VectorActivation`, which includes
+/// all the `ActivationFunction` of the `activations` module, applied
+/// independently on each output, as well as functions like `softmax()`
+/// that operate on the whole output vector. Using an `Activation` makes
+/// the layer clonable and comparable. Two layers are equal when they have
+/// the same parameters and activation, whatever the state their training
+/// methods keep between steps.
+///
+/// Each output neuron can also have its own activation function, by
+/// providing a `Vec` of activation functions (typically of `Activation`).
+#[derive(Clone, Debug)]
+pub struct FeedforwardLayer<F: Float, A: VectorActivation<F>> {
+    coeffs: Matrix<F>,
     biases: Vec<F>,
-    activation: ActivationFunction<F, V, D>
+    trainable_biases: bool,
+    activation: A,
+    weights_state: OptimizerState<F>,
+    biases_state: OptimizerState<F>,
+    weights_traces: EligibilityTraces<F>,
+    biases_traces: EligibilityTraces<F>
+}
+
+impl<F, A> PartialEq for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F> + PartialEq
+{
+    fn eq(&self, other: &FeedforwardLayer<F, A>) -> bool {
+        // the optimizer states and eligibility traces are left out
+        self.coeffs == other.coeffs
+            && self.biases == other.biases
+            && self.trainable_biases == other.trainable_biases
+            && self.activation == other.activation
+    }
 }
 
-impl<F, V, D> FeedforwardLayer<F, V, D>
+impl<F, A> FeedforwardLayer<F, A>
     where F: Float,
-          V: Fn(F) -> F,
-          D: Fn(F) -> F
+          A: VectorActivation<F>
 {
     /// Creates a new linear feedforward layer with all its weights set
     /// to 0 and its biases set to 0
     pub fn new(inputs: usize,
                outputs: usize,
-               activation: ActivationFunction<F, V, D>)
-        -> FeedforwardLayer<F, V, D>
+               activation: A)
+        -> FeedforwardLayer<F, A>
     {
         FeedforwardLayer {
-            inputs: inputs,
-            coeffs: vec![zero(); inputs*outputs],
+            coeffs: Matrix::zeros(outputs, inputs),
             biases: vec![zero(); outputs],
-            activation: activation
+            trainable_biases: true,
+            activation,
+            weights_state: OptimizerState::new(),
+            biases_state: OptimizerState::new(),
+            weights_traces: EligibilityTraces::new(),
+            biases_traces: EligibilityTraces::new()
         }
     }
 
@@ -54,42 +91,177 @@ impl<F, V, D> FeedforwardLayer<F, V, D>
     /// generated by provided closure (for example a random number generator).
     pub fn new_from<G>(inputs: usize,
                        outputs: usize,
-                       activation: ActivationFunction<F, V, D>,
+                       activation: A,
                        mut generator: G)
-        -> FeedforwardLayer<F, V, D>
+        -> FeedforwardLayer<F, A>
         where G: FnMut() -> F
     {
         FeedforwardLayer {
-            inputs: inputs,
-            coeffs: (0..inputs*outputs).map(|_| generator()).collect(),
+            coeffs: Matrix::from_vec(outputs, inputs,
+                                     (0..inputs*outputs).map(|_| generator()).collect()),
             biases: (0..outputs).map(|_| generator()).collect(),
-            activation: activation
+            trainable_biases: true,
+            activation,
+            weights_state: OptimizerState::new(),
+            biases_state: OptimizerState::new(),
+            weights_traces: EligibilityTraces::new(),
+            biases_traces: EligibilityTraces::new()
         }
     }
-}
 
-impl<F, V, D> Compute<F> for FeedforwardLayer<F, V, D>
-    where F: Float,
-          V: Fn(F) -> F,
-          D: Fn(F) -> F
-{
-    fn compute(&self, input: &[F]) -> Vec<F> {
-        let mut out = self.biases.clone();
-        for j in 0..self.biases.len() {
-            for i in 0..min(self.inputs, input.len()) {
-                out[j] = out[j] + self.coeffs[j*self.inputs + i] * input[i]
-            }
+    /// Creates a new linear feedforward layer with all its weights and biases
+    /// drawn uniformly at random in the range `[low, high)`.
+    ///
+    /// Panics if `low` is not lower than `high`.
+    pub fn new_random(inputs: usize,
+                      outputs: usize,
+                      activation: A,
+                      low: F,
+                      high: F)
+        -> FeedforwardLayer<F, A>
+    {
+        assert!(low < high, "The lower bound of the range must be lower than its upper bound.");
+        let generator = Initializer::Uniform(low, high).generator(inputs, outputs);
+        FeedforwardLayer::new_from(inputs, outputs, activation, generator)
+    }
+
+    /// Creates a new linear feedforward layer with all its weights and biases
+    /// drawn at random from a normal distribution centered on `0.0` with
+    /// given standard deviation.
+    pub fn new_normal(inputs: usize,
+                      outputs: usize,
+                      activation: A,
+                      std_dev: F)
+        -> FeedforwardLayer<F, A>
+    {
+        let generator = Initializer::Normal(std_dev).generator(inputs, outputs);
+        FeedforwardLayer::new_from(inputs, outputs, activation, generator)
+    }
+
+    /// Creates a new linear feedforward layer with its weights drawn according
+    /// to given initialization scheme, and its biases set to 0.
+    pub fn new_init(inputs: usize,
+                    outputs: usize,
+                    activation: A,
+                    init: Initializer<F>)
+        -> FeedforwardLayer<F, A>
+    {
+        let mut generator = init.generator(inputs, outputs);
+        FeedforwardLayer {
+            coeffs: Matrix::from_vec(outputs, inputs,
+                                     (0..inputs*outputs).map(|_| generator()).collect()),
+            biases: vec![zero(); outputs],
+            trainable_biases: true,
+            activation,
+            weights_state: OptimizerState::new(),
+            biases_state: OptimizerState::new(),
+            weights_traces: EligibilityTraces::new(),
+            biases_traces: EligibilityTraces::new()
         }
-        
-        for o in &mut out {
-            *o = (self.activation.value)(*o);
+    }
+
+    /// Creates a new feedforward layer from its weights matrix `W` and its
+    /// biases vector `B`.
+    ///
+    /// The weights matrix has a row for each output and a column for each
+    /// input, and the biases vector must have one value per output.
+    pub fn from_matrices(weights: Matrix<F>, biases: Vec<F>, activation: A)
+        -> FeedforwardLayer<F, A>
+    {
+        assert!(biases.len() == weights.rows(),
+                "The biases count must be equal to the rows count of the weights.");
+        FeedforwardLayer {
+            coeffs: weights,
+            biases,
+            trainable_biases: true,
+            activation,
+            weights_state: OptimizerState::new(),
+            biases_state: OptimizerState::new(),
+            weights_traces: EligibilityTraces::new(),
+            biases_traces: EligibilityTraces::new()
+        }
+    }
+
+    /// Turns this layer into a layer without biases.
+    ///
+    /// Its biases are set to 0 and are left untouched by the training.
+    pub fn without_biases(mut self) -> FeedforwardLayer<F, A> {
+        for b in &mut self.biases {
+            *b = zero();
         }
+        self.trainable_biases = false;
+        self
+    }
+
+    /// Whether the biases of this layer are trained, see `without_biases()`.
+    pub fn has_biases(&self) -> bool {
+        self.trainable_biases
+    }
+
+    /// The weights matrix of the layer: the weight between input `i` and
+    /// output `j` is at `(j, i)`.
+    pub fn weights(&self) -> &Matrix<F> {
+        &self.coeffs
+    }
+
+    /// Mutable access to the weights matrix of the layer, see `weights()`.
+    pub fn weights_mut(&mut self) -> &mut Matrix<F> {
+        &mut self.coeffs
+    }
+
+    /// The biases of the layer, one per output.
+    pub fn biases(&self) -> &[F] {
+        &self.biases
+    }
 
+    /// Mutable access to the biases of the layer.
+    ///
+    /// Panics if the layer has no biases, see `without_biases()`.
+    pub fn biases_mut(&mut self) -> &mut [F] {
+        assert!(self.trainable_biases, "A layer without biases has no biases to change.");
+        &mut self.biases
+    }
+
+    /// The activation function of the layer.
+    pub fn activation(&self) -> &A {
+        &self.activation
+    }
+
+    /// Clears the state kept by the training method between training steps,
+    /// like the velocities of a gradient descent with momentum.
+    pub fn reset_optimizer(&mut self) {
+        self.weights_state.reset();
+        self.biases_state.reset();
+    }
+
+    /// Clears the eligibility traces kept by a `RewardHebbian` rule between
+    /// training steps, typically at the start of each episode.
+    pub fn reset_traces(&mut self) {
+        self.weights_traces.reset();
+        self.biases_traces.reset();
+    }
+
+    /// Computes `W*X + B`, the values of the outputs before the activation
+    /// function is applied.
+    fn pre_activation(&self, input: &[F]) -> Vec<F> {
+        let mut out = self.coeffs.mul_vec(input);
+        for (o, &b) in out.iter_mut().zip(&self.biases) {
+            *o = *o + b;
+        }
         out
     }
+}
+
+impl<F, A> Compute<F> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.activation.activate(&self.pre_activation(input))
+    }
 
     fn input_size(&self) -> usize {
-        self.inputs
+        self.coeffs.cols()
     }
 
     fn output_size(&self) -> usize {
@@ -97,10 +269,25 @@ impl<F, V, D> Compute<F> for FeedforwardLayer<F, V, D>
     }
 }
 
-impl<F, V, D> SupervisedTrain<F, PerceptronRule<F>> for FeedforwardLayer<F, V, D>
+impl<F, A> Differentiable<F> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let preact = self.pre_activation(input);
+        let out = self.activation.activate(&preact);
+        let mut output_gradient = output_gradient.to_owned();
+        output_gradient.resize(out.len(), zero());
+        let deltas = self.activation.jacobian_product_with_output(&preact, &out, &output_gradient);
+        let mut gradient = self.coeffs.transpose_mul_vec(&deltas);
+        gradient.resize(input.len(), zero());
+        gradient
+    }
+}
+
+impl<F, A> SupervisedTrain<F, PerceptronRule<F>> for FeedforwardLayer<F, A>
     where F: Float,
-          V: Fn(F) -> F,
-          D: Fn(F) -> F
+          A: VectorActivation<F>
 {
     fn supervised_train(&mut self,
                         rule: &PerceptronRule<F>,
@@ -110,64 +297,101 @@ impl<F, V, D> SupervisedTrain<F, PerceptronRule<F>> for FeedforwardLayer<F, V, D
         let out = self.compute(input);
         for j in 0..self.biases.len() {
             let diff = out[j] - target.get(j).map(|v| *v).unwrap_or(zero());
-            for i in 0..min(self.inputs, input.len()) {
-                self.coeffs[i + j*self.inputs] =
-                    self.coeffs[i + j*self.inputs] - rule.rate * diff * input[i];
+            for i in 0..min(self.coeffs.cols(), input.len()) {
+                self.coeffs[(j, i)] = self.coeffs[(j, i)] - rule.rate() * diff * input[i];
+            }
+            if self.trainable_biases {
+                self.biases[j] = self.biases[j] - rule.rate() * diff;
+            }
+        }
+    }
+}
+
+impl<F, A> SupervisedTrain<F, DeltaRule<F>> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn supervised_train(&mut self,
+                        rule: &DeltaRule<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        let out = self.pre_activation(input);
+        for (j, &o) in out.iter().enumerate() {
+            let diff = o - target.get(j).cloned().unwrap_or(zero());
+            for (i, &x) in input.iter().enumerate().take(self.coeffs.cols()) {
+                self.coeffs[(j, i)] = self.coeffs[(j, i)] - rule.rate() * diff * x;
+            }
+            if self.trainable_biases {
+                self.biases[j] = self.biases[j] - rule.rate() * diff;
+            }
+        }
+    }
+}
+
+impl<F, A> UnsupervisedTrain<F, RewardHebbian<F>> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn unsupervised_train(&mut self, rule: &RewardHebbian<F>, input: &[F]) {
+        let out = self.compute(input);
+        let cols = self.coeffs.cols();
+        let mut correlations = vec![zero(); self.coeffs.rows() * cols];
+        for j in 0..out.len() {
+            for i in 0..min(cols, input.len()) {
+                correlations[j*cols + i] = out[j] * input[i];
             }
-            self.biases[j] = self.biases[j] - rule.rate * diff;
+        }
+        rule.update(self.coeffs.as_mut_slice(), &correlations, &mut self.weights_traces);
+        if self.trainable_biases {
+            rule.update(&mut self.biases, &out, &mut self.biases_traces);
         }
     }
 }
 
-impl<F, V, D> BackpropTrain<F, GradientDescent<F>> for FeedforwardLayer<F, V, D>
+impl<F, A, M> BackpropTrain<F, M> for FeedforwardLayer<F, A>
     where F: Float,
-          V: Fn(F) -> F,
-          D: Fn(F) -> F
+          A: VectorActivation<F>,
+          M: GradientMethod<F>
 {
     fn backprop_train(&mut self,
-                      rule: &GradientDescent<F>,
+                      rule: &M,
                       input: &[F],
                       target: &[F])
         -> Vec<F>
     {
         // we need to compute the intermediate states
-        let mut out = self.biases.clone();
-        for j in 0..self.biases.len() {
-            for i in 0..min(self.inputs, input.len()) {
-                out[j] = out[j] + self.coeffs[j*self.inputs + i] * input[i]
-            }
-        }
+        let preact = self.pre_activation(input);
+        let out = self.activation.activate(&preact);
 
-        let deltas = out.iter()
-                            .map(|x| { (self.activation.derivative)(*x) })
-                            .collect::<Vec<_>>();
-        for o in &mut out {
-            *o = (self.activation.value)(*o);
-        }
+        // gradient of the squared error with regard to the pre-activation values
+        let errors = out.iter()
+                        .enumerate()
+                        .map(|(j, o)| *o - target.get(j).cloned().unwrap_or(zero()))
+                        .collect::<Vec<_>>();
+        let deltas = self.activation.jacobian_product_with_output(&preact, &out, &errors);
 
         let mut returned = input.to_owned();
+        let cols = self.coeffs.cols();
+        let mut gradient = vec![zero(); self.coeffs.rows() * cols];
         for j in 0..self.biases.len() {
-            for i in 0..min(self.inputs, input.len()) {
-                returned[i] = returned[i] - self.coeffs[i + j*self.inputs]*deltas[j];
-                self.coeffs[i + j*self.inputs] =
-                    self.coeffs[i + j*self.inputs]
-                    - rule.rate * input.get(i).map(|x| *x).unwrap_or(zero())
-                                * deltas[j]
-                                * ( out[j] - target.get(j).map(|x| *x).unwrap_or(zero()) )
-
+            for i in 0..min(cols, input.len()) {
+                returned[i] = returned[i] - self.coeffs[(j, i)]*deltas[j];
+                gradient[j*cols + i] = input[i] * deltas[j];
             }
-            self.biases[j] = self.biases[j]
-                    - rule.rate * deltas[j]
-                                * ( out[j] - target.get(j).map(|x| *x).unwrap_or(zero()) );
+        }
+        rule.regularize(self.coeffs.as_slice(), &mut gradient);
+        rule.update(self.coeffs.as_mut_slice(), &gradient, &mut self.weights_state);
+        if self.trainable_biases {
+            rule.update(&mut self.biases, &deltas, &mut self.biases_state);
         }
         returned
     }
 }
 
-impl<F, V, D> SupervisedTrain<F, GradientDescent<F>> for FeedforwardLayer<F, V, D>
+impl<F, A> SupervisedTrain<F, GradientDescent<F>> for FeedforwardLayer<F, A>
     where F: Float,
-          V: Fn(F) -> F,
-          D: Fn(F) -> F
+          A: VectorActivation<F>
 {
     fn supervised_train(&mut self,
                         rule: &GradientDescent<F>,
@@ -178,19 +402,124 @@ impl<F, V, D> SupervisedTrain<F, GradientDescent<F>> for FeedforwardLayer<F, V,
     }
 }
 
+impl<F, A> SupervisedTrain<F, Momentum<F>> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn supervised_train(&mut self,
+                        rule: &Momentum<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F, A> SupervisedTrain<F, Adam<F>> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn supervised_train(&mut self,
+                        rule: &Adam<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F, A> SupervisedTrain<F, Adagrad<F>> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn supervised_train(&mut self,
+                        rule: &Adagrad<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F, A> SupervisedTrain<F, Adadelta<F>> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn supervised_train(&mut self,
+                        rule: &Adadelta<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F, A, M> SupervisedTrain<F, Regularized<F, M>> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F>,
+          M: GradientMethod<F>
+{
+    fn supervised_train(&mut self,
+                        rule: &Regularized<F, M>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F, A> Symbolic<F> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F> + SymbolicActivation<F>
+{
+    fn symbolic(&self, inputs: &[String]) -> Vec<String> {
+        let inputs = (0..self.coeffs.cols()).map(|i| inputs.get(i).cloned().unwrap_or("0".to_owned()))
+                                     .collect::<Vec<_>>();
+        let preact = (0..self.biases.len()).map(|j| {
+            linear_combination(self.coeffs.row(j), &inputs, self.biases[j])
+        }).collect::<Vec<_>>();
+        self.activation.symbolic(&preact)
+    }
+}
+
+impl<F, A> IntervalCompute<F> for FeedforwardLayer<F, A>
+    where F: Float,
+          A: VectorActivation<F> + IntervalActivation<F>
+{
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        // W*X + B is within W*M + B +/- |W|*R, where M is the center of the
+        // input box and R its radius
+        let two = F::one() + F::one();
+        let mut center = self.biases.clone();
+        let mut radius = vec![zero(); self.biases.len()];
+        for j in 0..self.biases.len() {
+            for i in 0..min(self.coeffs.cols(), min(lower.len(), upper.len())) {
+                let w = self.coeffs[(j, i)];
+                center[j] = center[j] + w * (upper[i] + lower[i]) / two;
+                radius[j] = radius[j] + w.abs() * (upper[i] - lower[i]) / two;
+            }
+        }
+        let pre_lower = center.iter().zip(&radius).map(|(&c, &r)| c - r).collect::<Vec<_>>();
+        let pre_upper = center.iter().zip(&radius).map(|(&c, &r)| c + r).collect::<Vec<_>>();
+        self.activation.activate_bounds(&pre_lower, &pre_upper)
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
-    use {Compute, SupervisedTrain};
-    use activations::{identity, step, sigmoid};
-    use training::{PerceptronRule, GradientDescent};
+    use {Compute, Differentiable, SupervisedTrain, UnsupervisedTrain};
+    use activations::{identity, step, sigmoid, softmax, Activation};
+    use training::{PerceptronRule, DeltaRule, RewardHebbian, GradientDescent, GradientMethod, Momentum, Adam};
     use util::Chain;
+    use init::Initializer;
+    use linalg::Matrix;
 
     use super::FeedforwardLayer;
 
     #[test]
     fn basics() {
-        let layer = FeedforwardLayer::<f32, _, _>::new(7, 3, identity());
+        let layer = FeedforwardLayer::<f32, _>::new(7, 3, identity());
         assert_eq!(layer.input_size(), 7);
         assert_eq!(layer.output_size(), 3);
     }
@@ -208,7 +537,7 @@ mod tests {
     #[test]
     fn perceptron_rule() {
         let mut layer = FeedforwardLayer::new(4, 2, step());
-        let rule = PerceptronRule { rate: 0.5f32 };
+        let rule = PerceptronRule::new(0.5f32).unwrap();
         for _ in 0..3 {
             layer.supervised_train(&rule, &[1.0,1.0,1.0,1.0], &[0.0, 0.0]);
             layer.supervised_train(&rule, &[1.0,-1.0,1.0,-1.0], &[1.0, 1.0]);
@@ -226,7 +555,7 @@ mod tests {
             move || { acc += 1; (1.0f32 + ((13*acc) % 12) as f32) / 13.0f32}
         };
         let mut layer = FeedforwardLayer::new_from(4, 2, sigmoid(), random);
-        let rule = GradientDescent { rate: 0.5f32 };
+        let rule = GradientDescent::new(0.5f32).unwrap();
         for _ in 0..40 {
             layer.supervised_train(&rule, &[1.0,1.0,1.0,1.0], &[0.0, 0.0]);
             layer.supervised_train(&rule, &[1.0,-1.0,1.0,-1.0], &[1.0, 1.0]);
@@ -244,7 +573,7 @@ mod tests {
             move || { acc += 1; (1.0f32 + ((13*acc) % 12) as f32) / 13.0f32}
         };
         let mut layer = Chain::new(FeedforwardLayer::new_from(4, 8, sigmoid(), &mut random), FeedforwardLayer::new_from(8, 2, sigmoid(), &mut random));
-        let rule = GradientDescent { rate: 0.5f32 };
+        let rule = GradientDescent::new(0.5f32).unwrap();
         for _ in 0..200 {
             layer.supervised_train(&rule, &[1.0, 1.0,1.0, 1.0], &[1.0, 0.0]);
             layer.supervised_train(&rule, &[1.0,-1.0,1.0,-1.0], &[0.0, 1.0]);
@@ -254,4 +583,204 @@ mod tests {
         println!("{:?}", layer.compute(&[1.0, -1.0, 1.0, -1.0]));
         assert!({ let out = layer.compute(&[1.0, -1.0, 1.0, -1.0]); out[0] < 0.2 && out[1] > 0.8 });
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn softmax_layer() {
+        let mut layer = FeedforwardLayer::new(4, 2, softmax());
+        let rule = GradientDescent::new(1.0f32).unwrap();
+        for _ in 0..100 {
+            layer.supervised_train(&rule, &[1.0, 1.0,1.0, 1.0], &[1.0, 0.0]);
+            layer.supervised_train(&rule, &[1.0,-1.0,1.0,-1.0], &[0.0, 1.0]);
+        }
+        let out = layer.compute(&[1.0, 1.0, 1.0, 1.0]);
+        assert!((out[0] + out[1] - 1.0).abs() < 0.00001);
+        assert!(out[0] > 0.8);
+        assert!({ let out = layer.compute(&[1.0, -1.0, 1.0, -1.0]); out[1] > 0.8 });
+    }
+
+    #[test]
+    fn clone_with_activation_enum() {
+        let mut layer = FeedforwardLayer::new_from(2, 2, Activation::Sigmoid, || 0.5f32);
+        let copy = layer.clone();
+        assert!(layer == copy);
+        layer.supervised_train(&GradientDescent::new(0.5).unwrap(), &[1.0, 0.0], &[0.0, 0.0]);
+        assert!(layer != copy);
+        assert_eq!(copy.compute(&[0.0, 0.0]), FeedforwardLayer::new_from(2, 2, sigmoid(), || 0.5f32).compute(&[0.0, 0.0]));
+    }
+
+    #[test]
+    fn per_neuron_activations() {
+        let mut layer = FeedforwardLayer::new_from(2, 2, vec![Activation::Identity, Activation::Sigmoid], || 1.0f32);
+        let out = layer.compute(&[1.0, 1.0]);
+        assert_eq!(out[0], 3.0);
+        assert!((out[1] - 0.95257413).abs() < 0.00001);
+        // a linear output next to a sigmoid one
+        let rule = GradientDescent::new(0.1f32).unwrap();
+        for _ in 0..500 {
+            layer.supervised_train(&rule, &[1.0, 0.0], &[4.0, 0.0]);
+            layer.supervised_train(&rule, &[0.0, 1.0], &[-2.0, 1.0]);
+        }
+        let out = layer.compute(&[1.0, 0.0]);
+        assert!((out[0] - 4.0).abs() < 0.01 && out[1] < 0.2);
+        let out = layer.compute(&[0.0, 1.0]);
+        assert!((out[0] + 2.0).abs() < 0.01 && out[1] > 0.8);
+    }
+
+    #[test]
+    fn random_init() {
+        let layer = FeedforwardLayer::new_random(10, 10, identity(), -0.5f32, 0.5);
+        assert!(layer.coeffs.as_slice().iter().chain(&layer.biases).all(|w| (-0.5..0.5).contains(w)));
+        // weights are not all identical
+        assert!(layer.coeffs.as_slice().iter().any(|&w| w != layer.coeffs[(0, 0)]));
+        let layer = FeedforwardLayer::new_normal(10, 10, identity(), 0.1f32);
+        assert!(layer.coeffs.as_slice().iter().any(|&w| w != layer.coeffs[(0, 0)]));
+        let mean = layer.coeffs.as_slice().iter().fold(0.0, |s, w| s + w) / 100.0;
+        assert!(mean.abs() < 0.1);
+        let layer = FeedforwardLayer::new_init(10, 10, identity(), Initializer::<f32>::Xavier);
+        assert!(layer.coeffs.as_slice().iter().any(|&w| w != layer.coeffs[(0, 0)]));
+        assert!(layer.biases.iter().all(|&b| b == 0.0));
+    }
+
+    #[test]
+    fn parameters() {
+        let weights = Matrix::from_vec(2, 3, vec![1.0f32, 0.0, 0.0, 0.0, 1.0, 1.0]);
+        let mut layer = FeedforwardLayer::from_matrices(weights, vec![0.5, -0.5], identity());
+        assert_eq!(layer.input_size(), 3);
+        assert_eq!(layer.output_size(), 2);
+        assert_eq!(layer.compute(&[1.0, 2.0, 3.0]), [1.5, 4.5]);
+        layer.weights_mut()[(1, 0)] = 2.0;
+        layer.biases_mut()[0] = 0.0;
+        assert_eq!(layer.weights().as_slice(), [1.0, 0.0, 0.0, 2.0, 1.0, 1.0]);
+        assert_eq!(layer.biases(), [0.0, -0.5]);
+        assert_eq!(layer.compute(&[1.0, 2.0, 3.0]), [1.0, 6.5]);
+    }
+
+    #[test]
+    fn input_gradient() {
+        let mut acc = 0;
+        let layer = FeedforwardLayer::new_from(3, 2, sigmoid(), || { acc += 1; ((acc * 7) % 5) as f64 - 2.0 });
+        let input = [0.3, -0.5, 0.8];
+        let v = [1.0, -2.0];
+        let gradient = layer.input_gradient(&input, &v);
+        let h = 1e-6;
+        for i in 0..3 {
+            let (mut xp, mut xm) = (input, input);
+            xp[i] += h;
+            xm[i] -= h;
+            let (yp, ym) = (layer.compute(&xp), layer.compute(&xm));
+            let num = (0..2).fold(0.0, |s, j| s + v[j] * (yp[j] - ym[j]) / (2.0*h));
+            assert!((num - gradient[i]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn without_biases() {
+        let mut layer = FeedforwardLayer::new_from(2, 2, sigmoid(), || 0.5f32).without_biases();
+        assert!(!layer.has_biases());
+        assert_eq!(layer.biases(), [0.0, 0.0]);
+        let rule = GradientDescent::new(0.5f32).unwrap();
+        for _ in 0..10 {
+            layer.supervised_train(&rule, &[1.0, -1.0], &[1.0, 0.0]);
+        }
+        assert_eq!(layer.biases(), [0.0, 0.0]);
+        assert!(layer.weights()[(0, 0)] != 0.5);
+        // without bias, a null input always gives sigmoid(0)
+        assert_eq!(layer.compute(&[0.0, 0.0]), [0.5, 0.5]);
+    }
+
+    #[test]
+    fn delta_rule() {
+        // fit y = 2*x0 - x1 + 0.5 with a linear unit
+        let mut layer = FeedforwardLayer::new(2, 1, identity());
+        let rule = DeltaRule::new(0.1f32).unwrap();
+        for _ in 0..200 {
+            for &(x0, x1) in &[(0.0f32, 0.0f32), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0), (-1.0, 0.5)] {
+                layer.supervised_train(&rule, &[x0, x1], &[2.0*x0 - x1 + 0.5]);
+            }
+        }
+        assert!((layer.weights()[(0, 0)] - 2.0).abs() < 0.01);
+        assert!((layer.weights()[(0, 1)] + 1.0).abs() < 0.01);
+        assert!((layer.biases()[0] - 0.5).abs() < 0.01);
+
+        // an ADALINE: the step function is ignored by the training
+        let mut layer = FeedforwardLayer::new(4, 1, step());
+        for _ in 0..20 {
+            layer.supervised_train(&rule, &[1.0,1.0,1.0,1.0], &[-1.0]);
+            layer.supervised_train(&rule, &[1.0,-1.0,1.0,-1.0], &[1.0]);
+        }
+        assert_eq!(layer.compute(&[1.0, 1.0, 1.0, 1.0]), [0.0f32]);
+        assert_eq!(layer.compute(&[1.0, -1.0, 1.0, -1.0]), [1.0f32]);
+    }
+
+    #[test]
+    fn momentum() {
+        let samples = [([1.0f64, 0.0], 0.5), ([0.0, 1.0], -0.3), ([1.0, 1.0], 0.2)];
+        let error = |layer: &FeedforwardLayer<f64, _>| samples.iter().fold(0.0, |s, &(x, y)| {
+            s + (layer.compute(&x)[0] - y).powi(2)
+        });
+        let mut plain = FeedforwardLayer::new(2, 1, identity());
+        let mut heavy = FeedforwardLayer::new(2, 1, identity());
+        let mut nesterov = FeedforwardLayer::new(2, 1, identity());
+        for _ in 0..20 {
+            for &(x, y) in &samples {
+                plain.supervised_train(&GradientDescent::new(0.02).unwrap(), &x, &[y]);
+                heavy.supervised_train(&Momentum::new(0.02, 0.9).unwrap(), &x, &[y]);
+                nesterov.supervised_train(&Momentum::nesterov(0.02, 0.9).unwrap(), &x, &[y]);
+            }
+        }
+        assert!(error(&heavy) < error(&plain));
+        assert!(error(&nesterov) < error(&plain));
+    }
+
+    #[test]
+    fn adam_train() {
+        let mut random = {
+            let mut acc = 0;
+            move || { acc += 1; (1.0f32 + ((13*acc) % 12) as f32) / 13.0f32 - 0.5}
+        };
+        let mut layer = Chain::new(FeedforwardLayer::new_from(4, 8, sigmoid(), &mut random), FeedforwardLayer::new_from(8, 2, sigmoid(), &mut random));
+        let rule = Adam::new(0.05f32).unwrap();
+        for _ in 0..100 {
+            layer.supervised_train(&rule, &[1.0, 1.0,1.0, 1.0], &[1.0, 0.0]);
+            layer.supervised_train(&rule, &[1.0,-1.0,1.0,-1.0], &[0.0, 1.0]);
+        }
+        assert!({ let out = layer.compute(&[1.0, 1.0, 1.0, 1.0]); out[0] > 0.8 && out[1] < 0.2 });
+        assert!({ let out = layer.compute(&[1.0, -1.0, 1.0, -1.0]); out[0] < 0.2 && out[1] > 0.8 });
+    }
+
+    #[test]
+    fn weight_decay() {
+        // without any error, the weights shrink and the biases stay
+        let mut layer = FeedforwardLayer::new_from(2, 1, identity(), || 1.0f32);
+        let rule = GradientDescent::new(0.1).unwrap().regularized(0.0, 1.0).unwrap();
+        layer.supervised_train(&rule, &[0.0, 0.0], &[1.0]);
+        assert_eq!(layer.weights().as_slice(), [0.9, 0.9]);
+        assert_eq!(layer.biases(), [1.0]);
+    }
+
+    #[test]
+    fn reward_hebbian() {
+        let mut layer = FeedforwardLayer::new_from(2, 1, Activation::Identity, || 0.5f64).without_biases();
+        let mut rule = RewardHebbian::new(0.1, 0.5).unwrap();
+        // an unrewarded step only builds the traces
+        layer.unsupervised_train(&rule, &[1.0, 0.0]);
+        assert_eq!(layer.weights().as_slice(), [0.5, 0.5]);
+        // y = 0.5: traces become [0.5 * 0.5 + 0.5, 0.0]
+        rule.set_reward(2.0);
+        layer.unsupervised_train(&rule, &[1.0, 0.0]);
+        assert!((layer.weights()[(0, 0)] - 0.65).abs() < 1e-12);
+        assert_eq!(layer.weights()[(0, 1)], 0.5);
+        // the traces are apart from the state of the gradient methods
+        layer.reset_optimizer();
+        assert_eq!(layer.weights_traces.traces(), [0.75, 0.0]);
+        // and do not take part in the comparisons
+        let mut reset = layer.clone();
+        reset.reset_traces();
+        assert_eq!(reset, layer);
+        // a punishment weakens the correlated weights
+        layer.reset_traces();
+        rule.set_reward(-1.0);
+        layer.unsupervised_train(&rule, &[0.0, 1.0]);
+        assert!(layer.weights()[(0, 1)] < 0.5);
+    }
+}
@@ -0,0 +1,176 @@
+//! Golden-output regression snapshots.
+//!
+//! A `GoldenOutputs` records the outputs of a network on a fixed set of probe
+//! inputs, and can be saved to a file. Later versions of the network (after
+//! a retraining, a refactoring or an upgrade of this crate) can then be
+//! checked against it, to detect silent changes of behavior.
+//!
+//! The file format is plain text, with one line per probe: the comma
+//! separated inputs, a `;`, and the comma separated outputs.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use num::{Float, one};
+
+use Compute;
+
+/// A difference between the outputs of a network and a `GoldenOutputs`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch<F> {
+    /// The index of the probe input.
+    pub probe: usize,
+    /// The index of the output.
+    pub output: usize,
+    /// The recorded value, `None` if the network has more outputs than
+    /// recorded.
+    pub expected: Option<F>,
+    /// The value computed by the network, `None` if it has less outputs than
+    /// recorded.
+    pub actual: Option<F>
+}
+
+/// The recorded outputs of a network on a set of probe inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GoldenOutputs<F> {
+    inputs: Vec<Vec<F>>,
+    outputs: Vec<Vec<F>>
+}
+
+fn parse_values<F: Float>(text: &str) -> io::Result<Vec<F>> {
+    if text.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    text.split(',').map(|v| {
+        v.trim().parse::<f64>()
+         .map(|x| F::from(x).unwrap())
+         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }).collect()
+}
+
+fn write_values<F: Float, W: Write>(writer: &mut W, values: &[F]) -> io::Result<()> {
+    for (i, v) in values.iter().enumerate() {
+        if i > 0 {
+            write!(writer, ",")?;
+        }
+        write!(writer, "{}", v.to_f64().unwrap())?;
+    }
+    Ok(())
+}
+
+impl<F: Float> GoldenOutputs<F> {
+    /// Records the outputs of `network` on given probe inputs.
+    pub fn record<N: Compute<F>>(network: &N, probes: &[Vec<F>]) -> GoldenOutputs<F> {
+        GoldenOutputs {
+            inputs: probes.to_owned(),
+            outputs: probes.iter().map(|x| network.compute(x)).collect()
+        }
+    }
+
+    /// The probe inputs.
+    pub fn inputs(&self) -> &[Vec<F>] {
+        &self.inputs
+    }
+
+    /// The recorded outputs, one vector per probe input.
+    pub fn outputs(&self) -> &[Vec<F>] {
+        &self.outputs
+    }
+
+    /// Checks that the outputs of `network` on the probe inputs match the
+    /// recorded ones, returning the first mismatch otherwise.
+    ///
+    /// An output matches if it differs from the recorded value by at most
+    /// `tolerance`, relatively to this value when it is larger than `1.0`.
+    pub fn verify<N: Compute<F>>(&self, network: &N, tolerance: F) -> Result<(), Mismatch<F>> {
+        for (probe, (input, expected)) in self.inputs.iter().zip(&self.outputs).enumerate() {
+            let actual = network.compute(input);
+            for output in 0..expected.len().max(actual.len()) {
+                let (e, a) = (expected.get(output).cloned(), actual.get(output).cloned());
+                let matches = match (e, a) {
+                    (Some(e), Some(a)) => (a - e).abs() <= tolerance * e.abs().max(one()),
+                    _ => false
+                };
+                if !matches {
+                    return Err(Mismatch { probe, output, expected: e, actual: a });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes the snapshot in the text format of this module.
+    pub fn write<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (input, output) in self.inputs.iter().zip(&self.outputs) {
+            write_values(&mut writer, input)?;
+            write!(writer, ";")?;
+            write_values(&mut writer, output)?;
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    /// Reads a snapshot in the text format of this module.
+    pub fn read<R: BufRead>(reader: R) -> io::Result<GoldenOutputs<F>> {
+        let mut golden = GoldenOutputs { inputs: Vec::new(), outputs: Vec::new() };
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut parts = line.splitn(2, ';');
+            let input = parse_values(parts.next().unwrap_or(""))?;
+            let output = match parts.next() {
+                Some(text) => parse_values(text)?,
+                None => return Err(io::Error::new(io::ErrorKind::InvalidData, "missing ';' separator"))
+            };
+            golden.inputs.push(input);
+            golden.outputs.push(output);
+        }
+        Ok(golden)
+    }
+
+    /// Saves the snapshot to a file, see `write(..)`.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write(File::create(path)?)
+    }
+
+    /// Loads a snapshot from a file, see `read(..)`.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<GoldenOutputs<F>> {
+        GoldenOutputs::read(BufReader::new(File::open(path)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {FeedforwardLayer, Matrix};
+    use activations::Activation;
+
+    use super::{GoldenOutputs, Mismatch};
+
+    #[test]
+    fn golden() {
+        let mut layer = FeedforwardLayer::from_matrices(
+            Matrix::from_vec(2, 2, vec![1.0f64, 0.5, -1.0, 2.0]), vec![0.0, 0.1], Activation::Sigmoid);
+        let probes = vec![vec![0.0, 0.0], vec![1.0, -0.3], vec![-2.5, 1e-3]];
+        let golden = GoldenOutputs::record(&layer, &probes);
+        assert_eq!(golden.verify(&layer, 0.0), Ok(()));
+
+        let mut text = Vec::new();
+        golden.write(&mut text).unwrap();
+        let read = GoldenOutputs::<f64>::read(&text[..]).unwrap();
+        assert_eq!(read, golden);
+        assert!(GoldenOutputs::<f64>::read(&b"1,2\n"[..]).is_err());
+
+        layer.biases_mut()[1] = 0.1001;
+        assert!(golden.verify(&layer, 1e-3).is_ok());
+        match golden.verify(&layer, 1e-6) {
+            Err(Mismatch { probe: 0, output: 1, .. }) => {},
+            other => panic!("unexpected verification result: {:?}", other)
+        }
+        let smaller = FeedforwardLayer::new(2, 1, Activation::Sigmoid);
+        assert_eq!(golden.verify(&smaller, 1.0),
+                   Err(Mismatch { probe: 0, output: 1, expected: Some(golden.outputs()[0][1]), actual: None }));
+    }
+}
@@ -0,0 +1,89 @@
+//! Weight initialization schemes.
+//!
+//! Initializing all the weights of a network to the same value makes all
+//! the neurons of a layer learn the same thing. These schemes draw them at
+//! random, and most of them scale the distribution according to the number
+//! of inputs (fan-in) and outputs (fan-out) of the layer, which is essential
+//! for deep stacks of layers to be trainable.
+
+use num::Float;
+
+use rand::thread_rng;
+use rand::distributions::{IndependentSample, Normal, Range};
+
+/// A weight initialization scheme.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Initializer<F: Float> {
+    /// Uniform distribution in the range `[low, high)`.
+    Uniform(F, F),
+    /// Normal distribution centered on `0.0` with given standard deviation.
+    Normal(F),
+    /// Xavier (or Glorot) initialization: uniform distribution in the range
+    /// `[-l, l)` where `l = sqrt(6 / (fan_in + fan_out))`.
+    ///
+    /// Suited for sigmoid-like activation functions.
+    Xavier,
+    /// He initialization: normal distribution with standard deviation
+    /// `sqrt(2 / fan_in)`.
+    ///
+    /// Suited for rectifier-like activation functions.
+    He,
+    /// LeCun initialization: normal distribution with standard deviation
+    /// `sqrt(1 / fan_in)`.
+    LeCun
+}
+
+impl<F: Float> Initializer<F> {
+    /// Returns a closure drawing the weights of a layer with given number of
+    /// inputs and outputs.
+    pub fn generator(&self, inputs: usize, outputs: usize) -> impl FnMut() -> F {
+        let (fan_in, fan_out) = (inputs.max(1) as f64, outputs.max(1) as f64);
+        let distribution = match *self {
+            Initializer::Uniform(low, high) =>
+                Distribution::Uniform(Range::new(low.to_f64().unwrap(), high.to_f64().unwrap())),
+            Initializer::Normal(std_dev) =>
+                Distribution::Normal(Normal::new(0.0, std_dev.to_f64().unwrap())),
+            Initializer::Xavier => {
+                let l = (6.0 / (fan_in + fan_out)).sqrt();
+                Distribution::Uniform(Range::new(-l, l))
+            },
+            Initializer::He => Distribution::Normal(Normal::new(0.0, (2.0 / fan_in).sqrt())),
+            Initializer::LeCun => Distribution::Normal(Normal::new(0.0, (1.0 / fan_in).sqrt()))
+        };
+        let mut rng = thread_rng();
+        move || {
+            let x = match distribution {
+                Distribution::Uniform(ref range) => range.ind_sample(&mut rng),
+                Distribution::Normal(ref normal) => normal.ind_sample(&mut rng)
+            };
+            F::from(x).unwrap()
+        }
+    }
+}
+
+enum Distribution {
+    Uniform(Range<f64>),
+    Normal(Normal)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Initializer;
+
+    #[test]
+    fn xavier_bounds() {
+        // l = sqrt(6 / 6) = 1
+        let mut gen = Initializer::<f64>::Xavier.generator(2, 4);
+        let values = (0..1000).map(|_| gen()).collect::<Vec<_>>();
+        assert!(values.iter().all(|x| (-1.0..1.0).contains(x)));
+        assert!(values.iter().any(|&x| x < -0.9) && values.iter().any(|&x| x > 0.9));
+    }
+
+    #[test]
+    fn he_std_dev() {
+        let mut gen = Initializer::<f64>::He.generator(50, 10);
+        let n = 10000;
+        let var = (0..n).map(|_| gen().powi(2)).fold(0.0, |s, x| s + x) / n as f64;
+        assert!((var - 2.0 / 50.0).abs() < 0.005);
+    }
+}
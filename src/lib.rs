@@ -14,16 +14,42 @@ extern crate rand;
 
 use num::Float;
 
-pub use linalg::SymmetricMatrix;
+pub use linalg::{Matrix, SymmetricMatrix};
 
-pub use boltzmann::BoltzmannMachine;
+pub use boltzmann::{BoltzmannMachine, Snapshot, Trace, UnitType};
 pub use feedforward::FeedforwardLayer;
+pub use maxout::MaxoutLayer;
+pub use mlp::{Mlp, MlpBuilder};
+pub use rnn::{Gate, Gru, SimpleRnn};
+pub use softmax::SoftmaxLayer;
+
+#[macro_use]
+mod macros;
 
 mod boltzmann;
 mod feedforward;
 mod linalg;
+mod maxout;
+mod mlp;
+mod rnn;
+mod softmax;
 
 pub mod activations;
+pub mod audit;
+pub mod active;
+pub mod connectivity;
+pub mod detector;
+pub mod encoding;
+pub mod export;
+pub mod golden;
+pub mod init;
+pub mod loss;
+pub mod preprocess;
+pub mod rl;
+pub mod robustness;
+pub mod serving;
+pub mod spiking;
+pub mod trainer;
 pub mod training;
 pub mod util;
 
@@ -37,6 +63,63 @@ pub trait Compute<F: Float>{
     fn input_size(&self) -> usize;
     /// The number of outputs generated by this network.
     fn output_size(&self) -> usize;
+    /// Switches this network between training mode (`true`) and evaluation
+    /// mode (`false`).
+    ///
+    /// Most networks behave the same in both modes and ignore it, but some,
+    /// like `util::Dropout`, only perturb their computation while training.
+    /// Networks start in evaluation mode.
+    fn set_training(&mut self, _training: bool) {}
+}
+
+/// Boxed networks, including trait objects like `Box<dyn Compute<F>>`,
+/// compute like the network they contain.
+impl<F: Float, N: Compute<F> + ?Sized> Compute<F> for Box<N> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        (**self).compute(input)
+    }
+
+    fn input_size(&self) -> usize {
+        (**self).input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        (**self).output_size()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        (**self).set_training(training);
+    }
+}
+
+/// A trait for networks with an internal state evolving over time, like
+/// recurrent layers, which process sequences one step at a time.
+///
+/// Unless documented otherwise, `Compute::compute(..)` gives the output of the
+/// next step without changing the state.
+pub trait StatefulCompute<F: Float>: Compute<F> {
+    /// Processes the input of the next step into output, updating the state.
+    fn step(&mut self, input: &[F]) -> Vec<F>;
+    /// Resets the state to its initial value, typically before processing a
+    /// new sequence.
+    fn reset_state(&mut self);
+    /// Processes a sequence of inputs, starting from the current state, and
+    /// returns the sequence of outputs.
+    fn compute_sequence(&mut self, inputs: &[Vec<F>]) -> Vec<Vec<F>> {
+        inputs.iter().map(|x| self.step(x)).collect()
+    }
+}
+
+/// A trait for networks able to compute the gradient of their outputs with
+/// regard to their inputs.
+pub trait Differentiable<F: Float>: Compute<F> {
+    /// Given the gradient of some value with regard to the outputs of the
+    /// network when processing `input`, computes the gradient of this value
+    /// with regard to the inputs.
+    ///
+    /// This is the product of the transposed Jacobian matrix of the network
+    /// at `input` with `output_gradient`.
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F>;
 }
 
 /// This trait describes a training method. It does not hold a lot of constraints
@@ -60,6 +143,17 @@ pub trait SupervisedTrain<F: Float, M: Method> {
     fn supervised_train(&mut self, rule: &M, input: &[F], target: &[F]);
 }
 
+/// A trait for stateful networks that can be trained using a certain method
+/// on sequences.
+pub trait SequenceTrain<F: Float, M: Method> {
+    /// Performs supervised training on given sequence of inputs and
+    /// sequence of target values using the learning parameters defined by
+    /// `rule`, starting from the current state.
+    ///
+    /// The state at the end of the sequence is kept.
+    fn sequence_train(&mut self, rule: &M, inputs: &[Vec<F>], targets: &[Vec<F>]);
+}
+
 /// A trait for networks that can be trained using a certain method in a
 /// back-propagation way: the training returns a values vector that is
 /// to be used as a target value for the previous layer.
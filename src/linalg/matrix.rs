@@ -0,0 +1,134 @@
+use std::cmp::min;
+use std::ops::{Index, IndexMut};
+
+use num::{Float, zero};
+
+/// Represents a dense matrix.
+///
+/// It can be indexed using matrices coordinates like `matrix[(i,j)]`,
+/// `i` being the row and `j` the column. Its values are stored in
+/// row-major order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Matrix<F: Float> {
+    rows: usize,
+    cols: usize,
+    values: Vec<F>
+}
+
+impl<F: Float> Matrix<F> {
+    /// Create a new `rows x cols` Matrix filled with zeros.
+    pub fn zeros(rows: usize, cols: usize) -> Matrix<F> {
+        Matrix {
+            rows,
+            cols,
+            values: vec![zero(); rows*cols]
+        }
+    }
+
+    /// Create a new `rows x cols` Matrix from its values in row-major
+    /// order.
+    pub fn from_vec(rows: usize, cols: usize, values: Vec<F>) -> Matrix<F> {
+        assert!(values.len() == rows*cols, "The values count must be equal to rows*cols.");
+        Matrix {
+            rows,
+            cols,
+            values
+        }
+    }
+
+    /// The number of rows of the matrix.
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// The number of columns of the matrix.
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// The values of row `i` of the matrix.
+    pub fn row(&self, i: usize) -> &[F] {
+        &self.values[i*self.cols..(i+1)*self.cols]
+    }
+
+    /// Computes the product of this matrix with the vector `v`.
+    ///
+    /// Missing values of `v` are considered to be `0.0`, superfluous ones
+    /// are ignored.
+    pub fn mul_vec(&self, v: &[F]) -> Vec<F> {
+        let n = min(self.cols, v.len());
+        (0..self.rows).map(|i| {
+            self.row(i)[..n].iter().zip(v).fold(zero(), |s, (&a, &x)| s + a * x)
+        }).collect()
+    }
+
+    /// Computes the product of the transpose of this matrix with the
+    /// vector `v`.
+    ///
+    /// Missing values of `v` are considered to be `0.0`, superfluous ones
+    /// are ignored.
+    pub fn transpose_mul_vec(&self, v: &[F]) -> Vec<F> {
+        let mut out = vec![zero(); self.cols];
+        for (i, &x) in v.iter().enumerate().take(self.rows) {
+            for (o, &a) in out.iter_mut().zip(self.row(i)) {
+                *o = *o + a * x;
+            }
+        }
+        out
+    }
+
+    /// The values of the matrix, in row-major order.
+    pub fn as_slice(&self) -> &[F] {
+        &self.values
+    }
+
+    /// Mutable access to the values of the matrix, in row-major order.
+    pub fn as_mut_slice(&mut self) -> &mut [F] {
+        &mut self.values
+    }
+
+    /// Consumes the matrix, returning its values in row-major order.
+    pub fn into_vec(self) -> Vec<F> {
+        self.values
+    }
+}
+
+impl<F: Float> Index<(usize, usize)> for Matrix<F> {
+    type Output = F;
+    fn index(&self, (i, j): (usize, usize)) -> &F {
+        assert!(j < self.cols, "Column index out of bounds.");
+        &self.values[i*self.cols + j]
+    }
+}
+
+impl<F: Float> IndexMut<(usize, usize)> for Matrix<F> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut F {
+        assert!(j < self.cols, "Column index out of bounds.");
+        &mut self.values[i*self.cols + j]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Matrix;
+
+    #[test]
+    fn indexing() {
+        let mut matrix = Matrix::<f32>::zeros(2, 3);
+        matrix[(1, 0)] = 4.0;
+        matrix[(0, 2)] = 3.0;
+        assert_eq!(matrix.as_slice(), [0.0, 0.0, 3.0, 4.0, 0.0, 0.0]);
+        let matrix = Matrix::from_vec(3, 2, vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(matrix[(2, 1)], 6.0);
+        assert_eq!(matrix[(1, 0)], 3.0);
+    }
+
+    #[test]
+    fn products() {
+        let matrix = Matrix::from_vec(2, 3, vec![1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(matrix.mul_vec(&[1.0, 0.0, -1.0]), [-2.0, -2.0]);
+        assert_eq!(matrix.mul_vec(&[1.0]), [1.0, 4.0]);
+        assert_eq!(matrix.transpose_mul_vec(&[1.0, -1.0]), [-3.0, -3.0, -3.0]);
+        assert_eq!(matrix.transpose_mul_vec(&[2.0]), [2.0, 4.0, 6.0]);
+    }
+}
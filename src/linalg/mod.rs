@@ -1,5 +1,7 @@
 //! Various  linear algebra utilities used by other components of this library.
 
+pub use self::matrix::Matrix;
 pub use self::symmetric::SymmetricMatrix;
 
+mod matrix;
 mod symmetric;
\ No newline at end of file
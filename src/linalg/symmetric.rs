@@ -7,6 +7,7 @@ use num::{Float, zero};
 /// It can be indexed using matrices coordinates like `matrix[(i,j)]`,
 /// but for all `i` and `j` in range: `matrix[(i,j)]` and `matrix[(j,i)]`
 /// are the same memory location.
+#[derive(Clone, Debug, PartialEq)]
 pub struct SymmetricMatrix<F: Float> {
     size: usize,
     values: Vec<F>
@@ -28,6 +29,21 @@ impl<F: Float> SymmetricMatrix<F> {
     pub fn size(&self) -> usize {
         self.size
     }
+
+    /// Iterates over the stored coefficients, as `(i, j, value)` with
+    /// `i <= j`, each pair of indices thus appearing only once.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = (usize, usize, F)> + 'a {
+        (0..self.size).flat_map(move |j| (0..j+1).map(move |i| (i, j, self[(i, j)])))
+    }
+
+    /// The indices `j != i` such that `matrix[(i,j)]` is not zero, along
+    /// with this coefficient.
+    pub fn nonzero_neighbors(&self, i: usize) -> Vec<(usize, F)> {
+        (0..self.size).filter(|&j| j != i)
+                      .map(|j| (j, self[(i, j)]))
+                      .filter(|&(_, v)| v != zero())
+                      .collect()
+    }
 }
 
 fn order_tuple(t: (usize, usize)) -> (usize, usize) {
@@ -71,4 +87,17 @@ mod tests {
             }
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn neighbors() {
+        let mut matrix = SymmetricMatrix::<f32>::zeros(4);
+        matrix[(0, 2)] = 1.0;
+        matrix[(3, 2)] = -2.0;
+        matrix[(2, 2)] = 5.0;
+        assert_eq!(matrix.nonzero_neighbors(2), [(0, 1.0), (3, -2.0)]);
+        assert_eq!(matrix.nonzero_neighbors(1), []);
+        let stored = matrix.iter().filter(|&(_, _, v)| v != 0.0).collect::<Vec<_>>();
+        assert_eq!(stored, [(0, 2, 1.0), (2, 2, 5.0), (2, 3, -2.0)]);
+        assert_eq!(matrix.iter().count(), 10);
+    }
+}
@@ -0,0 +1,537 @@
+//! Loss functions.
+//!
+//! The training methods of the layers minimize the squared error between
+//! their outputs and the targets. Other losses can be minimized by
+//! backpropagation through `backprop_with_loss(..)`, which replaces the
+//! target given to the network by one for which the gradient of the squared
+//! error is the gradient of the chosen loss.
+
+use std::marker::PhantomData;
+
+use num::{Float, one, zero};
+
+use {Compute, Method, BackpropTrain, SupervisedTrain};
+
+/// A trait for loss functions, measuring the error between the outputs of a
+/// network and the targets.
+pub trait Loss<F: Float> {
+    /// The value of the loss.
+    fn value(&self, output: &[F], target: &[F]) -> F;
+    /// The gradient of the loss with regard to the outputs.
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F>;
+}
+
+fn target_at<F: Float>(target: &[F], j: usize) -> F {
+    target.get(j).cloned().unwrap_or(zero())
+}
+
+/// The squared error `sum_j((y_j - t_j)^2) / 2`, whose mean over a dataset
+/// is half the mean squared error.
+///
+/// This is the loss implicitly minimized by the training of the layers.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SquaredError;
+
+impl<F: Float> Loss<F> for SquaredError {
+    fn value(&self, output: &[F], target: &[F]) -> F {
+        let sum = output.iter().enumerate().fold(zero::<F>(), |s, (j, &y)| {
+            let e = y - target_at(target, j);
+            s + e * e
+        });
+        sum / F::from(2).unwrap()
+    }
+
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F> {
+        output.iter().enumerate().map(|(j, &y)| y - target_at(target, j)).collect()
+    }
+}
+
+/// The cross-entropy `-sum_j(t_j * ln(y_j))`, for outputs and targets that
+/// are probability distributions over classes.
+///
+/// It is meant for classifiers with a softmax output, for which its
+/// gradient with regard to the pre-activation values is simply `y - t`.
+/// Outputs are clamped away from `0.0` to avoid infinite values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CrossEntropy;
+
+fn clamp_probability<F: Float>(y: F) -> F {
+    y.max(F::from(1e-12).unwrap())
+}
+
+impl<F: Float> Loss<F> for CrossEntropy {
+    fn value(&self, output: &[F], target: &[F]) -> F {
+        output.iter().enumerate().fold(zero::<F>(), |s, (j, &y)| {
+            s - target_at(target, j) * clamp_probability(y).ln()
+        })
+    }
+
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F> {
+        output.iter().enumerate().map(|(j, &y)| -target_at(target, j) / clamp_probability(y)).collect()
+    }
+}
+
+/// The symmetric cross-entropy `alpha * CE + beta * RCE`, for classifiers
+/// trained on noisy labels.
+///
+/// The reverse cross-entropy `RCE = -sum_j(y_j * ln(t_j))` exchanges the
+/// roles of the outputs and the targets, `ln(0)` being taken as `-4.0`. It
+/// penalizes the outputs linearly, which keeps the classifier from fitting
+/// the mislabeled samples as the `CrossEntropy` (`CE`) alone would.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SymmetricCrossEntropy<F>(pub F, pub F);
+
+fn clamped_log<F: Float>(t: F) -> F {
+    if t > zero() { t.ln().max(F::from(-4).unwrap()) } else { F::from(-4).unwrap() }
+}
+
+impl<F: Float> Loss<F> for SymmetricCrossEntropy<F> {
+    fn value(&self, output: &[F], target: &[F]) -> F {
+        let SymmetricCrossEntropy(alpha, beta) = *self;
+        output.iter().enumerate().fold(zero::<F>(), |s, (j, &y)| {
+            let t = target_at(target, j);
+            s - alpha * t * clamp_probability(y).ln() - beta * y * clamped_log(t)
+        })
+    }
+
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F> {
+        let SymmetricCrossEntropy(alpha, beta) = *self;
+        output.iter().enumerate().map(|(j, &y)| {
+            let t = target_at(target, j);
+            -alpha * t / clamp_probability(y) - beta * clamped_log(t)
+        }).collect()
+    }
+}
+
+/// The generalized cross-entropy `(1 - p^q) / q`, where `p = sum_j(t_j * y_j)`
+/// is the probability given to the target class, for classifiers trained on
+/// noisy labels.
+///
+/// The parameter `q` is in `(0, 1]`: it tends to the `CrossEntropy` as `q`
+/// tends to `0.0`, and is the mean absolute error for `q = 1.0`, which is
+/// robust to mislabeled samples but slow to train. `0.7` is a common
+/// compromise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeneralizedCrossEntropy<F>(pub F);
+
+fn target_probability<F: Float>(output: &[F], target: &[F]) -> F {
+    let p = output.iter().enumerate().fold(zero::<F>(), |s, (j, &y)| s + target_at(target, j) * y);
+    clamp_probability(p)
+}
+
+impl<F: Float> Loss<F> for GeneralizedCrossEntropy<F> {
+    fn value(&self, output: &[F], target: &[F]) -> F {
+        let q = self.0;
+        (one::<F>() - target_probability(output, target).powf(q)) / q
+    }
+
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F> {
+        let q = self.0;
+        let scale = target_probability(output, target).powf(q - one());
+        (0..output.len()).map(|j| -scale * target_at(target, j)).collect()
+    }
+}
+
+/// The Huber loss, quadratic for errors smaller than `delta` and linear
+/// beyond, which makes regression robust to outliers.
+///
+/// For each output, with `e = y - t`, it is `e^2 / 2` if `|e| <= delta` and
+/// `delta * (|e| - delta / 2)` otherwise.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Huber<F>(pub F);
+
+impl<F: Float> Loss<F> for Huber<F> {
+    fn value(&self, output: &[F], target: &[F]) -> F {
+        let delta = self.0;
+        let two = F::from(2).unwrap();
+        output.iter().enumerate().fold(zero::<F>(), |s, (j, &y)| {
+            let e = (y - target_at(target, j)).abs();
+            s + if e <= delta { e * e / two } else { delta * (e - delta / two) }
+        })
+    }
+
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F> {
+        let delta = self.0;
+        output.iter().enumerate().map(|(j, &y)| {
+            (y - target_at(target, j)).max(-delta).min(delta)
+        }).collect()
+    }
+}
+
+/// The hinge loss `sum_j(max(0, 1 - t_j * y_j))` of support vector machines,
+/// for targets that are `-1.0` or `1.0`.
+///
+/// Outputs of the right sign beyond a margin of `1.0` do not contribute to
+/// the loss, and a missing target (`0.0`) ignores its output.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hinge;
+
+impl<F: Float> Loss<F> for Hinge {
+    fn value(&self, output: &[F], target: &[F]) -> F {
+        output.iter().enumerate().fold(zero::<F>(), |s, (j, &y)| {
+            s + (one::<F>() - target_at(target, j) * y).max(zero())
+        })
+    }
+
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F> {
+        output.iter().enumerate().map(|(j, &y)| {
+            let t = target_at(target, j);
+            if t * y < one() { -t } else { zero() }
+        }).collect()
+    }
+}
+
+/// An adapter weighting the contribution of each output to a loss, for
+/// example to counter the imbalance of the classes of a dataset.
+///
+/// Each output `j` has its contribution to the loss and its gradient
+/// multiplied by `weights[j]`, missing weights being `1.0`. This assumes the
+/// loss is a sum of terms for each output, as all the losses of this module
+/// are. With one-hot targets and the `CrossEntropy`, it multiplies the loss
+/// of each sample by the weight of its class.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Weighted<F, L> {
+    loss: L,
+    weights: Vec<F>
+}
+
+impl<F: Float, L: Loss<F>> Weighted<F, L> {
+    /// Weights `loss` with given weights, one per output.
+    pub fn new(loss: L, weights: Vec<F>) -> Weighted<F, L> {
+        Weighted {
+            loss,
+            weights
+        }
+    }
+
+    /// The weights of the outputs.
+    pub fn weights(&self) -> &[F] {
+        &self.weights
+    }
+
+    fn weight(&self, j: usize) -> F {
+        self.weights.get(j).cloned().unwrap_or(one())
+    }
+}
+
+impl<F: Float, L: Loss<F>> Loss<F> for Weighted<F, L> {
+    fn value(&self, output: &[F], target: &[F]) -> F {
+        output.iter().enumerate().fold(zero::<F>(), |s, (j, &y)| {
+            s + self.weight(j) * self.loss.value(&[y], &[target_at(target, j)])
+        })
+    }
+
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F> {
+        let gradient = self.loss.gradient(output, target);
+        gradient.into_iter().enumerate().map(|(j, g)| self.weight(j) * g).collect()
+    }
+}
+
+/// An adapter smoothing the targets given to a loss, typically the
+/// `CrossEntropy` of a classifier, which keeps it from becoming overconfident.
+///
+/// With `n` outputs, each target `t_j` is replaced by
+/// `(1 - epsilon) * t_j + epsilon / n` before being given to the loss.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LabelSmoothing<F, L> {
+    loss: L,
+    epsilon: F
+}
+
+impl<F: Float, L: Loss<F>> LabelSmoothing<F, L> {
+    /// Smooths the targets given to `loss` by `epsilon`.
+    ///
+    /// Panics if `epsilon` is not in the range `[0, 1)`.
+    pub fn new(loss: L, epsilon: F) -> LabelSmoothing<F, L> {
+        assert!(epsilon >= zero() && epsilon < one(), "The smoothing must be in [0, 1).");
+        LabelSmoothing {
+            loss,
+            epsilon
+        }
+    }
+
+    /// The smoothing of the targets.
+    pub fn epsilon(&self) -> F {
+        self.epsilon
+    }
+
+    fn smooth(&self, target: &[F], outputs: usize) -> Vec<F> {
+        let uniform = self.epsilon / F::from(outputs.max(1)).unwrap();
+        (0..outputs).map(|j| (one::<F>() - self.epsilon) * target_at(target, j) + uniform).collect()
+    }
+}
+
+impl<F: Float, L: Loss<F>> Loss<F> for LabelSmoothing<F, L> {
+    fn value(&self, output: &[F], target: &[F]) -> F {
+        self.loss.value(output, &self.smooth(target, output.len()))
+    }
+
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F> {
+        self.loss.gradient(output, &self.smooth(target, output.len()))
+    }
+}
+
+/// An adapter penalizing the confidence of a classifier, by subtracting
+/// from a loss the entropy `-sum_j(y_j * ln(y_j))` of its outputs weighted
+/// by `beta`.
+///
+/// Low-entropy outputs, putting all the probability on a single class, are
+/// penalized, which improves the calibration of small classifiers. Given to
+/// `trainer::Trainer::with_loss(..)`, it acts as an entropy bonus during
+/// training.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfidencePenalty<F, L> {
+    loss: L,
+    beta: F
+}
+
+impl<F: Float, L: Loss<F>> ConfidencePenalty<F, L> {
+    /// Penalizes the confidence of the outputs, weighted by `beta`, in
+    /// addition to `loss`.
+    pub fn new(loss: L, beta: F) -> ConfidencePenalty<F, L> {
+        ConfidencePenalty {
+            loss,
+            beta
+        }
+    }
+
+    /// The weight of the penalty.
+    pub fn beta(&self) -> F {
+        self.beta
+    }
+}
+
+impl<F: Float, L: Loss<F>> Loss<F> for ConfidencePenalty<F, L> {
+    fn value(&self, output: &[F], target: &[F]) -> F {
+        let entropy = output.iter().fold(zero::<F>(), |s, &y| s - y * clamp_probability(y).ln());
+        self.loss.value(output, target) - self.beta * entropy
+    }
+
+    fn gradient(&self, output: &[F], target: &[F]) -> Vec<F> {
+        let gradient = self.loss.gradient(output, target);
+        gradient.into_iter().zip(output).map(|(g, &y)| {
+            g + self.beta * (clamp_probability(y).ln() + one())
+        }).collect()
+    }
+}
+
+/// Performs one step of backpropagation training of `network` on given
+/// input and target, minimizing `loss` rather than the squared error.
+///
+/// Returns the value to feed to the previous layer, like
+/// `BackpropTrain::backprop_train(..)`.
+pub fn backprop_with_loss<F, N, M, L>(network: &mut N, rule: &M, loss: &L, input: &[F], target: &[F])
+    -> Vec<F>
+    where F: Float,
+          N: BackpropTrain<F, M> + Compute<F>,
+          M: Method,
+          L: Loss<F>
+{
+    let output = network.compute(input);
+    let gradient = loss.gradient(&output, target);
+    let target = output.iter().zip(gradient).map(|(&y, g)| y - g).collect::<Vec<_>>();
+    network.backprop_train(rule, input, &target)
+}
+
+/// An adapter making the training of a network minimize a given loss, see
+/// `backprop_with_loss(..)`.
+///
+/// It can be given to a `trainer::Trainer` like any other network.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WithLoss<F, N, L> {
+    _marker: PhantomData<F>,
+    network: N,
+    loss: L
+}
+
+impl<F: Float, N: Compute<F>, L: Loss<F>> WithLoss<F, N, L> {
+    /// Wraps `network` so that its training minimizes `loss`.
+    pub fn new(network: N, loss: L) -> WithLoss<F, N, L> {
+        WithLoss { _marker: PhantomData, network, loss }
+    }
+
+    /// The wrapped network.
+    pub fn network(&self) -> &N {
+        &self.network
+    }
+
+    /// Consumes the adapter, returning the wrapped network.
+    pub fn into_network(self) -> N {
+        self.network
+    }
+
+    /// The value of the loss for given input and target.
+    pub fn loss(&self, input: &[F], target: &[F]) -> F {
+        self.loss.value(&self.network.compute(input), target)
+    }
+}
+
+impl<F: Float, N: Compute<F>, L: Loss<F>> Compute<F> for WithLoss<F, N, L> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.network.compute(input)
+    }
+
+    fn input_size(&self) -> usize {
+        self.network.input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.network.output_size()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.network.set_training(training);
+    }
+}
+
+impl<F, N, M, L> BackpropTrain<F, M> for WithLoss<F, N, L>
+    where F: Float,
+          N: BackpropTrain<F, M> + Compute<F>,
+          M: Method,
+          L: Loss<F>
+{
+    fn backprop_train(&mut self, rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        backprop_with_loss(&mut self.network, rule, &self.loss, input, target)
+    }
+}
+
+impl<F, N, M, L> SupervisedTrain<F, M> for WithLoss<F, N, L>
+    where F: Float,
+          N: BackpropTrain<F, M> + Compute<F>,
+          M: Method,
+          L: Loss<F>
+{
+    fn supervised_train(&mut self, rule: &M, input: &[F], target: &[F]) {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Compute, FeedforwardLayer, SupervisedTrain};
+    use activations::{Activation, softmax};
+    use training::GradientDescent;
+
+    use super::{Loss, SquaredError, CrossEntropy, Huber, Hinge, Weighted, WithLoss};
+    use super::{LabelSmoothing, ConfidencePenalty, SymmetricCrossEntropy, GeneralizedCrossEntropy};
+
+    #[test]
+    fn values() {
+        assert_eq!(SquaredError.value(&[1.0f64, 2.0], &[0.0, 0.0]), 2.5);
+        assert_eq!(SquaredError.gradient(&[1.0f64, 2.0], &[0.0, 1.0]), [1.0, 1.0]);
+        assert!((CrossEntropy.value(&[0.25f64, 0.75], &[0.0, 1.0]) + 0.75f64.ln()).abs() < 1e-12);
+        assert_eq!(CrossEntropy.gradient(&[0.25f64, 0.5], &[0.0, 1.0]), [0.0, -2.0]);
+        assert_eq!(Huber(1.0).value(&[0.5f64, 3.0], &[0.0, 0.0]), 0.125 + 2.5);
+        assert_eq!(Huber(1.0).gradient(&[0.5f64, 3.0, -2.0], &[0.0, 0.0, 0.0]), [0.5, 1.0, -1.0]);
+        assert_eq!(Hinge.value(&[0.5f64, 2.0, 0.5], &[1.0, 1.0, -1.0]), 2.0);
+        assert_eq!(Hinge.gradient(&[0.5f64, 2.0, 0.5, 1.0], &[1.0, 1.0, -1.0]), [-1.0, 0.0, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn weighted() {
+        let weighted = Weighted::new(SquaredError, vec![2.0f64, 0.0]);
+        assert_eq!(weighted.value(&[1.0, 1.0, 1.0], &[0.0, 0.0, 0.0]), 1.5);
+        assert_eq!(weighted.gradient(&[1.0, 1.0, 1.0], &[0.0, 0.0, 0.0]), [2.0, 0.0, 1.0]);
+        // the cross-entropy of a sample is weighted by its class
+        let weighted = Weighted::new(CrossEntropy, vec![1.0f64, 4.0]);
+        let (y, t) = ([0.25, 0.75], [0.0, 1.0]);
+        assert_eq!(weighted.value(&y, &t), 4.0 * CrossEntropy.value(&y, &t));
+
+        // a null weight leaves its output untrained
+        let mut net = WithLoss::new(FeedforwardLayer::new(1, 2, Activation::Identity),
+                                    Weighted::new(SquaredError, vec![1.0, 0.0]));
+        net.supervised_train(&GradientDescent::new(0.5).unwrap(), &[1.0f64], &[1.0, 1.0]);
+        assert_eq!(net.compute(&[1.0]), [1.0, 0.0]);
+    }
+
+    #[test]
+    fn calibration() {
+        let (y, t) = ([0.25f64, 0.75], [0.0, 1.0]);
+        let smoothed = LabelSmoothing::new(CrossEntropy, 0.2);
+        assert!((smoothed.value(&y, &t) + 0.1 * 0.25f64.ln() + 0.9 * 0.75f64.ln()).abs() < 1e-12);
+        let penalized = ConfidencePenalty::new(CrossEntropy, 0.5);
+        let entropy = -0.25 * 0.25f64.ln() - 0.75 * 0.75f64.ln();
+        assert!((penalized.value(&y, &t) - CrossEntropy.value(&y, &t) + 0.5 * entropy).abs() < 1e-12);
+        let gradient = penalized.gradient(&y, &t);
+        assert!((gradient[0] - 0.5 * (0.25f64.ln() + 1.0)).abs() < 1e-12);
+
+        // smoothing keeps a classifier from becoming overconfident
+        let rule = GradientDescent::new(0.5).unwrap();
+        let mut plain = WithLoss::new(FeedforwardLayer::new(1, 2, softmax()), CrossEntropy);
+        let mut smoothed = WithLoss::new(FeedforwardLayer::new(1, 2, softmax()), LabelSmoothing::new(CrossEntropy, 0.2));
+        for _ in 0..500 {
+            plain.supervised_train(&rule, &[1.0f64], &[1.0, 0.0]);
+            smoothed.supervised_train(&rule, &[1.0f64], &[1.0, 0.0]);
+        }
+        assert!(plain.compute(&[1.0])[0] > 0.99);
+        assert!((smoothed.compute(&[1.0])[0] - 0.9).abs() < 0.01);
+    }
+
+    #[test]
+    fn noisy_labels() {
+        let (y, t) = ([0.25f64, 0.75], [0.0, 1.0]);
+        let symmetric = SymmetricCrossEntropy(0.5, 1.0);
+        assert!((symmetric.value(&y, &t) - 0.5 * CrossEntropy.value(&y, &t) - 4.0 * 0.25).abs() < 1e-12);
+        assert_eq!(symmetric.gradient(&y, &t), [4.0, -0.5 / 0.75]);
+        let generalized = GeneralizedCrossEntropy(1.0);
+        assert!((generalized.value(&y, &t) - 0.25).abs() < 1e-12);
+        assert_eq!(generalized.gradient(&y, &t), [0.0, -1.0]);
+        let generalized = GeneralizedCrossEntropy(0.5);
+        assert!((generalized.value(&y, &t) - 2.0 * (1.0 - 0.75f64.sqrt())).abs() < 1e-12);
+
+        // with 30% of flipped labels, the cross-entropy fits the noise while
+        // the robust losses stay confident in the right class
+        let mut labels = vec![[1.0f64, 0.0]; 7];
+        labels.extend(vec![[0.0, 1.0]; 3]);
+        let rule = GradientDescent::new(0.1).unwrap();
+        let mut plain = WithLoss::new(FeedforwardLayer::new(1, 2, softmax()), CrossEntropy);
+        let mut symmetric = WithLoss::new(FeedforwardLayer::new(1, 2, softmax()), SymmetricCrossEntropy(0.1, 1.0));
+        let mut generalized = WithLoss::new(FeedforwardLayer::new(1, 2, softmax()), GeneralizedCrossEntropy(0.7));
+        for _ in 0..300 {
+            for label in &labels {
+                plain.supervised_train(&rule, &[1.0], label);
+                symmetric.supervised_train(&rule, &[1.0], label);
+                generalized.supervised_train(&rule, &[1.0], label);
+            }
+        }
+        assert!(plain.compute(&[1.0])[0] < 0.8);
+        assert!(symmetric.compute(&[1.0])[0] > 0.9);
+        assert!(generalized.compute(&[1.0])[0] > 0.9);
+    }
+
+    #[test]
+    fn robust_regression() {
+        // fit y = x with an outlier: the Huber loss is barely affected by it
+        let samples = [(0.0f64, 0.0), (1.0, 1.0), (2.0, 2.0), (3.0, 3.0), (4.0, 40.0)];
+        let mut squared = WithLoss::new(FeedforwardLayer::new(1, 1, Activation::Identity), SquaredError);
+        let mut huber = WithLoss::new(FeedforwardLayer::new(1, 1, Activation::Identity), Huber(0.5));
+        let rule = GradientDescent::new(0.01).unwrap();
+        for _ in 0..2000 {
+            for &(x, y) in &samples {
+                squared.supervised_train(&rule, &[x], &[y]);
+                huber.supervised_train(&rule, &[x], &[y]);
+            }
+        }
+        let huber_error = (huber.compute(&[2.0])[0] - 2.0).abs();
+        let squared_error = (squared.compute(&[2.0])[0] - 2.0).abs();
+        assert!(huber_error < 0.5);
+        assert!(squared_error > 2.0 * huber_error);
+    }
+
+    #[test]
+    fn softmax_cross_entropy() {
+        // with a softmax, the error on the pre-activation values is y - t
+        let mut layer = FeedforwardLayer::new(1, 2, softmax());
+        let mut reference = FeedforwardLayer::new(1, 2, Activation::Identity);
+        let mut wrapped = WithLoss::new(FeedforwardLayer::new(1, 2, softmax()), CrossEntropy);
+        wrapped.supervised_train(&GradientDescent::new(1.0).unwrap(), &[1.0f64], &[1.0, 0.0]);
+        layer.supervised_train(&GradientDescent::new(1.0).unwrap(), &[1.0f64], &[1.0, 0.0]);
+        // identity outputs are 0, softmax outputs are 0.5: same error as
+        // an identity layer with a target shifted by 0.5
+        reference.supervised_train(&GradientDescent::new(1.0).unwrap(), &[1.0f64], &[0.5, -0.5]);
+        assert_eq!(wrapped.network().weights(), reference.weights());
+        assert!(wrapped.network().weights()[(0, 0)] > layer.weights()[(0, 0)]);
+        assert!(wrapped.loss(&[1.0], &[1.0, 0.0]) < 0.5f64.ln().abs());
+        assert_eq!(wrapped.compute(&[1.0]).len(), 2);
+    }
+}
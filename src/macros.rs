@@ -0,0 +1,93 @@
+//! Macros
+
+/// Builds a network from a list of layers, chained from the input to the
+/// output.
+///
+/// Each item of the list is either:
+///
+/// - `dense(inputs, outputs, activation)`, a `FeedforwardLayer` with weights
+///   drawn by `Initializer::Xavier`,
+/// - `parallel([...], [...])`, a `util::Parallel` of the two networks
+///   described by the bracketed lists,
+/// - any other expression evaluating to a network.
+///
+/// The network is the nesting of `util::Chain`s of these layers, whose type
+/// never needs to be written:
+///
+/// ```
+/// #[macro_use] extern crate silinapse;
+/// use silinapse::Compute;
+/// use silinapse::activations::{identity, sigmoid};
+/// use silinapse::util::Dropout;
+///
+/// # fn main() {
+/// let network = network![
+///     dense(4, 8, sigmoid()),
+///     Dropout::new(8, 0.5),
+///     parallel([dense(8, 2, identity())], [dense(8, 2, sigmoid())]),
+///     dense(4, 1, identity())
+/// ];
+/// assert_eq!(network.compute(&[0.0f32; 4]).len(), 1);
+/// # }
+/// ```
+///
+/// It can also be invoked by its path, without importing it:
+///
+/// ```
+/// extern crate silinapse;
+/// use silinapse::activations::identity;
+///
+/// # fn main() {
+/// let network = silinapse::network![dense(2, 3, identity::<f32>()), dense(3, 1, identity())];
+/// # }
+/// ```
+#[macro_export]
+macro_rules! network {
+    (@item dense($inputs:expr, $outputs:expr, $activation:expr)) => {
+        $crate::FeedforwardLayer::new_init($inputs, $outputs, $activation,
+                                           $crate::init::Initializer::Xavier)
+    };
+    (@item parallel([$($first:tt)*], [$($second:tt)*])) => {
+        $crate::util::Parallel::new($crate::network!(@layers $($first)*), $crate::network!(@layers $($second)*))
+    };
+    (@layers dense $args:tt) => { $crate::network!(@item dense $args) };
+    (@layers dense $args:tt, $($rest:tt)+) => {
+        $crate::util::Chain::new($crate::network!(@item dense $args), $crate::network!(@layers $($rest)+))
+    };
+    (@layers parallel $args:tt) => { $crate::network!(@item parallel $args) };
+    (@layers parallel $args:tt, $($rest:tt)+) => {
+        $crate::util::Chain::new($crate::network!(@item parallel $args), $crate::network!(@layers $($rest)+))
+    };
+    (@layers $layer:expr) => { $layer };
+    (@layers $layer:expr, $($rest:tt)+) => {
+        $crate::util::Chain::new($layer, $crate::network!(@layers $($rest)+))
+    };
+    ($($layers:tt)+) => { $crate::network!(@layers $($layers)+) };
+}
+
+#[cfg(test)]
+mod tests {
+    use {Compute, FeedforwardLayer, Matrix};
+    use activations::{identity, sigmoid, Activation};
+    use util::{Chain, Identity, Parallel};
+
+    #[test]
+    fn network() {
+        let single = network![dense(3, 2, sigmoid::<f32>())];
+        assert_eq!((single.input_size(), single.output_size()), (3, 2));
+
+        let network = network![
+            dense(4, 8, sigmoid()),
+            parallel([dense(8, 2, identity())], [dense(8, 3, sigmoid()), Identity::new(3)]),
+            dense(5, 1, identity())
+        ];
+        assert_eq!((network.input_size(), network.output_size()), (4, 1));
+        assert_eq!(network.compute(&[1.0f32, 0.0, -1.0, 0.5]).len(), 1);
+
+        let sum = FeedforwardLayer::from_matrices(Matrix::from_vec(1, 2, vec![1.0, 1.0]),
+                                                  vec![0.0], Activation::Identity);
+        let expanded = Chain::new(Parallel::new(Identity::new(1), Identity::new(1)), sum.clone());
+        let network = network![parallel([Identity::new(1)], [Identity::new(1)]), sum];
+        assert_eq!(network.compute(&[2.0f64, 3.0]), expanded.compute(&[2.0, 3.0]));
+    }
+}
@@ -0,0 +1,277 @@
+//! Maxout layers
+
+use std::cmp::min;
+
+use num::{Float, zero};
+
+use {Compute, Differentiable, BackpropTrain, SupervisedTrain};
+use training::{GradientDescent, Momentum, Adam, Adagrad, Adadelta, Regularized, GradientMethod, OptimizerState};
+
+/// A maxout layer
+///
+/// Each output of this layer is associated with `k` linear pieces, each of
+/// them having all the inputs connected to it, and its value is the maximum
+/// of these pieces.
+///
+/// The effective computation for output `j` is thus, if `X` is the vector of
+/// inputs, `W_jp` the weights vector of piece `p` of output `j` and `b_jp` its
+/// bias:
+///
+/// ```text
+/// y_j = max_p( W_jp . X + b_jp )
+/// ```
+///
+/// This is a learned piecewise linear activation, and there is no separate
+/// activation function. The training only updates the piece that won the
+/// maximum, for each output.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaxoutLayer<F: Float> {
+    inputs: usize,
+    outputs: usize,
+    pieces: usize,
+    coeffs: Vec<F>,
+    biases: Vec<F>,
+    coeffs_state: OptimizerState<F>,
+    biases_state: OptimizerState<F>
+}
+
+impl<F: Float> MaxoutLayer<F> {
+    /// Creates a new maxout layer with `pieces` linear pieces per output,
+    /// with all its weights and biases set to 0.
+    ///
+    /// As all the pieces of an output are then identical, you most likely
+    /// want to use `new_from(..)` instead if the layer is to be trained.
+    pub fn new(inputs: usize, outputs: usize, pieces: usize) -> MaxoutLayer<F> {
+        assert!(pieces > 0, "A maxout layer needs at least one piece per output.");
+        MaxoutLayer {
+            inputs,
+            outputs,
+            pieces,
+            coeffs: vec![zero(); inputs*outputs*pieces],
+            biases: vec![zero(); outputs*pieces],
+            coeffs_state: OptimizerState::new(),
+            biases_state: OptimizerState::new()
+        }
+    }
+
+    /// Creates a new maxout layer with `pieces` linear pieces per output, with
+    /// all its weights and biases generated by provided closure (for example
+    /// a random number generator).
+    pub fn new_from<G>(inputs: usize,
+                       outputs: usize,
+                       pieces: usize,
+                       mut generator: G)
+        -> MaxoutLayer<F>
+        where G: FnMut() -> F
+    {
+        assert!(pieces > 0, "A maxout layer needs at least one piece per output.");
+        MaxoutLayer {
+            inputs,
+            outputs,
+            pieces,
+            coeffs: (0..inputs*outputs*pieces).map(|_| generator()).collect(),
+            biases: (0..outputs*pieces).map(|_| generator()).collect(),
+            coeffs_state: OptimizerState::new(),
+            biases_state: OptimizerState::new()
+        }
+    }
+
+    /// The number of linear pieces per output.
+    pub fn pieces(&self) -> usize {
+        self.pieces
+    }
+
+    /// Clears the state kept by the training method between training steps,
+    /// like the velocities of a gradient descent with momentum.
+    pub fn reset_optimizer(&mut self) {
+        self.coeffs_state.reset();
+        self.biases_state.reset();
+    }
+
+    /// Computes the outputs, as well as the index of the winning piece of
+    /// each of them.
+    fn compute_with_winners(&self, input: &[F]) -> (Vec<F>, Vec<usize>) {
+        let mut out = Vec::with_capacity(self.outputs);
+        let mut winners = Vec::with_capacity(self.outputs);
+        for j in 0..self.outputs {
+            let mut best = (F::neg_infinity(), 0);
+            for p in 0..self.pieces {
+                let piece = j*self.pieces + p;
+                let mut val = self.biases[piece];
+                for (i, &x) in input.iter().enumerate().take(self.inputs) {
+                    val = val + self.coeffs[piece*self.inputs + i] * x;
+                }
+                if val > best.0 { best = (val, p); }
+            }
+            out.push(best.0);
+            winners.push(best.1);
+        }
+        (out, winners)
+    }
+}
+
+impl<F: Float> Compute<F> for MaxoutLayer<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.compute_with_winners(input).0
+    }
+
+    fn input_size(&self) -> usize {
+        self.inputs
+    }
+
+    fn output_size(&self) -> usize {
+        self.outputs
+    }
+}
+
+impl<F: Float> Differentiable<F> for MaxoutLayer<F> {
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let (_, winners) = self.compute_with_winners(input);
+        let mut gradient = vec![zero(); input.len()];
+        for (j, &winner) in winners.iter().enumerate() {
+            let piece = j*self.pieces + winner;
+            let delta = output_gradient.get(j).cloned().unwrap_or(zero());
+            for (i, g) in gradient.iter_mut().enumerate().take(self.inputs) {
+                *g = *g + self.coeffs[piece*self.inputs + i] * delta;
+            }
+        }
+        gradient
+    }
+}
+
+impl<F: Float, M: GradientMethod<F>> BackpropTrain<F, M> for MaxoutLayer<F> {
+    fn backprop_train(&mut self,
+                      rule: &M,
+                      input: &[F],
+                      target: &[F])
+        -> Vec<F>
+    {
+        let (out, winners) = self.compute_with_winners(input);
+        let mut returned = input.to_owned();
+        let mut coeffs_gradient = vec![zero(); self.coeffs.len()];
+        let mut biases_gradient = vec![zero(); self.biases.len()];
+        for j in 0..self.outputs {
+            // only the winning piece receives the gradient
+            let piece = j*self.pieces + winners[j];
+            let delta = out[j] - target.get(j).cloned().unwrap_or(zero());
+            for i in 0..min(self.inputs, input.len()) {
+                returned[i] = returned[i] - self.coeffs[piece*self.inputs + i] * delta;
+                coeffs_gradient[piece*self.inputs + i] = input[i] * delta;
+            }
+            biases_gradient[piece] = delta;
+        }
+        rule.regularize(&self.coeffs, &mut coeffs_gradient);
+        rule.update(&mut self.coeffs, &coeffs_gradient, &mut self.coeffs_state);
+        rule.update(&mut self.biases, &biases_gradient, &mut self.biases_state);
+        returned
+    }
+}
+
+impl<F: Float> SupervisedTrain<F, GradientDescent<F>> for MaxoutLayer<F> {
+    fn supervised_train(&mut self,
+                        rule: &GradientDescent<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F: Float> SupervisedTrain<F, Momentum<F>> for MaxoutLayer<F> {
+    fn supervised_train(&mut self,
+                        rule: &Momentum<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F: Float> SupervisedTrain<F, Adam<F>> for MaxoutLayer<F> {
+    fn supervised_train(&mut self,
+                        rule: &Adam<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F: Float> SupervisedTrain<F, Adagrad<F>> for MaxoutLayer<F> {
+    fn supervised_train(&mut self,
+                        rule: &Adagrad<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F: Float> SupervisedTrain<F, Adadelta<F>> for MaxoutLayer<F> {
+    fn supervised_train(&mut self,
+                        rule: &Adadelta<F>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F: Float, M: GradientMethod<F>> SupervisedTrain<F, Regularized<F, M>> for MaxoutLayer<F> {
+    fn supervised_train(&mut self,
+                        rule: &Regularized<F, M>,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use {Compute, SupervisedTrain};
+    use training::GradientDescent;
+
+    use super::MaxoutLayer;
+
+    #[test]
+    fn basics() {
+        let layer = MaxoutLayer::<f32>::new(7, 3, 4);
+        assert_eq!(layer.input_size(), 7);
+        assert_eq!(layer.output_size(), 3);
+        assert_eq!(layer.pieces(), 4);
+    }
+
+    #[test]
+    fn compute() {
+        // pieces alternate between weights 1.0 and -1.0: this is the absolute value
+        let mut acc = 0;
+        let layer = MaxoutLayer::new_from(1, 1, 2, || {
+            acc += 1;
+            // coeffs are generated first, then biases
+            if acc > 2 { 0.0f32 } else if acc % 2 == 0 { 1.0 } else { -1.0 }
+        });
+        assert_eq!(layer.compute(&[3.0]), [3.0]);
+        assert_eq!(layer.compute(&[-2.0]), [2.0]);
+    }
+
+    #[test]
+    fn supervised_train() {
+        // learn the absolute value function
+        let mut acc = 0;
+        let mut layer = MaxoutLayer::new_from(1, 1, 2, || {
+            acc += 1;
+            if acc % 2 == 0 { 0.3f32 } else { -0.1 }
+        });
+        let rule = GradientDescent::new(0.05f32).unwrap();
+        for _ in 0..200 {
+            for &x in &[-2.0f32, -1.0, -0.5, 0.5, 1.0, 2.0] {
+                layer.supervised_train(&rule, &[x], &[x.abs()]);
+            }
+        }
+        for &x in &[-1.5f32, 1.5] {
+            assert!((layer.compute(&[x])[0] - 1.5).abs() < 0.05);
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Multi-layer perceptrons
+
+use num::Float;
+
+use {Compute, Differentiable, BackpropTrain, SupervisedTrain};
+use activations::Activation;
+use feedforward::FeedforwardLayer;
+use init::Initializer;
+use training::GradientMethod;
+
+/// A multi-layer perceptron
+///
+/// This is a stack of `FeedforwardLayer`s using `Activation`s, the output of
+/// each layer being the input of the next one. It computes and trains like
+/// the equivalent nesting of `util::Chain`s, but its type does not depend on
+/// its number of layers, and it is most easily created with `Mlp::builder(..)`:
+///
+/// ```
+/// # use silinapse::Mlp;
+/// # use silinapse::activations::Activation;
+/// # use silinapse::init::Initializer;
+/// let mlp = Mlp::<f32>::builder(784).hidden(128, Activation::Relu)
+///                                   .hidden(64, Activation::Relu)
+///                                   .output(10, Activation::Softmax)
+///                                   .init(Initializer::He)
+///                                   .build();
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mlp<F: Float> {
+    layers: Vec<FeedforwardLayer<F, Activation<F>>>
+}
+
+impl<F: Float> Mlp<F> {
+    /// Starts the description of a new multi-layer perceptron with given
+    /// number of inputs.
+    pub fn builder(inputs: usize) -> MlpBuilder<F> {
+        MlpBuilder {
+            inputs,
+            layers: Vec::new(),
+            init: Initializer::Xavier
+        }
+    }
+
+    /// Creates a multi-layer perceptron from its layers, each of them having
+    /// as many inputs as the previous one has outputs.
+    pub fn from_layers(layers: Vec<FeedforwardLayer<F, Activation<F>>>) -> Mlp<F> {
+        assert!(!layers.is_empty(), "A multi-layer perceptron needs at least one layer.");
+        for pair in layers.windows(2) {
+            assert!(pair[0].output_size() == pair[1].input_size(),
+                    "Each layer must have as many inputs as the previous one has outputs.");
+        }
+        Mlp { layers }
+    }
+
+    /// The layers of the perceptron, from the input to the output.
+    pub fn layers(&self) -> &[FeedforwardLayer<F, Activation<F>>] {
+        &self.layers
+    }
+
+    /// Mutable access to the layers of the perceptron.
+    pub fn layers_mut(&mut self) -> &mut [FeedforwardLayer<F, Activation<F>>] {
+        &mut self.layers
+    }
+
+    /// Clears the optimizer state of all the layers, see
+    /// `FeedforwardLayer::reset_optimizer()`.
+    pub fn reset_optimizer(&mut self) {
+        for layer in &mut self.layers {
+            layer.reset_optimizer();
+        }
+    }
+
+    // the inputs of each layer, followed by the output of the last one
+    fn activations(&self, input: &[F]) -> Vec<Vec<F>> {
+        let mut values = vec![input.to_owned()];
+        for layer in &self.layers {
+            let next = layer.compute(values.last().unwrap());
+            values.push(next);
+        }
+        values
+    }
+}
+
+impl<F: Float> Compute<F> for Mlp<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.layers.iter().fold(input.to_owned(), |x, layer| layer.compute(&x))
+    }
+
+    fn input_size(&self) -> usize {
+        self.layers[0].input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.layers[self.layers.len() - 1].output_size()
+    }
+}
+
+impl<F: Float> Differentiable<F> for Mlp<F> {
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let values = self.activations(input);
+        self.layers.iter().enumerate().rev().fold(output_gradient.to_owned(), |gradient, (i, layer)| {
+            layer.input_gradient(&values[i], &gradient)
+        })
+    }
+}
+
+impl<F: Float, M: GradientMethod<F>> BackpropTrain<F, M> for Mlp<F> {
+    fn backprop_train(&mut self, rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let values = self.activations(input);
+        self.layers.iter_mut().enumerate().rev().fold(target.to_owned(), |target, (i, layer)| {
+            layer.backprop_train(rule, &values[i], &target)
+        })
+    }
+}
+
+impl<F: Float, M: GradientMethod<F>> SupervisedTrain<F, M> for Mlp<F> {
+    fn supervised_train(&mut self,
+                        rule: &M,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+/// A builder of `Mlp`, see `Mlp::builder(..)`.
+///
+/// The weights are drawn with `Initializer::Xavier` unless specified
+/// otherwise, and the biases are set to 0.
+pub struct MlpBuilder<F: Float> {
+    inputs: usize,
+    layers: Vec<(usize, Activation<F>)>,
+    init: Initializer<F>
+}
+
+impl<F: Float> MlpBuilder<F> {
+    /// Adds a hidden layer with given number of neurons.
+    pub fn hidden(mut self, neurons: usize, activation: Activation<F>) -> MlpBuilder<F> {
+        self.layers.push((neurons, activation));
+        self
+    }
+
+    /// Adds the output layer, with given number of outputs.
+    ///
+    /// This is the same as adding a last hidden layer.
+    pub fn output(self, outputs: usize, activation: Activation<F>) -> MlpBuilder<F> {
+        self.hidden(outputs, activation)
+    }
+
+    /// Sets the initialization scheme of the weights.
+    pub fn init(mut self, init: Initializer<F>) -> MlpBuilder<F> {
+        self.init = init;
+        self
+    }
+
+    /// Creates the perceptron.
+    ///
+    /// Panics if no layer was added, like `Mlp::from_layers(..)`.
+    pub fn build(self) -> Mlp<F> {
+        let mut inputs = self.inputs;
+        let init = self.init;
+        let layers = self.layers.into_iter().map(|(outputs, activation)| {
+            let layer = FeedforwardLayer::new_init(inputs, outputs, activation, init);
+            inputs = outputs;
+            layer
+        }).collect();
+        Mlp::from_layers(layers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Compute, Differentiable, FeedforwardLayer, SupervisedTrain};
+    use activations::Activation;
+    use init::Initializer;
+    use training::GradientDescent;
+    use util::Chain;
+
+    use super::Mlp;
+
+    #[test]
+    fn builder() {
+        let mlp = Mlp::<f32>::builder(4).hidden(8, Activation::Relu)
+                                        .output(2, Activation::Softmax)
+                                        .init(Initializer::He)
+                                        .build();
+        assert_eq!(mlp.layers().len(), 2);
+        assert_eq!((mlp.input_size(), mlp.output_size()), (4, 2));
+        assert_eq!(mlp.layers()[1].input_size(), 8);
+        assert!(mlp.layers()[1].weights().as_slice().iter().any(|&w| w != 0.0));
+    }
+
+    #[test]
+    fn same_as_chain() {
+        let mut acc = 0;
+        let mut random = move || { acc += 1; ((acc * 7) % 5) as f64 / 5.0 - 0.4 };
+        let first = FeedforwardLayer::new_from(3, 4, Activation::Sigmoid, &mut random);
+        let second = FeedforwardLayer::new_from(4, 2, Activation::Identity, &mut random);
+        let mut chain = Chain::new(first.clone(), second.clone());
+        let mut mlp = Mlp::from_layers(vec![first, second]);
+        let input = [0.5, -1.0, 0.25];
+        assert_eq!(mlp.compute(&input), chain.compute(&input));
+        assert_eq!(mlp.input_gradient(&input, &[1.0, -1.0]), chain.input_gradient(&input, &[1.0, -1.0]));
+        for _ in 0..10 {
+            mlp.supervised_train(&GradientDescent::new(0.1).unwrap(), &input, &[1.0, 0.0]);
+            chain.supervised_train(&GradientDescent::new(0.1).unwrap(), &input, &[1.0, 0.0]);
+        }
+        assert_eq!(mlp.compute(&input), chain.compute(&input));
+    }
+}
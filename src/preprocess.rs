@@ -0,0 +1,512 @@
+//! Preprocessing of the inputs of networks.
+//!
+//! Real datasets rarely fit a network as is. The transformations of this
+//! module are fitted on a dataset, given as a slice of samples, and then
+//! implement `Compute` so that they can be chained in front of a network.
+
+use std::cmp::Ordering;
+
+use num::{Float, one, zero};
+
+use {Compute, Differentiable, Method, BackpropTrain};
+use robustness::IntervalCompute;
+
+/*
+ * Missing values
+ */
+
+/// The value replacing the missing values of a feature.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Imputation<F: Float> {
+    /// The mean of the known values of the feature.
+    Mean,
+    /// The median of the known values of the feature.
+    Median,
+    /// A fixed value.
+    Constant(F)
+}
+
+/// Replaces missing values, represented as NaN, by a per-feature value.
+///
+/// Optionally, a missing-indicator channel is appended to the output for
+/// each feature, set to `1.0` if the value was missing and `0.0` otherwise,
+/// so that the network can still tell imputed values apart.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Imputer<F: Float> {
+    values: Vec<F>,
+    indicators: bool
+}
+
+impl<F: Float> Imputer<F> {
+    /// Fits an imputer on given samples, which all have `features` values.
+    ///
+    /// Features with no known value in the samples are replaced by `0.0`
+    /// with the `Mean` and `Median` strategies.
+    pub fn fit(data: &[Vec<F>], features: usize, imputation: Imputation<F>, indicators: bool)
+        -> Imputer<F>
+    {
+        let values = (0..features).map(|i| {
+            let mut known = data.iter()
+                                .filter_map(|x| x.get(i).cloned())
+                                .filter(|x| !x.is_nan())
+                                .collect::<Vec<_>>();
+            match imputation {
+                Imputation::Constant(c) => c,
+                _ if known.is_empty() => zero(),
+                Imputation::Mean => {
+                    known.iter().fold(zero::<F>(), |s, &x| s + x) / F::from(known.len()).unwrap()
+                },
+                Imputation::Median => {
+                    known.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+                    let n = known.len();
+                    if n % 2 == 1 {
+                        known[n / 2]
+                    } else {
+                        (known[n / 2 - 1] + known[n / 2]) / F::from(2).unwrap()
+                    }
+                }
+            }
+        }).collect();
+        Imputer {
+            values,
+            indicators
+        }
+    }
+
+    /// The values replacing the missing values of each feature.
+    pub fn values(&self) -> &[F] {
+        &self.values
+    }
+}
+
+impl<F: Float> Compute<F> for Imputer<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let n = self.values.len();
+        let mut out = Vec::with_capacity(self.output_size());
+        for i in 0..n {
+            let x = input.get(i).cloned().unwrap_or(zero());
+            out.push(if x.is_nan() { self.values[i] } else { x });
+        }
+        if self.indicators {
+            for i in 0..n {
+                let missing = input.get(i).map(|x| x.is_nan()).unwrap_or(false);
+                out.push(if missing { one() } else { zero() });
+            }
+        }
+        out
+    }
+
+    fn input_size(&self) -> usize {
+        self.values.len()
+    }
+
+    fn output_size(&self) -> usize {
+        if self.indicators { 2 * self.values.len() } else { self.values.len() }
+    }
+}
+
+/*
+ * Feature selection
+ */
+
+/// A criterion to select the features of a dataset.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Selection<'a, F: Float> {
+    /// Keeps the features whose variance is above the threshold, discarding
+    /// the (almost) constant ones.
+    VarianceThreshold(F),
+    /// Keeps the `k` features having the highest mutual information with
+    /// given class labels, one per sample.
+    ///
+    /// The values of each feature are discretized into 10 bins of equal
+    /// width to estimate it.
+    MutualInformation(&'a [usize], usize)
+}
+
+fn variance<F: Float>(values: &[F]) -> F {
+    let n = F::from(values.len().max(1)).unwrap();
+    let mean = values.iter().fold(zero::<F>(), |s, &x| s + x) / n;
+    values.iter().fold(zero::<F>(), |s, &x| s + (x - mean) * (x - mean)) / n
+}
+
+fn mutual_information<F: Float>(values: &[F], labels: &[usize]) -> f64 {
+    let bins = 10;
+    let min = values.iter().fold(F::infinity(), |m, &x| m.min(x));
+    let max = values.iter().fold(F::neg_infinity(), |m, &x| m.max(x));
+    let classes = labels.iter().fold(0, |m, &c| m.max(c + 1));
+    let width = (max - min) / F::from(bins).unwrap();
+    let mut joint = vec![vec![0.0; classes]; bins];
+    for (&x, &c) in values.iter().zip(labels) {
+        let b = if width > zero() { ((x - min) / width).to_usize().unwrap_or(0).min(bins - 1) } else { 0 };
+        joint[b][c] += 1.0;
+    }
+    let n = values.len().min(labels.len()) as f64;
+    let bin_totals = joint.iter().map(|row| row.iter().sum::<f64>()).collect::<Vec<_>>();
+    let class_totals = (0..classes).map(|c| joint.iter().map(|row| row[c]).sum::<f64>()).collect::<Vec<_>>();
+    let mut mi = 0.0;
+    for b in 0..bins {
+        for c in 0..classes {
+            if joint[b][c] > 0.0 {
+                mi += joint[b][c] / n * (joint[b][c] * n / (bin_totals[b] * class_totals[c])).ln();
+            }
+        }
+    }
+    mi
+}
+
+/// Selects features of given samples, which all have `features` values,
+/// according to `selection`.
+///
+/// Returns the indices of the selected features in increasing order, which
+/// can be given to a `util::Slice` to reduce the inputs of a network.
+pub fn select_features<F: Float>(data: &[Vec<F>], features: usize, selection: Selection<F>)
+    -> Vec<usize>
+{
+    let column = |i: usize| data.iter().map(|x| x.get(i).cloned().unwrap_or(zero())).collect::<Vec<F>>();
+    match selection {
+        Selection::VarianceThreshold(threshold) => {
+            (0..features).filter(|&i| variance(&column(i)) > threshold).collect()
+        },
+        Selection::MutualInformation(labels, k) => {
+            let scores = (0..features).map(|i| mutual_information(&column(i), labels)).collect::<Vec<_>>();
+            let mut indices = (0..features).collect::<Vec<_>>();
+            indices.sort_by(|&a, &b| scores[b].partial_cmp(&scores[a]).unwrap_or(Ordering::Equal));
+            indices.truncate(k);
+            indices.sort();
+            indices
+        }
+    }
+}
+
+/*
+ * Polynomial features
+ */
+
+/// Expands the inputs into all their monomials up to a given degree.
+///
+/// For inputs `[a, b]` and degree 2, the outputs are `[a, b, a*a, a*b, b*b]`,
+/// or `[a, b, a*b]` if only the interactions are kept. The constant term is
+/// omitted, as it is redundant with the biases of the layers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PolynomialFeatures {
+    inputs: usize,
+    monomials: Vec<Vec<usize>>
+}
+
+impl PolynomialFeatures {
+    /// Creates the expansion of `inputs` inputs into their monomials of
+    /// degree `1` to `degree`.
+    ///
+    /// If `interaction_only` is set, the monomials containing an input more
+    /// than once (like `a*a`) are omitted.
+    pub fn new(inputs: usize, degree: usize, interaction_only: bool) -> PolynomialFeatures {
+        let mut monomials = Vec::new();
+        let mut last = vec![vec![]];
+        for _ in 0..degree {
+            // extend each monomial of the previous degree with inputs of
+            // index at least the last of its inputs, so each one appears once
+            last = last.iter().flat_map(|m: &Vec<usize>| {
+                let start = match m.last() {
+                    Some(&l) if interaction_only => l + 1,
+                    Some(&l) => l,
+                    None => 0
+                };
+                (start..inputs).map(move |i| { let mut m = m.clone(); m.push(i); m })
+            }).collect();
+            monomials.extend(last.iter().cloned());
+        }
+        PolynomialFeatures {
+            inputs,
+            monomials
+        }
+    }
+
+    /// The indices of the inputs multiplied together by each output.
+    pub fn monomials(&self) -> &[Vec<usize>] {
+        &self.monomials
+    }
+}
+
+impl<F: Float> Compute<F> for PolynomialFeatures {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.monomials.iter().map(|m| {
+            m.iter().fold(one::<F>(), |p, &i| p * input.get(i).cloned().unwrap_or(zero()))
+        }).collect()
+    }
+
+    fn input_size(&self) -> usize {
+        self.inputs
+    }
+
+    fn output_size(&self) -> usize {
+        self.monomials.len()
+    }
+}
+
+/*
+ * Discretization
+ */
+
+/// How the bins of a `KBinsDiscretizer` are placed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binning {
+    /// Bins of equal width between the smallest and largest values.
+    Uniform,
+    /// Bins containing the same number of values.
+    Quantile
+}
+
+/// How a `KBinsDiscretizer` outputs the bin of each value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Encoding {
+    /// A group of binary outputs per feature, one per bin, of which only the
+    /// one of the bin of the value is set to `1.0`.
+    OneHot,
+    /// A single output per feature, the index of the bin of the value.
+    Ordinal
+}
+
+/// Discretizes each feature into bins.
+///
+/// With a one-hot encoding, the outputs are binary, which is what units
+/// of Boltzmann machines expect.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KBinsDiscretizer<F: Float> {
+    edges: Vec<Vec<F>>,
+    encoding: Encoding
+}
+
+impl<F: Float> KBinsDiscretizer<F> {
+    /// Fits a discretizer of each feature into `bins` bins on given
+    /// samples, which all have `features` values.
+    pub fn fit(data: &[Vec<F>], features: usize, bins: usize, binning: Binning, encoding: Encoding)
+        -> KBinsDiscretizer<F>
+    {
+        assert!(bins > 0, "There must be at least one bin per feature.");
+        let edges = (0..features).map(|i| {
+            let mut values = data.iter()
+                                 .map(|x| x.get(i).cloned().unwrap_or(zero()))
+                                 .collect::<Vec<_>>();
+            if values.is_empty() {
+                return vec![zero(); bins - 1];
+            }
+            values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let (min, max) = (values[0], values[values.len() - 1]);
+            (1..bins).map(|k| match binning {
+                Binning::Uniform => {
+                    min + (max - min) * F::from(k).unwrap() / F::from(bins).unwrap()
+                },
+                Binning::Quantile => values[k * values.len() / bins]
+            }).collect()
+        }).collect();
+        KBinsDiscretizer {
+            edges,
+            encoding
+        }
+    }
+
+    /// The inner edges of the bins of each feature, in increasing order.
+    ///
+    /// A value `x` goes to the bin `k` such that
+    /// `edges[k-1] <= x < edges[k]`.
+    pub fn edges(&self) -> &[Vec<F>] {
+        &self.edges
+    }
+
+    /// The bin of value `x` of feature `i`.
+    pub fn bin(&self, i: usize, x: F) -> usize {
+        self.edges[i].iter().take_while(|&&e| e <= x).count()
+    }
+}
+
+impl<F: Float> Compute<F> for KBinsDiscretizer<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let mut out = Vec::with_capacity(self.output_size());
+        for (i, edges) in self.edges.iter().enumerate() {
+            let bin = self.bin(i, input.get(i).cloned().unwrap_or(zero()));
+            match self.encoding {
+                Encoding::Ordinal => out.push(F::from(bin).unwrap()),
+                Encoding::OneHot => {
+                    out.extend((0..edges.len() + 1).map(|k| if k == bin { one::<F>() } else { zero() }))
+                }
+            }
+        }
+        out
+    }
+
+    fn input_size(&self) -> usize {
+        self.edges.len()
+    }
+
+    fn output_size(&self) -> usize {
+        match self.encoding {
+            Encoding::Ordinal => self.edges.len(),
+            Encoding::OneHot => self.edges.iter().map(|e| e.len() + 1).sum()
+        }
+    }
+}
+
+/*
+ * Scaling
+ */
+
+/// A fixed elementwise affine transformation `x * scale + shift`.
+///
+/// It is not trained: the backpropagation only goes through it, so it can
+/// be chained in front of a network being trained.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Affine<F: Float> {
+    scale: Vec<F>,
+    shift: Vec<F>
+}
+
+impl<F: Float> Affine<F> {
+    /// Creates the transformation from the scale and the shift of each input.
+    pub fn new(scale: Vec<F>, shift: Vec<F>) -> Affine<F> {
+        assert!(scale.len() == shift.len(), "The scale and the shift must have the same size.");
+        Affine {
+            scale,
+            shift
+        }
+    }
+
+    /// Creates the transformation standardizing the `features` first values
+    /// of the samples of `data`: each of them gets a mean of `0.0` and a
+    /// standard deviation of `1.0` over the dataset.
+    ///
+    /// Constant features are only centered.
+    pub fn standardizing(data: &[Vec<F>], features: usize) -> Affine<F> {
+        let n = F::from(data.len().max(1)).unwrap();
+        let value = |x: &Vec<F>, i: usize| x.get(i).cloned().unwrap_or(zero());
+        let (scale, shift) = (0..features).map(|i| {
+            let mean = data.iter().fold(zero::<F>(), |s, x| s + value(x, i)) / n;
+            let var = data.iter().fold(zero::<F>(), |s, x| s + (value(x, i) - mean).powi(2)) / n;
+            let scale = if var > zero() { one::<F>() / var.sqrt() } else { one() };
+            (scale, -mean * scale)
+        }).unzip();
+        Affine::new(scale, shift)
+    }
+
+    /// The scale of each input.
+    pub fn scale(&self) -> &[F] {
+        &self.scale
+    }
+
+    /// The shift of each input.
+    pub fn shift(&self) -> &[F] {
+        &self.shift
+    }
+}
+
+impl<F: Float> Compute<F> for Affine<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.scale.iter().zip(&self.shift).enumerate().map(|(i, (&a, &b))| {
+            input.get(i).cloned().unwrap_or(zero()) * a + b
+        }).collect()
+    }
+
+    fn input_size(&self) -> usize {
+        self.scale.len()
+    }
+
+    fn output_size(&self) -> usize {
+        self.scale.len()
+    }
+}
+
+impl<F: Float> Differentiable<F> for Affine<F> {
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        (0..input.len()).map(|i| match (self.scale.get(i), output_gradient.get(i)) {
+            (Some(&a), Some(&g)) => a * g,
+            _ => zero()
+        }).collect()
+    }
+}
+
+impl<F: Float, M: Method> BackpropTrain<F, M> for Affine<F> {
+    fn backprop_train(&mut self, _rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let out = self.compute(input);
+        let errors = out.iter().enumerate().map(|(i, &o)| {
+            o - target.get(i).cloned().unwrap_or(zero())
+        }).collect::<Vec<_>>();
+        let gradient = self.input_gradient(input, &errors);
+        input.iter().zip(gradient).map(|(&x, g)| x - g).collect()
+    }
+}
+
+impl<F: Float> IntervalCompute<F> for Affine<F> {
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        let (l, u) = (self.compute(lower), self.compute(upper));
+        l.into_iter().zip(u).map(|(l, u)| (l.min(u), l.max(u))).unzip()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Compute, Differentiable, BackpropTrain};
+    use training::GradientDescent;
+
+    use super::{Imputation, Imputer, Selection, select_features, PolynomialFeatures};
+    use super::{Binning, Encoding, KBinsDiscretizer, Affine};
+
+    #[test]
+    fn imputer() {
+        let data = vec![vec![1.0, f64::NAN, 5.0], vec![2.0, f64::NAN, f64::NAN], vec![6.0, f64::NAN, 3.0]];
+        let mean = Imputer::fit(&data, 3, Imputation::Mean, false);
+        assert_eq!(mean.values(), [3.0, 0.0, 4.0]);
+        assert_eq!(mean.compute(&[f64::NAN, 1.0, f64::NAN]), [3.0, 1.0, 4.0]);
+        let median = Imputer::fit(&data, 3, Imputation::Median, true);
+        assert_eq!(median.values(), [2.0, 0.0, 4.0]);
+        assert_eq!(median.output_size(), 6);
+        assert_eq!(median.compute(&[f64::NAN, 1.0]), [2.0, 1.0, 0.0, 1.0, 0.0, 0.0]);
+        let constant = Imputer::fit(&data, 3, Imputation::Constant(-1.0), false);
+        assert_eq!(constant.compute(&[f64::NAN, f64::NAN, 0.5]), [-1.0, -1.0, 0.5]);
+    }
+
+    #[test]
+    fn feature_selection() {
+        // feature 0 is constant, feature 2 is the label, feature 1 is noise
+        let data = vec![vec![1.0f64, 0.0, 0.0], vec![1.0, 0.0, 1.0], vec![1.0, 0.0, 0.0],
+                        vec![1.0, 1.0, 1.0], vec![1.0, 1.0, 1.0], vec![1.0, 1.0, 0.0]];
+        let labels = [0, 1, 0, 1, 1, 0];
+        assert_eq!(select_features(&data, 3, Selection::VarianceThreshold(0.01)), [1, 2]);
+        assert_eq!(select_features(&data, 3, Selection::MutualInformation(&labels, 1)), [2]);
+        assert_eq!(select_features(&data, 3, Selection::MutualInformation(&labels, 2)), [1, 2]);
+    }
+
+    #[test]
+    fn polynomial_features() {
+        let poly = PolynomialFeatures::new(2, 2, false);
+        assert_eq!(poly.compute(&[2.0f32, 3.0]), [2.0f32, 3.0, 4.0, 6.0, 9.0]);
+        let poly = PolynomialFeatures::new(3, 3, true);
+        assert_eq!(poly.monomials(), [vec![0], vec![1], vec![2], vec![0, 1], vec![0, 2],
+                                      vec![1, 2], vec![0, 1, 2]]);
+        assert_eq!(Compute::<f32>::output_size(&PolynomialFeatures::new(3, 3, false)), 19);
+    }
+
+    #[test]
+    fn discretizer() {
+        let data = vec![vec![0.0f64, 1.0], vec![1.0, 2.0], vec![2.0, 3.0], vec![10.0, 100.0]];
+        let uniform = KBinsDiscretizer::fit(&data, 2, 2, Binning::Uniform, Encoding::Ordinal);
+        assert_eq!(uniform.edges(), [vec![5.0], vec![50.5]]);
+        assert_eq!(uniform.compute(&[4.0, 60.0]), [0.0, 1.0]);
+        let quantile = KBinsDiscretizer::fit(&data, 2, 2, Binning::Quantile, Encoding::OneHot);
+        assert_eq!(quantile.edges(), [vec![2.0], vec![3.0]]);
+        assert_eq!(quantile.output_size(), 4);
+        assert_eq!(quantile.compute(&[1.5, 3.0]), [1.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn affine() {
+        let mut affine = Affine::new(vec![2.0f64, -1.0], vec![0.5, 1.0]);
+        assert_eq!(affine.compute(&[1.0, 3.0]), [2.5, -2.0]);
+        assert_eq!(affine.input_gradient(&[1.0, 3.0], &[1.0, 1.0]), [2.0, -1.0]);
+        let returned = affine.backprop_train(&GradientDescent::new(1.0).unwrap(), &[1.0, 3.0], &[2.5, -1.0]);
+        assert_eq!(returned, [1.0, 2.0]);
+
+        let data = vec![vec![1.0, 5.0], vec![3.0, 5.0]];
+        let standardizing = Affine::standardizing(&data, 2);
+        assert_eq!(standardizing.compute(&data[0]), [-1.0, 0.0]);
+        assert_eq!(standardizing.compute(&data[1]), [1.0, 0.0]);
+    }
+}
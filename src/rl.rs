@@ -0,0 +1,348 @@
+//! Reinforcement learning utilities.
+//!
+//! This module defines the `Environment` trait, describing a world an agent
+//! interacts with, along with a few classic toy environments, and tools to
+//! evaluate agents.
+
+use num::{Float, one, zero};
+
+use rand::{Rand, random, thread_rng};
+use rand::distributions::{IndependentSample, Range};
+
+/// An environment an agent can interact with, by episodes.
+///
+/// Actions are discrete and identified by their index, from `0` to
+/// `action_count() - 1`. Observations are vectors of values that can be
+/// directly fed to a network.
+pub trait Environment<F: Float> {
+    /// Starts a new episode, and returns the initial observation.
+    fn reset(&mut self) -> Vec<F>;
+    /// Performs given action.
+    ///
+    /// Returns the new observation, the reward associated with this action,
+    /// and whether the episode is over. Once it is, `reset()` must be called
+    /// before taking any new step.
+    fn step(&mut self, action: usize) -> (Vec<F>, F, bool);
+    /// The size of the observations generated by this environment.
+    fn observation_size(&self) -> usize;
+    /// The number of possible actions.
+    fn action_count(&self) -> usize;
+}
+
+/*
+ * Multi-armed bandit
+ */
+
+/// A multi-armed bandit.
+///
+/// Each action pulls an arm, which gives a reward of `1.0` with a fixed
+/// probability specific to this arm, and `0.0` otherwise. Episodes are
+/// one step long, and the observations are empty.
+pub struct MultiArmedBandit<F: Float> {
+    probabilities: Vec<F>
+}
+
+impl<F: Float> MultiArmedBandit<F> {
+    /// Creates a new bandit with one arm for each of the given probabilities
+    /// of reward.
+    pub fn new(probabilities: Vec<F>) -> MultiArmedBandit<F> {
+        MultiArmedBandit { probabilities }
+    }
+}
+
+impl<F: Float + Rand> Environment<F> for MultiArmedBandit<F> {
+    fn reset(&mut self) -> Vec<F> {
+        Vec::new()
+    }
+
+    fn step(&mut self, action: usize) -> (Vec<F>, F, bool) {
+        let reward = if random::<F>() < self.probabilities[action] { one() } else { zero() };
+        (Vec::new(), reward, true)
+    }
+
+    fn observation_size(&self) -> usize {
+        0
+    }
+
+    fn action_count(&self) -> usize {
+        self.probabilities.len()
+    }
+}
+
+/*
+ * Grid world
+ */
+
+/// A rectangular grid, in which the agent needs to reach a goal cell.
+///
+/// The actions are moving up (`0`), right (`1`), down (`2`) or left (`3`),
+/// moving against a wall leaves the agent in place. Reaching the goal gives
+/// a reward of `1.0` and ends the episode, all other steps give no reward.
+/// The episode also ends if the goal has not been reached after a maximum
+/// number of steps.
+///
+/// The observation is a one-hot encoding of the position of the agent, cell
+/// `(x, y)` being at index `x + y * width`.
+pub struct GridWorld {
+    width: usize,
+    height: usize,
+    start: (usize, usize),
+    goal: (usize, usize),
+    max_steps: usize,
+    position: (usize, usize),
+    steps: usize
+}
+
+impl GridWorld {
+    /// Creates a new grid world of given dimensions, in which the agent
+    /// starts each episode at `start` and needs to reach `goal` in at most
+    /// `max_steps` steps.
+    pub fn new(width: usize,
+               height: usize,
+               start: (usize, usize),
+               goal: (usize, usize),
+               max_steps: usize)
+        -> GridWorld
+    {
+        assert!(start.0 < width && start.1 < height, "The start must be in the grid.");
+        assert!(goal.0 < width && goal.1 < height, "The goal must be in the grid.");
+        GridWorld {
+            width,
+            height,
+            start,
+            goal,
+            max_steps,
+            position: start,
+            steps: 0
+        }
+    }
+
+    /// The current position of the agent.
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    fn observation<F: Float>(&self) -> Vec<F> {
+        let mut obs = vec![zero(); self.width * self.height];
+        obs[self.position.0 + self.position.1 * self.width] = one();
+        obs
+    }
+}
+
+impl<F: Float> Environment<F> for GridWorld {
+    fn reset(&mut self) -> Vec<F> {
+        self.position = self.start;
+        self.steps = 0;
+        self.observation()
+    }
+
+    fn step(&mut self, action: usize) -> (Vec<F>, F, bool) {
+        let (x, y) = self.position;
+        self.position = match action {
+            0 if y > 0 => (x, y - 1),
+            1 if x + 1 < self.width => (x + 1, y),
+            2 if y + 1 < self.height => (x, y + 1),
+            3 if x > 0 => (x - 1, y),
+            _ => (x, y)
+        };
+        self.steps += 1;
+        if self.position == self.goal {
+            (self.observation(), one(), true)
+        } else {
+            (self.observation(), zero(), self.steps >= self.max_steps)
+        }
+    }
+
+    fn observation_size(&self) -> usize {
+        self.width * self.height
+    }
+
+    fn action_count(&self) -> usize {
+        4
+    }
+}
+
+/*
+ * Cart-pole
+ */
+
+/// The classic pole balancing problem.
+///
+/// A pole is attached by an unactuated joint to a cart moving on a
+/// frictionless track. The actions are pushing the cart to the left (`0`)
+/// or to the right (`1`), and each step the pole remains upright gives a
+/// reward of `1.0`. The episode ends when the pole leans more than 12 degrees,
+/// when the cart leaves the track, or after a maximum number of steps.
+///
+/// The observation is `[position, velocity, angle, angular velocity]`.
+pub struct CartPole<F: Float> {
+    state: [F; 4],
+    max_steps: usize,
+    steps: usize
+}
+
+impl<F: Float> CartPole<F> {
+    /// Creates a new cart-pole environment whose episodes last at most
+    /// `max_steps` steps.
+    pub fn new(max_steps: usize) -> CartPole<F> {
+        CartPole {
+            state: [zero(); 4],
+            max_steps,
+            steps: 0
+        }
+    }
+}
+
+fn c<F: Float>(x: f64) -> F {
+    F::from(x).unwrap()
+}
+
+impl<F: Float + Rand> Environment<F> for CartPole<F> {
+    fn reset(&mut self) -> Vec<F> {
+        let range = Range::new(-0.05, 0.05);
+        let mut rng = thread_rng();
+        for s in &mut self.state {
+            *s = c(range.ind_sample(&mut rng));
+        }
+        self.steps = 0;
+        self.state.to_vec()
+    }
+
+    fn step(&mut self, action: usize) -> (Vec<F>, F, bool) {
+        let (gravity, cart_mass, pole_mass, half_length, tau) =
+            (c::<F>(9.8), c::<F>(1.0), c::<F>(0.1), c::<F>(0.5), c::<F>(0.02));
+        let total_mass = cart_mass + pole_mass;
+        let force = if action == 1 { c::<F>(10.0) } else { c::<F>(-10.0) };
+        let [x, x_dot, theta, theta_dot] = self.state;
+        let (sin, cos) = (theta.sin(), theta.cos());
+
+        let temp = (force + pole_mass * half_length * theta_dot * theta_dot * sin) / total_mass;
+        let theta_acc = (gravity * sin - cos * temp)
+            / (half_length * (c::<F>(4.0 / 3.0) - pole_mass * cos * cos / total_mass));
+        let x_acc = temp - pole_mass * half_length * theta_acc * cos / total_mass;
+
+        self.state = [x + tau * x_dot,
+                      x_dot + tau * x_acc,
+                      theta + tau * theta_dot,
+                      theta_dot + tau * theta_acc];
+        self.steps += 1;
+
+        let done = self.state[0].abs() > c(2.4)
+                || self.state[2].abs() > c(12.0f64.to_radians())
+                || self.steps >= self.max_steps;
+        (self.state.to_vec(), one(), done)
+    }
+
+    fn observation_size(&self) -> usize {
+        4
+    }
+
+    fn action_count(&self) -> usize {
+        2
+    }
+}
+
+/*
+ * Tournaments
+ */
+
+/// The result of a game between two players.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    /// The first player won.
+    FirstWins,
+    /// The second player won.
+    SecondWins,
+    /// Nobody won.
+    Draw
+}
+
+/// Runs a round-robin tournament between `players`, and returns their
+/// Elo-style ratings, in the same order.
+///
+/// The players are typically successive checkpoints of a policy network,
+/// and `play` a closure making two of them play a game against each other,
+/// the first argument taking the first move.
+///
+/// During each of the `rounds` rounds, every pair of players plays two
+/// games, each player taking the first move once. All players start with a
+/// rating of `1500.0`, which is updated after each game with given `k`
+/// factor (`32.0` is a classic value).
+pub fn tournament<P, G>(players: &[P], rounds: usize, k: f64, mut play: G) -> Vec<f64>
+    where G: FnMut(&P, &P) -> Outcome
+{
+    let mut ratings = vec![1500.0; players.len()];
+    for _ in 0..rounds {
+        for a in 0..players.len() {
+            for b in 0..players.len() {
+                if a == b { continue; }
+                let score = match play(&players[a], &players[b]) {
+                    Outcome::FirstWins => 1.0,
+                    Outcome::SecondWins => 0.0,
+                    Outcome::Draw => 0.5
+                };
+                let expected = 1.0 / (1.0 + 10f64.powf((ratings[b] - ratings[a]) / 400.0));
+                ratings[a] += k * (score - expected);
+                ratings[b] -= k * (score - expected);
+            }
+        }
+    }
+    ratings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Environment, MultiArmedBandit, GridWorld, CartPole, Outcome, tournament};
+
+    #[test]
+    fn bandit() {
+        let mut bandit = MultiArmedBandit::new(vec![0.0f32, 1.0]);
+        assert_eq!(Environment::<f32>::action_count(&bandit), 2);
+        assert_eq!(bandit.reset(), []);
+        assert_eq!(bandit.step(0), (vec![], 0.0, true));
+        assert_eq!(bandit.step(1), (vec![], 1.0, true));
+    }
+
+    #[test]
+    fn gridworld() {
+        let mut grid = GridWorld::new(3, 2, (0, 0), (2, 1), 10);
+        let obs: Vec<f32> = grid.reset();
+        assert_eq!(obs, [1.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+        // bumping into a wall
+        let (obs, reward, done) = grid.step(0);
+        assert_eq!((obs, reward, done), (vec![1.0f32, 0.0, 0.0, 0.0, 0.0, 0.0], 0.0, false));
+        let _: (Vec<f32>, f32, bool) = grid.step(1);
+        let _: (Vec<f32>, f32, bool) = grid.step(2);
+        let (obs, reward, done): (Vec<f32>, f32, bool) = grid.step(1);
+        assert_eq!(grid.position(), (2, 1));
+        assert_eq!((obs, reward, done), (vec![0.0, 0.0, 0.0, 0.0, 0.0, 1.0], 1.0, true));
+    }
+
+    #[test]
+    fn cartpole() {
+        let mut env = CartPole::<f64>::new(500);
+        assert_eq!(env.reset().len(), 4);
+        // always pushing in the same direction makes the pole fall quickly
+        let mut steps = 0;
+        loop {
+            let (_, reward, done) = env.step(1);
+            assert_eq!(reward, 1.0);
+            steps += 1;
+            if done { break; }
+        }
+        assert!(steps < 100);
+    }
+
+    #[test]
+    fn elo_tournament() {
+        // the stronger player always wins, equal players draw
+        let players = [1, 3, 2, 2];
+        let ratings = tournament(&players, 10, 32.0, |a, b| {
+            if a > b { Outcome::FirstWins } else if a < b { Outcome::SecondWins } else { Outcome::Draw }
+        });
+        assert!(ratings[1] > ratings[2] && ratings[2] > ratings[0]);
+        assert!(ratings[1] > ratings[3] && ratings[3] > ratings[0]);
+        // ratings are zero-sum
+        assert!((ratings.iter().fold(0.0, |s, r| s + r) - 4.0 * 1500.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,662 @@
+//! Recurrent layers
+
+use std::cmp::min;
+
+use num::{Float, one, zero};
+
+use {Compute, StatefulCompute, SequenceTrain};
+use activations::{Activation, VectorActivation};
+use init::Initializer;
+use linalg::Matrix;
+use training::{Bptt, GradientMethod, OptimizerState};
+
+/// An Elman recurrent layer
+///
+/// This layer has a hidden state `H`, which is also its output. At each step,
+/// if `X` is the vector of inputs, `W` the input weights matrix, `U` the
+/// recurrent weights matrix, `B` the vector of biases and `f()` the
+/// activation function, the new state is:
+///
+/// ```text
+/// H = f( W*X + U*H + B )
+/// ```
+///
+/// The state starts at `0.0`. `step(..)` computes the output and updates the
+/// state, while `compute(..)` only computes the output of the next step.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimpleRnn<F: Float, A: VectorActivation<F>> {
+    weights: Weights<F>,
+    activation: A,
+    state: Vec<F>
+}
+
+impl<F, A> SimpleRnn<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    /// Creates a new recurrent layer with all its weights and biases set to 0.
+    pub fn new(inputs: usize, hidden: usize, activation: A) -> SimpleRnn<F, A> {
+        SimpleRnn::from_weights(Weights::zeros(inputs, hidden), activation)
+    }
+
+    /// Creates a new recurrent layer with its weights drawn according to
+    /// given initialization scheme, and its biases set to 0.
+    pub fn new_init(inputs: usize, hidden: usize, activation: A, init: Initializer<F>)
+        -> SimpleRnn<F, A>
+    {
+        SimpleRnn::from_weights(Weights::init(inputs, hidden, init), activation)
+    }
+
+    /// Creates a new recurrent layer from its input weights matrix `W`, its
+    /// recurrent weights matrix `U` and its biases vector `B`.
+    ///
+    /// `W` has a row for each hidden unit and a column for each input, `U` is
+    /// a square matrix with a row and a column for each hidden unit, and the
+    /// biases vector must have one value per hidden unit.
+    pub fn from_matrices(input_weights: Matrix<F>,
+                         recurrent_weights: Matrix<F>,
+                         biases: Vec<F>,
+                         activation: A)
+        -> SimpleRnn<F, A>
+    {
+        SimpleRnn::from_weights(Weights::new(input_weights, recurrent_weights, biases), activation)
+    }
+
+    fn from_weights(weights: Weights<F>, activation: A) -> SimpleRnn<F, A> {
+        SimpleRnn {
+            state: vec![zero(); weights.biases.len()],
+            weights,
+            activation
+        }
+    }
+
+    /// The input weights matrix `W`: the weight between input `i` and hidden
+    /// unit `j` is at `(j, i)`.
+    pub fn input_weights(&self) -> &Matrix<F> {
+        &self.weights.input_weights
+    }
+
+    /// Mutable access to the input weights matrix, see `input_weights()`.
+    pub fn input_weights_mut(&mut self) -> &mut Matrix<F> {
+        &mut self.weights.input_weights
+    }
+
+    /// The recurrent weights matrix `U`: the weight from the previous value
+    /// of hidden unit `i` to hidden unit `j` is at `(j, i)`.
+    pub fn recurrent_weights(&self) -> &Matrix<F> {
+        &self.weights.recurrent_weights
+    }
+
+    /// Mutable access to the recurrent weights matrix, see
+    /// `recurrent_weights()`.
+    pub fn recurrent_weights_mut(&mut self) -> &mut Matrix<F> {
+        &mut self.weights.recurrent_weights
+    }
+
+    /// The biases of the layer, one per hidden unit.
+    pub fn biases(&self) -> &[F] {
+        &self.weights.biases
+    }
+
+    /// Mutable access to the biases of the layer.
+    pub fn biases_mut(&mut self) -> &mut [F] {
+        &mut self.weights.biases
+    }
+
+    /// The current hidden state.
+    pub fn state(&self) -> &[F] {
+        &self.state
+    }
+
+    /// Sets the hidden state.
+    pub fn set_state(&mut self, state: &[F]) {
+        set_state(&mut self.state, state);
+    }
+
+    /// Clears the state kept by the training method between updates, like
+    /// momentum velocities.
+    pub fn reset_optimizer(&mut self) {
+        self.weights.reset_optimizer();
+    }
+}
+
+impl<F, A> Compute<F> for SimpleRnn<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.activation.activate(&self.weights.pre_activation(input, &self.state))
+    }
+
+    fn input_size(&self) -> usize {
+        self.weights.input_weights.cols()
+    }
+
+    fn output_size(&self) -> usize {
+        self.weights.biases.len()
+    }
+}
+
+impl<F, A> StatefulCompute<F> for SimpleRnn<F, A>
+    where F: Float,
+          A: VectorActivation<F>
+{
+    fn step(&mut self, input: &[F]) -> Vec<F> {
+        let out = self.compute(input);
+        self.state = out.clone();
+        out
+    }
+
+    fn reset_state(&mut self) {
+        for s in &mut self.state {
+            *s = zero();
+        }
+    }
+}
+
+impl<F, A, M> SequenceTrain<F, Bptt<M>> for SimpleRnn<F, A>
+    where F: Float,
+          A: VectorActivation<F>,
+          M: GradientMethod<F>
+{
+    fn sequence_train(&mut self, rule: &Bptt<M>, inputs: &[Vec<F>], targets: &[Vec<F>]) {
+        let (steps, truncation) = (min(inputs.len(), targets.len()), rule.truncation());
+        for (inputs, targets) in inputs[..steps].chunks(truncation).zip(targets.chunks(truncation)) {
+            // unroll the layer, keeping the state before each step
+            let mut states = vec![self.state.clone()];
+            let mut preacts = Vec::with_capacity(inputs.len());
+            for input in inputs {
+                let preact = self.weights.pre_activation(input, &states[states.len() - 1]);
+                states.push(self.activation.activate(&preact));
+                preacts.push(preact);
+            }
+
+            // back through time, `next` being the gradient coming from the next step
+            let mut gradients = Gradients::zeros(&self.weights);
+            let mut next = vec![zero(); self.state.len()];
+            for t in (0..inputs.len()).rev() {
+                let errors = step_errors(&states[t+1], &targets[t], &next);
+                let deltas = self.activation.jacobian_product_with_output(&preacts[t], &states[t+1], &errors);
+                gradients.accumulate(&deltas, &inputs[t], &states[t]);
+                next = self.weights.recurrent_weights.transpose_mul_vec(&deltas);
+            }
+            self.weights.apply(rule.method(), gradients);
+            self.state = states.pop().unwrap();
+        }
+    }
+}
+
+/*
+ * Gated recurrent unit
+ */
+
+/// The gates of a `Gru`, each having its own weights and biases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gate {
+    /// The update gate `Z`, choosing between keeping the state and replacing
+    /// it by the candidate.
+    Update,
+    /// The reset gate `R`, choosing which part of the state the candidate
+    /// depends on.
+    Reset,
+    /// The candidate state `C`.
+    Candidate
+}
+
+/// A gated recurrent unit layer
+///
+/// This is a lighter alternative to the LSTM, whose state `H` is also its
+/// output. With `σ` the sigmoid function and `⊙` the element-wise product,
+/// each step computes:
+///
+/// ```text
+/// Z = σ( Wz*X + Uz*H + Bz )
+/// R = σ( Wr*X + Ur*H + Br )
+/// C = tanh( Wc*X + Uc*(R ⊙ H) + Bc )
+/// H = (1 - Z) ⊙ H + Z ⊙ C
+/// ```
+///
+/// The weights and biases of each gate are accessed by giving the `Gate`.
+/// Like for `SimpleRnn`, the state starts at `0.0` and only `step(..)`
+/// changes it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gru<F: Float> {
+    update: Weights<F>,
+    reset: Weights<F>,
+    candidate: Weights<F>,
+    state: Vec<F>
+}
+
+impl<F: Float> Gru<F> {
+    /// Creates a new GRU layer with all its weights and biases set to 0.
+    pub fn new(inputs: usize, hidden: usize) -> Gru<F> {
+        Gru::from_weights(Weights::zeros(inputs, hidden),
+                          Weights::zeros(inputs, hidden),
+                          Weights::zeros(inputs, hidden))
+    }
+
+    /// Creates a new GRU layer with the weights of all gates drawn according
+    /// to given initialization scheme, and its biases set to 0.
+    pub fn new_init(inputs: usize, hidden: usize, init: Initializer<F>) -> Gru<F> {
+        Gru::from_weights(Weights::init(inputs, hidden, init),
+                          Weights::init(inputs, hidden, init),
+                          Weights::init(inputs, hidden, init))
+    }
+
+    fn from_weights(update: Weights<F>, reset: Weights<F>, candidate: Weights<F>) -> Gru<F> {
+        Gru {
+            state: vec![zero(); update.biases.len()],
+            update,
+            reset,
+            candidate
+        }
+    }
+
+    fn gate(&self, gate: Gate) -> &Weights<F> {
+        match gate {
+            Gate::Update => &self.update,
+            Gate::Reset => &self.reset,
+            Gate::Candidate => &self.candidate
+        }
+    }
+
+    fn gate_mut(&mut self, gate: Gate) -> &mut Weights<F> {
+        match gate {
+            Gate::Update => &mut self.update,
+            Gate::Reset => &mut self.reset,
+            Gate::Candidate => &mut self.candidate
+        }
+    }
+
+    /// The input weights matrix of given gate, with a row for each hidden
+    /// unit and a column for each input.
+    pub fn input_weights(&self, gate: Gate) -> &Matrix<F> {
+        &self.gate(gate).input_weights
+    }
+
+    /// Mutable access to the input weights matrix of given gate.
+    pub fn input_weights_mut(&mut self, gate: Gate) -> &mut Matrix<F> {
+        &mut self.gate_mut(gate).input_weights
+    }
+
+    /// The recurrent weights matrix of given gate, with a row and a column
+    /// for each hidden unit.
+    pub fn recurrent_weights(&self, gate: Gate) -> &Matrix<F> {
+        &self.gate(gate).recurrent_weights
+    }
+
+    /// Mutable access to the recurrent weights matrix of given gate.
+    pub fn recurrent_weights_mut(&mut self, gate: Gate) -> &mut Matrix<F> {
+        &mut self.gate_mut(gate).recurrent_weights
+    }
+
+    /// The biases of given gate, one per hidden unit.
+    pub fn biases(&self, gate: Gate) -> &[F] {
+        &self.gate(gate).biases
+    }
+
+    /// Mutable access to the biases of given gate.
+    pub fn biases_mut(&mut self, gate: Gate) -> &mut [F] {
+        &mut self.gate_mut(gate).biases
+    }
+
+    /// The current hidden state.
+    pub fn state(&self) -> &[F] {
+        &self.state
+    }
+
+    /// Sets the hidden state.
+    pub fn set_state(&mut self, state: &[F]) {
+        set_state(&mut self.state, state);
+    }
+
+    /// Clears the state kept by the training method between updates, like
+    /// momentum velocities.
+    pub fn reset_optimizer(&mut self) {
+        self.update.reset_optimizer();
+        self.reset.reset_optimizer();
+        self.candidate.reset_optimizer();
+    }
+
+    // computes a step from given state, keeping the intermediate values
+    fn forward(&self, input: &[F], state: &[F]) -> GruStep<F> {
+        let sigmoid = |x| Activation::Sigmoid.value(x);
+        let update = self.update.pre_activation(input, state).into_iter().map(sigmoid).collect::<Vec<_>>();
+        let reset = self.reset.pre_activation(input, state).into_iter().map(sigmoid).collect::<Vec<_>>();
+        let gated = state.iter().zip(&reset).map(|(&h, &r)| r * h).collect::<Vec<_>>();
+        let candidate = self.candidate.pre_activation(input, &gated).into_iter().map(F::tanh).collect::<Vec<_>>();
+        let output = state.iter().zip(&update).zip(&candidate).map(|((&h, &z), &c)| {
+            (one::<F>() - z) * h + z * c
+        }).collect();
+        GruStep {
+            update,
+            reset,
+            gated,
+            candidate,
+            output
+        }
+    }
+}
+
+// the intermediate values of a step of a GRU
+struct GruStep<F> {
+    update: Vec<F>,
+    reset: Vec<F>,
+    gated: Vec<F>,
+    candidate: Vec<F>,
+    output: Vec<F>
+}
+
+impl<F: Float> Compute<F> for Gru<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.forward(input, &self.state).output
+    }
+
+    fn input_size(&self) -> usize {
+        self.update.input_weights.cols()
+    }
+
+    fn output_size(&self) -> usize {
+        self.update.biases.len()
+    }
+}
+
+impl<F: Float> StatefulCompute<F> for Gru<F> {
+    fn step(&mut self, input: &[F]) -> Vec<F> {
+        let out = self.compute(input);
+        self.state = out.clone();
+        out
+    }
+
+    fn reset_state(&mut self) {
+        for s in &mut self.state {
+            *s = zero();
+        }
+    }
+}
+
+impl<F, M> SequenceTrain<F, Bptt<M>> for Gru<F>
+    where F: Float,
+          M: GradientMethod<F>
+{
+    fn sequence_train(&mut self, rule: &Bptt<M>, inputs: &[Vec<F>], targets: &[Vec<F>]) {
+        let (steps, truncation) = (min(inputs.len(), targets.len()), rule.truncation());
+        for (inputs, targets) in inputs[..steps].chunks(truncation).zip(targets.chunks(truncation)) {
+            // unroll the layer, keeping the state before each step
+            let mut states = vec![self.state.clone()];
+            let mut forwards = Vec::with_capacity(inputs.len());
+            for input in inputs {
+                let forward = self.forward(input, &states[states.len() - 1]);
+                states.push(forward.output.clone());
+                forwards.push(forward);
+            }
+
+            // back through time, `next` being the gradient coming from the next step
+            let mut update_gradients = Gradients::zeros(&self.update);
+            let mut reset_gradients = Gradients::zeros(&self.reset);
+            let mut candidate_gradients = Gradients::zeros(&self.candidate);
+            let hidden = self.state.len();
+            let mut next = vec![zero(); hidden];
+            for t in (0..inputs.len()).rev() {
+                let (previous, step) = (&states[t], &forwards[t]);
+                let errors = step_errors(&step.output, &targets[t], &next);
+                // gradients with regard to the pre-activation values of the gates
+                let mut update_deltas = vec![zero(); hidden];
+                let mut candidate_deltas = vec![zero(); hidden];
+                for j in 0..hidden {
+                    let (z, c) = (step.update[j], step.candidate[j]);
+                    update_deltas[j] = errors[j] * (c - previous[j]) * z * (one::<F>() - z);
+                    candidate_deltas[j] = errors[j] * z * (one::<F>() - c * c);
+                }
+                let gated_errors = self.candidate.recurrent_weights.transpose_mul_vec(&candidate_deltas);
+                let reset_deltas = (0..hidden).map(|j| {
+                    let r = step.reset[j];
+                    gated_errors[j] * previous[j] * r * (one::<F>() - r)
+                }).collect::<Vec<_>>();
+
+                update_gradients.accumulate(&update_deltas, &inputs[t], previous);
+                reset_gradients.accumulate(&reset_deltas, &inputs[t], previous);
+                candidate_gradients.accumulate(&candidate_deltas, &inputs[t], &step.gated);
+
+                let through_update = self.update.recurrent_weights.transpose_mul_vec(&update_deltas);
+                let through_reset = self.reset.recurrent_weights.transpose_mul_vec(&reset_deltas);
+                next = (0..hidden).map(|j| {
+                    errors[j] * (one::<F>() - step.update[j]) + gated_errors[j] * step.reset[j]
+                        + through_update[j] + through_reset[j]
+                }).collect();
+            }
+            self.update.apply(rule.method(), update_gradients);
+            self.reset.apply(rule.method(), reset_gradients);
+            self.candidate.apply(rule.method(), candidate_gradients);
+            self.state = states.pop().unwrap();
+        }
+    }
+}
+
+/*
+ * Weights
+ */
+
+// the input weights, recurrent weights and biases of a layer or a gate
+#[derive(Clone, Debug, PartialEq)]
+struct Weights<F: Float> {
+    input_weights: Matrix<F>,
+    recurrent_weights: Matrix<F>,
+    biases: Vec<F>,
+    input_state: OptimizerState<F>,
+    recurrent_state: OptimizerState<F>,
+    biases_state: OptimizerState<F>
+}
+
+impl<F: Float> Weights<F> {
+    fn new(input_weights: Matrix<F>, recurrent_weights: Matrix<F>, biases: Vec<F>) -> Weights<F> {
+        let hidden = biases.len();
+        assert!(input_weights.rows() == hidden,
+                "The input weights must have a row for each hidden unit.");
+        assert!(recurrent_weights.rows() == hidden && recurrent_weights.cols() == hidden,
+                "The recurrent weights must have a row and a column for each hidden unit.");
+        Weights {
+            input_weights,
+            recurrent_weights,
+            biases,
+            input_state: OptimizerState::new(),
+            recurrent_state: OptimizerState::new(),
+            biases_state: OptimizerState::new()
+        }
+    }
+
+    fn zeros(inputs: usize, hidden: usize) -> Weights<F> {
+        Weights::new(Matrix::zeros(hidden, inputs), Matrix::zeros(hidden, hidden), vec![zero(); hidden])
+    }
+
+    fn init(inputs: usize, hidden: usize, init: Initializer<F>) -> Weights<F> {
+        let mut input_generator = init.generator(inputs, hidden);
+        let mut recurrent_generator = init.generator(hidden, hidden);
+        Weights::new(
+            Matrix::from_vec(hidden, inputs, (0..hidden*inputs).map(|_| input_generator()).collect()),
+            Matrix::from_vec(hidden, hidden, (0..hidden*hidden).map(|_| recurrent_generator()).collect()),
+            vec![zero(); hidden]
+        )
+    }
+
+    // W*X + U*H + B
+    fn pre_activation(&self, input: &[F], state: &[F]) -> Vec<F> {
+        let mut out = self.input_weights.mul_vec(input);
+        for ((o, r), &b) in out.iter_mut().zip(self.recurrent_weights.mul_vec(state)).zip(&self.biases) {
+            *o = *o + r + b;
+        }
+        out
+    }
+
+    fn reset_optimizer(&mut self) {
+        self.input_state.reset();
+        self.recurrent_state.reset();
+        self.biases_state.reset();
+    }
+
+    fn apply<M: GradientMethod<F>>(&mut self, rule: &M, mut gradients: Gradients<F>) {
+        rule.regularize(self.input_weights.as_slice(), &mut gradients.input_weights);
+        rule.regularize(self.recurrent_weights.as_slice(), &mut gradients.recurrent_weights);
+        rule.update(self.input_weights.as_mut_slice(), &gradients.input_weights, &mut self.input_state);
+        rule.update(self.recurrent_weights.as_mut_slice(), &gradients.recurrent_weights, &mut self.recurrent_state);
+        rule.update(&mut self.biases, &gradients.biases, &mut self.biases_state);
+    }
+}
+
+// the gradients of the error with regard to some weights, accumulated over
+// the steps of a sequence
+struct Gradients<F> {
+    inputs: usize,
+    input_weights: Vec<F>,
+    recurrent_weights: Vec<F>,
+    biases: Vec<F>
+}
+
+impl<F: Float> Gradients<F> {
+    fn zeros(weights: &Weights<F>) -> Gradients<F> {
+        let (hidden, inputs) = (weights.biases.len(), weights.input_weights.cols());
+        Gradients {
+            inputs,
+            input_weights: vec![zero(); hidden * inputs],
+            recurrent_weights: vec![zero(); hidden * hidden],
+            biases: vec![zero(); hidden]
+        }
+    }
+
+    // adds the gradients of a step, given the gradients with regard to the
+    // pre-activation values, and the input and state of this step
+    fn accumulate(&mut self, deltas: &[F], input: &[F], state: &[F]) {
+        let hidden = self.biases.len();
+        for (j, &delta) in deltas.iter().enumerate().take(hidden) {
+            for (w, &x) in self.input_weights[j*self.inputs..(j+1)*self.inputs].iter_mut().zip(input) {
+                *w = *w + delta * x;
+            }
+            for (w, &s) in self.recurrent_weights[j*hidden..(j+1)*hidden].iter_mut().zip(state) {
+                *w = *w + delta * s;
+            }
+            self.biases[j] = self.biases[j] + delta;
+        }
+    }
+}
+
+// the gradient of the squared error of a step with regard to its output,
+// plus the gradient coming from the next steps
+fn step_errors<F: Float>(output: &[F], target: &[F], next: &[F]) -> Vec<F> {
+    output.iter().zip(next).enumerate().map(|(j, (&o, &n))| {
+        o - target.get(j).cloned().unwrap_or(zero()) + n
+    }).collect()
+}
+
+fn set_state<F: Float>(state: &mut [F], value: &[F]) {
+    for (s, &v) in state.iter_mut().zip(value) {
+        *s = v;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Compute, Matrix, SequenceTrain, StatefulCompute};
+    use activations::{identity, Activation};
+    use init::Initializer;
+    use training::{Bptt, GradientDescent};
+
+    use super::{Gate, Gru, SimpleRnn};
+
+    #[test]
+    fn step() {
+        // h(t) = x(t) + 0.5 * h(t-1)
+        let mut rnn = SimpleRnn::from_matrices(Matrix::from_vec(1, 1, vec![1.0f64]),
+                                               Matrix::from_vec(1, 1, vec![0.5]),
+                                               vec![0.0], identity());
+        assert_eq!((rnn.input_size(), rnn.output_size()), (1, 1));
+        assert_eq!(rnn.compute(&[1.0]), [1.0]);
+        assert_eq!(rnn.state(), [0.0]);
+        assert_eq!(rnn.compute_sequence(&[vec![1.0], vec![0.0], vec![2.0]]),
+                   [vec![1.0], vec![0.5], vec![2.25]]);
+        assert_eq!(rnn.state(), [2.25]);
+        rnn.reset_state();
+        assert_eq!(rnn.step(&[1.0]), [1.0]);
+    }
+
+    #[test]
+    fn init() {
+        let rnn = SimpleRnn::<f32, _>::new_init(3, 4, Activation::Sigmoid, Initializer::Xavier);
+        assert_eq!(rnn.recurrent_weights().rows(), 4);
+        assert!(rnn.input_weights().as_slice().iter().any(|&w| w != 0.0));
+        assert!(rnn.recurrent_weights().as_slice().iter().any(|&w| w != 0.0));
+        assert_eq!(rnn.compute(&[0.0, 0.0, 0.0]), [0.5; 4]);
+    }
+
+    #[test]
+    fn gru() {
+        let mut gru = Gru::<f64>::new(2, 2);
+        assert_eq!((gru.input_size(), gru.output_size()), (2, 2));
+        // all gates at 0.5 and a null candidate: the state is halved
+        gru.set_state(&[1.0, -2.0]);
+        assert_eq!(gru.step(&[3.0, 4.0]), [0.5, -1.0]);
+        // an open update gate replaces the state by the candidate
+        gru.biases_mut(Gate::Update)[0] = 50.0;
+        gru.biases_mut(Gate::Candidate)[0] = 1.0;
+        gru.input_weights_mut(Gate::Candidate)[(1, 0)] = 1.0;
+        let out = gru.compute(&[0.5, 0.0]);
+        assert!((out[0] - 1.0f64.tanh()).abs() < 1e-9);
+        assert!((out[1] - (-0.5 + 0.5 * 0.5f64.tanh())).abs() < 1e-9);
+        gru.reset_state();
+        assert_eq!(gru.state(), [0.0, 0.0]);
+        assert_eq!(gru.recurrent_weights(Gate::Reset).rows(), 2);
+    }
+
+    #[test]
+    fn bptt() {
+        // learn h(t) = x(t) + 0.5 * h(t-1)
+        let mut rnn = SimpleRnn::new(1, 1, identity());
+        let inputs = (0..20).map(|i| vec![((i * 7) % 5) as f64 / 5.0 - 0.4]).collect::<Vec<_>>();
+        let mut expected = SimpleRnn::from_matrices(Matrix::from_vec(1, 1, vec![1.0]),
+                                                    Matrix::from_vec(1, 1, vec![0.5]),
+                                                    vec![0.0], identity());
+        let targets = expected.compute_sequence(&inputs);
+        let rule = Bptt::new(GradientDescent::new(0.1).unwrap(), 5).unwrap();
+        for _ in 0..500 {
+            rnn.reset_state();
+            rnn.sequence_train(&rule, &inputs, &targets);
+        }
+        assert!((rnn.input_weights()[(0, 0)] - 1.0).abs() < 0.01);
+        assert!((rnn.recurrent_weights()[(0, 0)] - 0.5).abs() < 0.01);
+        assert!(rnn.biases()[0].abs() < 0.01);
+    }
+
+    #[test]
+    fn gru_bptt() {
+        // the updates of a plain gradient descent are the gradients of the
+        // squared error, which are checked against finite differences
+        let gru = Gru::<f64>::new_init(2, 3, Initializer::Xavier);
+        let inputs = vec![vec![0.5, -1.0], vec![1.0, 0.2], vec![-0.3, 0.8], vec![0.0, 1.0]];
+        let targets = vec![vec![0.1, 0.2, -0.3], vec![0.4, -0.5, 0.6], vec![0.0, 0.3, 0.3], vec![-0.2, 0.1, 0.5]];
+        let error = |gru: &Gru<f64>| {
+            let mut gru = gru.clone();
+            gru.compute_sequence(&inputs).iter().zip(&targets).fold(0.0, |s, (o, t)| {
+                s + o.iter().zip(t).fold(0.0, |s, (o, t)| s + 0.5 * (o - t) * (o - t))
+            })
+        };
+        let rate = 1e-3;
+        let mut trained = gru.clone();
+        trained.sequence_train(&Bptt::new(GradientDescent::new(rate).unwrap(), 4).unwrap(), &inputs, &targets);
+        let eps = 1e-6;
+        for &gate in &[Gate::Update, Gate::Reset, Gate::Candidate] {
+            let check = |gradient: f64, modify: &dyn Fn(&mut Gru<f64>, f64)| {
+                let (mut plus, mut minus) = (gru.clone(), gru.clone());
+                modify(&mut plus, eps);
+                modify(&mut minus, -eps);
+                let numeric = (error(&plus) - error(&minus)) / (2.0 * eps);
+                assert!((gradient - numeric).abs() < 1e-6, "{:?}: {} != {}", gate, gradient, numeric);
+            };
+            check((gru.input_weights(gate)[(1, 0)] - trained.input_weights(gate)[(1, 0)]) / rate,
+                  &|g, e| g.input_weights_mut(gate)[(1, 0)] += e);
+            check((gru.recurrent_weights(gate)[(2, 1)] - trained.recurrent_weights(gate)[(2, 1)]) / rate,
+                  &|g, e| g.recurrent_weights_mut(gate)[(2, 1)] += e);
+            check((gru.biases(gate)[0] - trained.biases(gate)[0]) / rate,
+                  &|g, e| g.biases_mut(gate)[0] += e);
+        }
+        assert_eq!(trained.state(), &gru.clone().compute_sequence(&inputs)[3][..]);
+    }
+}
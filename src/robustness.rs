@@ -0,0 +1,284 @@
+//! Robustness analysis of networks.
+//!
+//! Networks implementing `IntervalCompute` can propagate intervals rather
+//! than values: given a box of inputs, they compute bounds that are
+//! guaranteed to contain all the outputs of the network for any input in
+//! this box. This allows to certify that bounded perturbations of an input
+//! cannot change the decision of a classifier.
+//!
+//! Conversely, networks implementing `Differentiable` can be attacked using
+//! adversarial examples: inputs perturbed in the direction that increases
+//! their error the most.
+
+use num::{Float, one, zero};
+
+use {Compute, Differentiable};
+use activations::{Activation, Softmax, VectorActivation};
+
+/// A trait for networks that can compute guaranteed bounds of their outputs.
+pub trait IntervalCompute<F: Float> {
+    /// Given the lower and upper bounds of each input, computes lower and
+    /// upper bounds of each output.
+    ///
+    /// The bounds are guaranteed, but not necessarily tight: any input
+    /// within the input bounds gives outputs within the output bounds, but
+    /// the output bounds may be wider than the actual range of outputs.
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>);
+}
+
+/// A trait for activation functions that can compute guaranteed bounds
+/// of their outputs.
+pub trait IntervalActivation<F: Float> {
+    /// Given the lower and upper bounds of the pre-activation values,
+    /// computes lower and upper bounds of the activated values.
+    fn activate_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>);
+}
+
+/// Checks that the most activated output of `network` (the class it picks,
+/// for a classifier) for `input` stays the same when each input is
+/// perturbed by at most `epsilon`.
+///
+/// Returns the index of this output if it is certified, `None` otherwise.
+/// As the bounds are not tight, `None` does not mean that a perturbation
+/// changing the decision actually exists.
+pub fn certify<F, N>(network: &N, input: &[F], epsilon: F) -> Option<usize>
+    where F: Float,
+          N: Compute<F> + IntervalCompute<F>
+{
+    let output = network.compute(input);
+    let class = (0..output.len()).fold(0, |best, i| if output[i] > output[best] { i } else { best });
+    let lower = input.iter().map(|&x| x - epsilon).collect::<Vec<_>>();
+    let upper = input.iter().map(|&x| x + epsilon).collect::<Vec<_>>();
+    let (out_lower, out_upper) = network.compute_bounds(&lower, &upper);
+    if (0..output.len()).all(|i| i == class || out_upper[i] < out_lower[class]) {
+        Some(class)
+    } else {
+        None
+    }
+}
+
+// gradient of the squared error 0.5*|output - target|^2 with regard to the input
+fn error_gradient<F, N>(network: &N, input: &[F], target: &[F]) -> Vec<F>
+    where F: Float,
+          N: Differentiable<F>
+{
+    let output = network.compute(input);
+    let errors = output.iter()
+                       .enumerate()
+                       .map(|(j, o)| *o - target.get(j).cloned().unwrap_or(zero()))
+                       .collect::<Vec<_>>();
+    network.input_gradient(input, &errors)
+}
+
+fn sign<F: Float>(x: F) -> F {
+    if x > zero() { one() } else if x < zero() { -one::<F>() } else { zero() }
+}
+
+/// Generates an adversarial example using the Fast Gradient Sign Method.
+///
+/// Each input is moved by `epsilon` in the direction increasing the squared
+/// error between the output of the network and `target` (typically the
+/// correct output for `input`).
+pub fn fgsm<F, N>(network: &N, input: &[F], target: &[F], epsilon: F) -> Vec<F>
+    where F: Float,
+          N: Differentiable<F>
+{
+    let gradient = error_gradient(network, input, target);
+    input.iter().zip(gradient).map(|(&x, g)| x + epsilon * sign(g)).collect()
+}
+
+/// Generates an adversarial example using Projected Gradient Descent.
+///
+/// This is an iterated version of `fgsm(..)`: `steps` steps of size
+/// `step_size` are done, each of them followed by a projection back into
+/// the box of inputs perturbed by at most `epsilon`.
+pub fn pgd<F, N>(network: &N,
+                 input: &[F],
+                 target: &[F],
+                 epsilon: F,
+                 step_size: F,
+                 steps: usize)
+    -> Vec<F>
+    where F: Float,
+          N: Differentiable<F>
+{
+    let mut adversarial = input.to_owned();
+    for _ in 0..steps {
+        let gradient = error_gradient(network, &adversarial, target);
+        for ((a, &x), g) in adversarial.iter_mut().zip(input).zip(gradient) {
+            *a = (*a + step_size * sign(g)).max(x - epsilon).min(x + epsilon);
+        }
+    }
+    adversarial
+}
+
+fn c<F: Float>(x: f64) -> F {
+    F::from(x).unwrap()
+}
+
+// Bounds of a function decreasing until `argmin` and increasing after it.
+fn valley_bounds<F, V>(f: V, argmin: F, min: F, l: F, u: F) -> (F, F)
+    where F: Float,
+          V: Fn(F) -> F
+{
+    let (fl, fu) = (f(l), f(u));
+    // the position of the minimum is approximate, take some margin
+    let margin = c::<F>(1e-3) * (one::<F>() + argmin.abs());
+    if l <= argmin + margin && u >= argmin - margin {
+        (min, fl.max(fu))
+    } else {
+        (fl.min(fu), fl.max(fu))
+    }
+}
+
+impl<F: Float> IntervalActivation<F> for Activation<F> {
+    fn activate_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        if let Activation::Softmax = *self {
+            return Softmax.activate_bounds(lower, upper);
+        }
+        lower.iter().zip(upper).map(|(&l, &u)| {
+            let f = |x: F| self.activate(&[x])[0];
+            match *self {
+                // non-decreasing functions
+                Activation::Identity | Activation::Relu | Activation::Sigmoid | Activation::Step
+                | Activation::StepWithThreshold(_) | Activation::Softplus
+                | Activation::Softsign => (f(l), f(u)),
+                Activation::SigmoidWithSteepness(k) => {
+                    if k >= zero() { (f(l), f(u)) } else { (f(u), f(l)) }
+                },
+                Activation::Gaussian => {
+                    let (fl, fu) = (f(l), f(u));
+                    let upper = if l <= zero() && u >= zero() { one() } else { fl.max(fu) };
+                    (fl.min(fu), upper)
+                },
+                Activation::Swish(beta) if beta > zero() => {
+                    valley_bounds(f, c::<F>(-1.278465) / beta, c::<F>(-0.27846455) / beta, l, u)
+                },
+                Activation::Swish(beta) if beta == zero() => (f(l), f(u)),
+                Activation::Gelu => valley_bounds(f, c(-0.752461), c(-0.17004076), l, u),
+                // nothing is known about these functions
+                _ => (F::neg_infinity(), F::infinity())
+            }
+        }).unzip()
+    }
+}
+
+impl<F: Float> IntervalActivation<F> for Softmax {
+    fn activate_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        // output i is the smallest when x_i is at its lower bound and all
+        // the others at their upper bound, and conversely
+        let shift = upper.iter().fold(F::neg_infinity(), |m, &x| m.max(x));
+        let sum_lower = lower.iter().fold(zero::<F>(), |s, &x| s + (x - shift).exp());
+        let sum_upper = upper.iter().fold(zero::<F>(), |s, &x| s + (x - shift).exp());
+        lower.iter().zip(upper).map(|(&l, &u)| {
+            let (el, eu) = ((l - shift).exp(), (u - shift).exp());
+            (el / (el + sum_upper - eu), eu / (eu + sum_lower - el))
+        }).unzip()
+    }
+}
+
+impl<F: Float, A: IntervalActivation<F>> IntervalActivation<F> for Vec<A> {
+    fn activate_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        lower.iter().zip(upper).enumerate().map(|(i, (&l, &u))| match self.get(i) {
+            Some(a) => {
+                let (l, u) = a.activate_bounds(&[l], &[u]);
+                (l[0], u[0])
+            },
+            None => (l, u)
+        }).unzip()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use activations::{Activation, VectorActivation};
+    use util::Chain;
+    use {Compute, FeedforwardLayer, Matrix};
+
+    use super::{IntervalActivation, IntervalCompute, certify, fgsm, pgd};
+
+    #[test]
+    fn activation_bounds() {
+        let (l, u) = Activation::Sigmoid.activate_bounds(&[-1.0f64], &[2.0]);
+        assert!(l[0] < 0.27 && u[0] > 0.88);
+        let (l, u) = Activation::Gaussian.activate_bounds(&[-1.0f64, 1.0], &[2.0, 2.0]);
+        assert_eq!(u[0], 1.0);
+        assert!(l[0] == (-4.0f64).exp() && u[1] == (-1.0f64).exp());
+        for &a in &[Activation::Gelu, Activation::Swish(1.0), Activation::Swish(3.0)] {
+            let (l, u) = a.activate_bounds(&[-3.0f64], &[1.0]);
+            for i in 0..400 {
+                let x = -3.0 + i as f64 * 0.01;
+                let y = a.activate(&[x])[0];
+                assert!(y >= l[0] && y <= u[0]);
+            }
+        }
+        let (l, u) = Activation::Softmax.activate_bounds(&[0.0f64, 1.0], &[0.5, 1.0]);
+        assert!((l[0] - 1.0 / (1.0 + 1.0f64.exp())).abs() < 1e-12);
+        assert!((u[0] - 1.0 / (1.0 + 0.5f64.exp())).abs() < 1e-12);
+        assert!(l[1] <= 1.0 - u[0] + 1e-12 && u[1] >= 1.0 - l[0] - 1e-12);
+    }
+
+    #[test]
+    fn network_bounds() {
+        let layer = FeedforwardLayer::from_matrices(
+            Matrix::from_vec(2, 2, vec![1.0f64, -1.0, 2.0, 0.5]),
+            vec![0.0, 1.0],
+            Activation::Identity
+        );
+        let (l, u) = layer.compute_bounds(&[0.0, -1.0], &[1.0, 1.0]);
+        assert_eq!(l, [-1.0, 0.5]);
+        assert_eq!(u, [2.0, 3.5]);
+
+        let net = Chain::new(layer, FeedforwardLayer::from_matrices(
+            Matrix::from_vec(2, 2, vec![1.0, 0.0, 0.0, 1.0]),
+            vec![0.0, 0.0],
+            Activation::Softmax
+        ));
+        // output 1 wins by a large margin
+        assert_eq!(certify(&net, &[0.0, 0.0], 0.01), Some(1));
+        assert_eq!(certify(&net, &[0.0, 0.0], 2.0), None);
+        assert_eq!(net.compute(&[0.0, 0.0]).len(), 2);
+    }
+
+    #[test]
+    fn relu_network_bounds() {
+        // the bounds of a ReLU are exact: [max(0, l), max(0, u)]
+        let (l, u) = Activation::Relu.activate_bounds(&[-2.0f64, -1.0, 0.5], &[-1.0, 3.0, 2.0]);
+        assert_eq!(l, [0.0, 0.0, 0.5]);
+        assert_eq!(u, [0.0, 3.0, 2.0]);
+
+        let net = Chain::new(
+            FeedforwardLayer::from_matrices(Matrix::from_vec(2, 2, vec![1.0f64, -1.0, -1.0, 1.0]),
+                                            vec![0.0, 0.0], Activation::Relu),
+            FeedforwardLayer::from_matrices(Matrix::from_vec(2, 2, vec![1.0, 0.0, 0.0, 1.0]),
+                                            vec![0.5, 0.0], Activation::Identity)
+        );
+        let (l, u) = net.compute_bounds(&[1.0, 0.0], &[2.0, 0.5]);
+        assert_eq!(l, [1.0, 0.0]);
+        assert_eq!(u, [2.5, 0.0]);
+        // output 0 is at least 1.0 and output 1 at most 0.0
+        assert_eq!(certify(&net, &[1.5, 0.25], 0.25), Some(0));
+        assert_eq!(certify(&net, &[1.5, 0.25], 2.0), None);
+    }
+
+    #[test]
+    fn adversarial_examples() {
+        let net = Chain::new(
+            FeedforwardLayer::from_matrices(Matrix::from_vec(2, 2, vec![1.0f64, -2.0, 0.5, 1.0]),
+                                            vec![0.0, 0.0], Activation::Sigmoid),
+            FeedforwardLayer::from_matrices(Matrix::from_vec(1, 2, vec![2.0, -1.0]),
+                                            vec![0.0], Activation::Identity)
+        );
+        let input = [0.3, -0.2];
+        let target = net.compute(&input);
+        let error = |x: &[f64]| (net.compute(x)[0] - target[0] + 0.1).powi(2);
+        // with a target slightly below the output, the attack pushes the output up
+        let shifted = [target[0] - 0.1];
+        let adv = fgsm(&net, &input, &shifted, 0.05);
+        assert!(adv.iter().zip(&input).all(|(a, x)| ((a - x).abs() - 0.05).abs() < 1e-12));
+        assert!(error(&adv) > error(&input));
+        let adv2 = pgd(&net, &input, &shifted, 0.05, 0.01, 20);
+        assert!(adv2.iter().zip(&input).all(|(a, x)| (a - x).abs() <= 0.05 + 1e-12));
+        assert!(error(&adv2) >= error(&adv) - 1e-9);
+    }
+}
@@ -0,0 +1,233 @@
+//! Serving of networks.
+//!
+//! The `AbRouter` adapter rolls a new version of a network out safely: it
+//! serves a fraction of the computations with this challenger, the others
+//! with the current champion, and records the outputs of both networks for
+//! each input, so that they can be compared offline before switching. Only
+//! the most recent records are kept, up to a capacity.
+
+use std::cell::{Cell, Ref, RefCell};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+use num::Float;
+
+use rand::random;
+
+use Compute;
+
+/// One of the two networks of an `AbRouter`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Variant {
+    /// The network currently in production.
+    Champion,
+    /// The network being rolled out.
+    Challenger
+}
+
+/// The record of one computation of an `AbRouter`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pair<F> {
+    /// The input of the computation.
+    pub input: Vec<F>,
+    /// The output of the champion.
+    pub champion: Vec<F>,
+    /// The output of the challenger.
+    pub challenger: Vec<F>,
+    /// The network whose output was returned.
+    pub served: Variant
+}
+
+/// An adapter routing each computation to one of two networks, serving the
+/// output of the challenger with probability `fraction` and the output of
+/// the champion otherwise.
+///
+/// Both networks are computed on each input, and their paired outputs are
+/// recorded until taken with `take_pairs()`. Once `capacity()` pairs are
+/// recorded, the oldest one is dropped for each new one, 1000 pairs being
+/// kept by default. The two networks must have the same number of inputs
+/// and outputs.
+pub struct AbRouter<F, A, B> {
+    _marker: PhantomData<F>,
+    champion: A,
+    challenger: B,
+    fraction: f64,
+    counts: Cell<(usize, usize)>,
+    capacity: usize,
+    pairs: RefCell<VecDeque<Pair<F>>>
+}
+
+impl<F: Float, A: Compute<F>, B: Compute<F>> AbRouter<F, A, B> {
+    /// Routes the computations between `champion` and `challenger`, the
+    /// latter serving given fraction of them.
+    ///
+    /// Panics if the fraction is not between `0.0` and `1.0`, or if the
+    /// networks do not have the same number of inputs and outputs.
+    pub fn new(champion: A, challenger: B, fraction: f64) -> AbRouter<F, A, B> {
+        assert!((0.0..=1.0).contains(&fraction), "The fraction must be between 0 and 1.");
+        assert!(champion.input_size() == challenger.input_size()
+                    && champion.output_size() == challenger.output_size(),
+                "The champion and the challenger must have the same number of inputs and outputs.");
+        AbRouter {
+            _marker: PhantomData,
+            champion,
+            challenger,
+            fraction,
+            counts: Cell::new((0, 0)),
+            capacity: 1000,
+            pairs: RefCell::new(VecDeque::new())
+        }
+    }
+
+    /// Sets the number of paired outputs kept, the oldest ones being
+    /// dropped first.
+    ///
+    /// Panics if it is zero.
+    pub fn with_capacity(mut self, capacity: usize) -> AbRouter<F, A, B> {
+        assert!(capacity > 0, "At least one pair must be kept.");
+        self.capacity = capacity;
+        let pairs = self.pairs.get_mut();
+        while pairs.len() > capacity {
+            pairs.pop_front();
+        }
+        self
+    }
+
+    /// The number of paired outputs kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The network currently in production.
+    pub fn champion(&self) -> &A {
+        &self.champion
+    }
+
+    /// The network being rolled out.
+    pub fn challenger(&self) -> &B {
+        &self.challenger
+    }
+
+    /// The fraction of the computations served by the challenger.
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+
+    /// Changes the fraction of the computations served by the challenger,
+    /// for example to widen the rollout.
+    ///
+    /// Panics if it is not between `0.0` and `1.0`.
+    pub fn set_fraction(&mut self, fraction: f64) {
+        assert!((0.0..=1.0).contains(&fraction), "The fraction must be between 0 and 1.");
+        self.fraction = fraction;
+    }
+
+    /// The number of computations served by given network so far.
+    pub fn count(&self, variant: Variant) -> usize {
+        let (champion, challenger) = self.counts.get();
+        match variant {
+            Variant::Champion => champion,
+            Variant::Challenger => challenger
+        }
+    }
+
+    /// The paired outputs recorded since the last call to `take_pairs()`,
+    /// from the oldest to the most recent.
+    pub fn pairs<'a>(&'a self) -> Ref<'a, VecDeque<Pair<F>>> {
+        self.pairs.borrow()
+    }
+
+    /// Returns the paired outputs recorded so far, and clears them.
+    pub fn take_pairs(&mut self) -> Vec<Pair<F>> {
+        self.pairs.get_mut().drain(..).collect()
+    }
+
+    /// Consumes the adapter, returning the champion and the challenger.
+    pub fn into_parts(self) -> (A, B) {
+        (self.champion, self.challenger)
+    }
+}
+
+impl<F: Float, A: Compute<F>, B: Compute<F>> Compute<F> for AbRouter<F, A, B> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let champion = self.champion.compute(input);
+        let challenger = self.challenger.compute(input);
+        let (mut champions, mut challengers) = self.counts.get();
+        let served = if random::<f64>() < self.fraction {
+            challengers += 1;
+            Variant::Challenger
+        } else {
+            champions += 1;
+            Variant::Champion
+        };
+        self.counts.set((champions, challengers));
+        let output = match served {
+            Variant::Champion => champion.clone(),
+            Variant::Challenger => challenger.clone()
+        };
+        let mut pairs = self.pairs.borrow_mut();
+        if pairs.len() == self.capacity {
+            pairs.pop_front();
+        }
+        pairs.push_back(Pair {
+            input: input.to_owned(),
+            champion,
+            challenger,
+            served
+        });
+        output
+    }
+
+    fn input_size(&self) -> usize {
+        self.champion.input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.champion.output_size()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.champion.set_training(training);
+        self.challenger.set_training(training);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use Compute;
+    use util::FixedOutput;
+
+    use super::{AbRouter, Variant};
+
+    #[test]
+    fn routing() {
+        let mut router = AbRouter::new(FixedOutput::new(&[1.0f64]), FixedOutput::new(&[2.0]), 0.0);
+        assert_eq!(router.compute(&[0.5]), [1.0]);
+        router.set_fraction(1.0);
+        assert_eq!(router.compute(&[0.25]), [2.0]);
+        assert_eq!((router.count(Variant::Champion), router.count(Variant::Challenger)), (1, 1));
+        let pairs = router.take_pairs();
+        assert_eq!(pairs[0].input, [0.5]);
+        assert_eq!((&pairs[1].champion[..], &pairs[1].challenger[..]), (&[1.0][..], &[2.0][..]));
+        assert_eq!((pairs[0].served, pairs[1].served), (Variant::Champion, Variant::Challenger));
+        assert!(router.pairs().is_empty());
+
+        router.set_fraction(0.25);
+        for _ in 0..1000 {
+            router.compute(&[]);
+        }
+        let challenged = router.count(Variant::Challenger) - 1;
+        assert!(challenged > 180 && challenged < 320);
+        assert_eq!(router.pairs().iter().filter(|p| p.served == Variant::Challenger).count(), challenged);
+
+        // only the most recent pairs are kept
+        let mut router = router.with_capacity(3);
+        assert_eq!(router.pairs().len(), 3);
+        router.set_fraction(0.0);
+        for i in 0..5 {
+            router.compute(&[i as f64]);
+        }
+        let inputs = router.take_pairs().into_iter().map(|p| p.input[0]).collect::<Vec<_>>();
+        assert_eq!(inputs, [2.0, 3.0, 4.0]);
+    }
+}
@@ -0,0 +1,189 @@
+//! Softmax output layers
+
+use num::{Float, zero};
+
+use {Compute, Differentiable, BackpropTrain, SupervisedTrain};
+use activations::{Activation, Softmax, VectorActivation};
+use feedforward::FeedforwardLayer;
+use init::Initializer;
+use linalg::Matrix;
+use robustness::{IntervalCompute, IntervalActivation};
+use training::GradientMethod;
+
+/// A softmax output layer for classification
+///
+/// It computes the same thing as a `FeedforwardLayer` with a `softmax()`
+/// activation: its outputs are all positive and sum to `1.0`, giving the
+/// probability of each class.
+///
+/// Its training however minimizes the cross-entropy between its outputs and
+/// the targets, which should be probability distributions over the classes
+/// (typically one-hot vectors). The error on the pre-activation values is
+/// then simply `Y - T`, which is both cheaper to compute and better behaved
+/// than the squared error through the softmax Jacobian, whose gradients
+/// vanish when the outputs saturate.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SoftmaxLayer<F: Float> {
+    linear: FeedforwardLayer<F, Activation<F>>
+}
+
+impl<F: Float> SoftmaxLayer<F> {
+    /// Creates a new softmax layer with all its weights and biases set to 0.
+    pub fn new(inputs: usize, outputs: usize) -> SoftmaxLayer<F> {
+        SoftmaxLayer {
+            linear: FeedforwardLayer::new(inputs, outputs, Activation::Identity)
+        }
+    }
+
+    /// Creates a new softmax layer with its weights drawn according to given
+    /// initialization scheme, and its biases set to 0.
+    pub fn new_init(inputs: usize, outputs: usize, init: Initializer<F>) -> SoftmaxLayer<F> {
+        SoftmaxLayer {
+            linear: FeedforwardLayer::new_init(inputs, outputs, Activation::Identity, init)
+        }
+    }
+
+    /// Creates a new softmax layer from its weights matrix and its biases
+    /// vector, see `FeedforwardLayer::from_matrices(..)`.
+    pub fn from_matrices(weights: Matrix<F>, biases: Vec<F>) -> SoftmaxLayer<F> {
+        SoftmaxLayer {
+            linear: FeedforwardLayer::from_matrices(weights, biases, Activation::Identity)
+        }
+    }
+
+    /// The weights matrix of the layer: the weight between input `i` and
+    /// output `j` is at `(j, i)`.
+    pub fn weights(&self) -> &Matrix<F> {
+        self.linear.weights()
+    }
+
+    /// Mutable access to the weights matrix of the layer, see `weights()`.
+    pub fn weights_mut(&mut self) -> &mut Matrix<F> {
+        self.linear.weights_mut()
+    }
+
+    /// The biases of the layer, one per output.
+    pub fn biases(&self) -> &[F] {
+        self.linear.biases()
+    }
+
+    /// Mutable access to the biases of the layer.
+    pub fn biases_mut(&mut self) -> &mut [F] {
+        self.linear.biases_mut()
+    }
+
+    /// Clears the state kept by the training method between training steps,
+    /// like the velocities of a gradient descent with momentum.
+    pub fn reset_optimizer(&mut self) {
+        self.linear.reset_optimizer();
+    }
+
+    /// The index of the most probable class for `input`.
+    pub fn classify(&self, input: &[F]) -> usize {
+        let logits = self.linear.compute(input);
+        (0..logits.len()).fold(0, |best, j| if logits[j] > logits[best] { j } else { best })
+    }
+}
+
+impl<F: Float> Compute<F> for SoftmaxLayer<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        Softmax.activate(&self.linear.compute(input))
+    }
+
+    fn input_size(&self) -> usize {
+        self.linear.input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.linear.output_size()
+    }
+}
+
+impl<F: Float> Differentiable<F> for SoftmaxLayer<F> {
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let logits = self.linear.compute(input);
+        let out = Softmax.activate(&logits);
+        let mut output_gradient = output_gradient.to_owned();
+        output_gradient.resize(out.len(), zero());
+        let deltas = Softmax.jacobian_product_with_output(&logits, &out, &output_gradient);
+        self.linear.input_gradient(input, &deltas)
+    }
+}
+
+impl<F: Float, M: GradientMethod<F>> BackpropTrain<F, M> for SoftmaxLayer<F> {
+    fn backprop_train(&mut self, rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let logits = self.linear.compute(input);
+        let out = Softmax.activate(&logits);
+        // the linear part is trained with the error Y - T on its outputs
+        let linear_target = logits.iter().zip(&out).enumerate().map(|(j, (&z, &y))| {
+            z - y + target.get(j).cloned().unwrap_or(zero())
+        }).collect::<Vec<_>>();
+        self.linear.backprop_train(rule, input, &linear_target)
+    }
+}
+
+impl<F: Float, M: GradientMethod<F>> SupervisedTrain<F, M> for SoftmaxLayer<F> {
+    fn supervised_train(&mut self,
+                        rule: &M,
+                        input: &[F],
+                        target: &[F])
+    {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F: Float> IntervalCompute<F> for SoftmaxLayer<F> {
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        let (lower, upper) = self.linear.compute_bounds(lower, upper);
+        Softmax.activate_bounds(&lower, &upper)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Compute, Differentiable, FeedforwardLayer, Matrix, SupervisedTrain};
+    use activations::softmax;
+    use training::GradientDescent;
+
+    use super::SoftmaxLayer;
+
+    #[test]
+    fn compute() {
+        let weights = Matrix::from_vec(3, 2, vec![1.0f64, 0.0, 0.0, 1.0, -1.0, 2.0]);
+        let layer = SoftmaxLayer::from_matrices(weights.clone(), vec![0.0, 0.5, 0.0]);
+        let reference = FeedforwardLayer::from_matrices(weights, vec![0.0, 0.5, 0.0], softmax());
+        assert_eq!(layer.compute(&[0.3, -0.2]), reference.compute(&[0.3, -0.2]));
+        assert_eq!(layer.input_gradient(&[0.3, -0.2], &[1.0, 0.0, -1.0]),
+                   reference.input_gradient(&[0.3, -0.2], &[1.0, 0.0, -1.0]));
+        assert_eq!(layer.classify(&[0.0, 0.0]), 1);
+        assert_eq!(layer.classify(&[-1.0, 1.0]), 2);
+    }
+
+    #[test]
+    fn cross_entropy_gradient() {
+        // outputs start uniform: the error on each class is 1/3 - t
+        let mut layer = SoftmaxLayer::new(1, 3);
+        layer.supervised_train(&GradientDescent::new(3.0).unwrap(), &[1.0f64], &[0.0, 1.0, 0.0]);
+        assert!((layer.weights()[(0, 0)] + 1.0).abs() < 1e-12);
+        assert!((layer.weights()[(1, 0)] - 2.0).abs() < 1e-12);
+        assert!((layer.biases()[2] + 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn classification() {
+        let samples = [([1.0f32, 0.0], 0), ([0.0, 1.0], 1), ([-1.0, -1.0], 2)];
+        let mut layer = SoftmaxLayer::new(2, 3);
+        let rule = GradientDescent::new(0.5).unwrap();
+        for _ in 0..100 {
+            for &(x, c) in &samples {
+                let mut target = [0.0; 3];
+                target[c] = 1.0;
+                layer.supervised_train(&rule, &x, &target);
+            }
+        }
+        for &(x, c) in &samples {
+            assert_eq!(layer.classify(&x), c);
+            assert!(layer.compute(&x)[c] > 0.9);
+        }
+    }
+}
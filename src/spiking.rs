@@ -0,0 +1,440 @@
+//! Spiking networks.
+//!
+//! A multi-layer perceptron trained with backpropagation can be converted
+//! into a network of integrate-and-fire neurons, where the activations of
+//! its ReLU layers are encoded by the spike rates of the neurons.
+//!
+//! Each hidden layer becomes a layer of integrate-and-fire neurons with a
+//! threshold of `1.0`, which accumulate their input current into their
+//! membrane potential, and emit a spike each time it reaches the threshold,
+//! which is then subtracted from it. The spike rate of such a neuron, over
+//! enough time steps, is its ReLU activation clipped to `[0, 1]`. The weights
+//! are normalized layer by layer with the highest activation each layer
+//! reaches on a set of samples, so that the spike rates stay below `1.0`.
+//!
+//! The spikes of a simulation can be recorded in a `Raster`, which gives the
+//! firing rates, interspike-interval statistics and synchrony of the neurons,
+//! and exports the spikes as CSV for plotting.
+
+use std::error::Error;
+use std::fmt;
+
+use num::{Float, one, zero};
+
+use Compute;
+use activations::{Activation, VectorActivation};
+use linalg::Matrix;
+use mlp::Mlp;
+
+/// The error returned when converting a multi-layer perceptron that is not
+/// suited to spiking neurons.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConversionError {
+    /// The hidden layer at given index does not use `Activation::Relu`.
+    Activation(usize),
+    /// No sample was given to normalize the weights.
+    NoSamples
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConversionError::Activation(i) => write!(f, "the hidden layer {} does not use ReLU", i),
+            ConversionError::NoSamples => write!(f, "samples are needed to normalize the weights")
+        }
+    }
+}
+
+impl Error for ConversionError {}
+
+// a layer of integrate-and-fire neurons with a threshold of 1.0
+#[derive(Clone, Debug, PartialEq)]
+struct SpikingLayer<F: Float> {
+    weights: Matrix<F>,
+    biases: Vec<F>
+}
+
+/// A network of integrate-and-fire neurons converted from a ReLU multi-layer
+/// perceptron, see `from_mlp(..)`.
+///
+/// Its inputs are injected as constant currents into the first layer, and
+/// its output layer does not spike: it receives the spike rates of the last
+/// hidden layer, and applies the activation of the output layer of the
+/// perceptron to the matching pre-activation values.
+///
+/// Each computation simulates the network from its resting state for a
+/// given number of time steps: the more steps, the closer the outputs to
+/// those of the perceptron.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpikingNetwork<F: Float> {
+    hidden: Vec<SpikingLayer<F>>,
+    output: SpikingLayer<F>,
+    activation: Activation<F>,
+    scales: Vec<F>,
+    steps: usize
+}
+
+impl<F: Float> SpikingNetwork<F> {
+    /// Converts a trained multi-layer perceptron, whose hidden layers all use
+    /// `Activation::Relu`, normalizing its weights with the activations it
+    /// computes on given samples of its inputs.
+    ///
+    /// The network simulates 100 time steps by default, see
+    /// `with_steps(..)`.
+    pub fn from_mlp(mlp: &Mlp<F>, samples: &[Vec<F>]) -> Result<SpikingNetwork<F>, ConversionError> {
+        let layers = mlp.layers();
+        let (last, hidden) = layers.split_last().unwrap();
+        for (i, layer) in hidden.iter().enumerate() {
+            if *layer.activation() != Activation::Relu {
+                return Err(ConversionError::Activation(i));
+            }
+        }
+        if samples.is_empty() {
+            return Err(ConversionError::NoSamples);
+        }
+
+        // the highest activation of each hidden layer over the samples
+        let mut scales = vec![zero::<F>(); hidden.len()];
+        for sample in samples {
+            let mut x = sample.clone();
+            for (layer, scale) in hidden.iter().zip(scales.iter_mut()) {
+                x = layer.compute(&x);
+                *scale = x.iter().fold(*scale, |m, &a| m.max(a));
+            }
+        }
+        for scale in &mut scales {
+            if *scale <= zero() {
+                // a silent layer, whatever its scale
+                *scale = one();
+            }
+        }
+
+        let mut previous = one::<F>();
+        let mut spiking = Vec::with_capacity(hidden.len());
+        for (layer, &scale) in hidden.iter().zip(&scales) {
+            spiking.push(SpikingLayer {
+                weights: scaled(layer.weights(), previous / scale),
+                biases: layer.biases().iter().map(|&b| b / scale).collect()
+            });
+            previous = scale;
+        }
+        Ok(SpikingNetwork {
+            hidden: spiking,
+            output: SpikingLayer {
+                weights: scaled(last.weights(), previous),
+                biases: last.biases().to_owned()
+            },
+            activation: *last.activation(),
+            scales,
+            steps: 100
+        })
+    }
+
+    /// Sets the number of time steps simulated by each computation.
+    pub fn with_steps(mut self, steps: usize) -> SpikingNetwork<F> {
+        assert!(steps > 0, "At least one time step must be simulated.");
+        self.steps = steps;
+        self
+    }
+
+    /// The number of time steps simulated by each computation.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// The highest activation of each hidden layer of the perceptron on the
+    /// samples, which its weights were normalized with.
+    pub fn scales(&self) -> &[F] {
+        &self.scales
+    }
+
+    /// Simulates the network on given input, returning the number of spikes
+    /// emitted by each neuron of each hidden layer.
+    pub fn spike_counts(&self, input: &[F]) -> Vec<Vec<usize>> {
+        let mut counts = self.hidden.iter().map(|l| vec![0; l.biases.len()]).collect::<Vec<_>>();
+        self.simulate(input, |spike| counts[spike.layer][spike.neuron] += 1);
+        counts
+    }
+
+    /// Simulates the network on given input, recording all the spikes of the
+    /// hidden layers.
+    pub fn raster(&self, input: &[F]) -> Raster {
+        let mut spikes = Vec::new();
+        self.simulate(input, |spike| spikes.push(spike));
+        Raster {
+            steps: self.steps,
+            sizes: self.hidden.iter().map(|l| l.biases.len()).collect(),
+            spikes
+        }
+    }
+
+    // simulates the network from its resting state, reporting each spike of
+    // the hidden layers in order
+    fn simulate<S: FnMut(Spike)>(&self, input: &[F], mut on_spike: S) {
+        let mut potentials = self.hidden.iter().map(|l| vec![zero::<F>(); l.biases.len()]).collect::<Vec<_>>();
+        for step in 0..self.steps {
+            let mut current = input.to_owned();
+            for (layer, (weights, potentials)) in self.hidden.iter().zip(&mut potentials).enumerate() {
+                let inputs = weights.weights.mul_vec(&current);
+                current = potentials.iter_mut().zip(inputs).zip(&weights.biases).enumerate()
+                    .map(|(neuron, ((v, i), &b))| {
+                        *v = *v + i + b;
+                        if *v >= one() {
+                            *v = *v - one();
+                            on_spike(Spike { step, layer, neuron });
+                            one()
+                        } else {
+                            zero()
+                        }
+                    }).collect();
+            }
+        }
+    }
+}
+
+// the matrix multiplied by given factor
+fn scaled<F: Float>(matrix: &Matrix<F>, factor: F) -> Matrix<F> {
+    Matrix::from_vec(matrix.rows(), matrix.cols(), matrix.as_slice().iter().map(|&w| w * factor).collect())
+}
+
+/*
+ * Spike analysis
+ */
+
+/// A spike emitted by a neuron of a hidden layer of a `SpikingNetwork`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Spike {
+    /// The time step of the spike, starting at `0`.
+    pub step: usize,
+    /// The index of the hidden layer of the neuron.
+    pub layer: usize,
+    /// The index of the neuron in its layer.
+    pub neuron: usize
+}
+
+/// The statistics of the intervals between the successive spikes of a
+/// neuron, in time steps.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IsiStats {
+    /// The number of intervals, one less than the number of spikes.
+    pub count: usize,
+    /// The mean interval.
+    pub mean: f64,
+    /// The standard deviation of the intervals.
+    pub std_dev: f64,
+    /// The coefficient of variation `std_dev / mean`, `0.0` for a regular
+    /// neuron and `1.0` for a Poisson one.
+    pub cv: f64
+}
+
+/// The spikes of a simulation of a `SpikingNetwork`, see
+/// `SpikingNetwork::raster(..)`.
+///
+/// Layers are given by their index among the hidden layers, and the
+/// analysis methods panic if it is out of range.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raster {
+    steps: usize,
+    sizes: Vec<usize>,
+    spikes: Vec<Spike>
+}
+
+impl Raster {
+    /// The number of time steps simulated.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// The number of neurons of each hidden layer.
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    /// All the spikes, ordered by time step.
+    pub fn spikes(&self) -> &[Spike] {
+        &self.spikes
+    }
+
+    /// The number of spikes of each neuron of given layer.
+    pub fn counts(&self, layer: usize) -> Vec<usize> {
+        let mut counts = vec![0; self.sizes[layer]];
+        for spike in self.spikes.iter().filter(|s| s.layer == layer) {
+            counts[spike.neuron] += 1;
+        }
+        counts
+    }
+
+    /// The firing rate of each neuron of given layer, in spikes per time
+    /// step.
+    pub fn firing_rates(&self, layer: usize) -> Vec<f64> {
+        self.counts(layer).into_iter().map(|c| c as f64 / self.steps as f64).collect()
+    }
+
+    /// The statistics of the interspike intervals of given neuron, or `None`
+    /// if it spiked less than twice.
+    pub fn isi(&self, layer: usize, neuron: usize) -> Option<IsiStats> {
+        assert!(neuron < self.sizes[layer], "No such neuron in the layer.");
+        let steps = self.spikes.iter()
+            .filter(|s| s.layer == layer && s.neuron == neuron)
+            .map(|s| s.step as f64)
+            .collect::<Vec<_>>();
+        if steps.len() < 2 {
+            return None;
+        }
+        let intervals = steps.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+        let count = intervals.len();
+        let mean = intervals.iter().sum::<f64>() / count as f64;
+        let variance = intervals.iter().map(|i| (i - mean) * (i - mean)).sum::<f64>() / count as f64;
+        let std_dev = variance.sqrt();
+        Some(IsiStats { count, mean, std_dev, cv: std_dev / mean })
+    }
+
+    /// The synchrony of the neurons of given layer, as the measure `chi` of
+    /// Golomb and Rinzel, with the spikes counted in bins of `bin` time
+    /// steps.
+    ///
+    /// It is the standard deviation over time of the mean activity of the
+    /// layer, divided by the root mean square of the standard deviations of
+    /// the activities of its neurons: `1.0` for neurons spiking in unison,
+    /// and close to `0.0` for independent neurons in a large layer. Returns
+    /// `None` if the activities of all the neurons are constant.
+    pub fn synchrony(&self, layer: usize, bin: usize) -> Option<f64> {
+        assert!(bin > 0, "The bins must be at least one time step long.");
+        // the bin of the last step, plus one
+        let bins = (self.steps - 1) / bin + 1;
+        let neurons = self.sizes[layer];
+        let mut activity = vec![vec![0.0; bins]; neurons];
+        for spike in self.spikes.iter().filter(|s| s.layer == layer) {
+            activity[spike.neuron][spike.step / bin] += 1.0;
+        }
+        let variance = |trace: &[f64]| {
+            let mean = trace.iter().sum::<f64>() / trace.len() as f64;
+            trace.iter().map(|x| (x - mean) * (x - mean)).sum::<f64>() / trace.len() as f64
+        };
+        let neuron_variance = activity.iter().map(|t| variance(t)).sum::<f64>() / neurons as f64;
+        if neuron_variance <= 0.0 {
+            return None;
+        }
+        let population = (0..bins).map(|t| {
+            activity.iter().map(|a| a[t]).sum::<f64>() / neurons as f64
+        }).collect::<Vec<_>>();
+        Some((variance(&population) / neuron_variance).sqrt())
+    }
+
+    /// The spikes as CSV, with a header and one `step,layer,neuron` line per
+    /// spike.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("step,layer,neuron\n");
+        for spike in &self.spikes {
+            csv.push_str(&format!("{},{},{}\n", spike.step, spike.layer, spike.neuron));
+        }
+        csv
+    }
+}
+
+impl<F: Float> Compute<F> for SpikingNetwork<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let rates = match self.spike_counts(input).pop() {
+            Some(counts) => {
+                let steps = F::from(self.steps).unwrap();
+                counts.into_iter().map(|c| F::from(c).unwrap() / steps).collect()
+            },
+            // no hidden layer, the input goes straight to the output layer
+            None => input.to_owned()
+        };
+        let mut x = self.output.weights.mul_vec(&rates);
+        for (x, &b) in x.iter_mut().zip(&self.output.biases) {
+            *x = *x + b;
+        }
+        self.activation.activate(&x)
+    }
+
+    fn input_size(&self) -> usize {
+        self.hidden.first().unwrap_or(&self.output).weights.cols()
+    }
+
+    fn output_size(&self) -> usize {
+        self.output.biases.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Compute, FeedforwardLayer, Matrix, Mlp};
+    use activations::Activation;
+
+    use super::{ConversionError, Spike, SpikingNetwork};
+
+    fn mlp() -> Mlp<f64> {
+        let hidden = FeedforwardLayer::from_matrices(Matrix::from_vec(3, 2, vec![1.0, -1.0, 0.5, 2.0, -1.5, 0.5]),
+                                                     vec![0.1, 0.0, 0.2], Activation::Relu);
+        let output = FeedforwardLayer::from_matrices(Matrix::from_vec(2, 3, vec![1.0, 0.5, -1.0, -0.5, 1.0, 2.0]),
+                                                     vec![0.0, 0.1], Activation::Identity);
+        Mlp::from_layers(vec![hidden, output])
+    }
+
+    #[test]
+    fn conversion() {
+        let mlp = mlp();
+        let samples = (0..20).map(|i| {
+            let t = i as f64 / 19.0;
+            vec![2.0 * t - 1.0, 1.0 - t]
+        }).collect::<Vec<_>>();
+        let spiking = SpikingNetwork::from_mlp(&mlp, &samples).unwrap().with_steps(2000);
+        assert_eq!((spiking.input_size(), spiking.output_size()), (2, 2));
+        assert_eq!(spiking.scales().len(), 1);
+        for sample in &samples {
+            let (expected, actual) = (mlp.compute(sample), spiking.compute(sample));
+            for (e, a) in expected.iter().zip(actual) {
+                assert!((e - a).abs() < 0.02);
+            }
+        }
+        // the spike rates encode the normalized activations of the hidden layer
+        let hidden = mlp.layers()[0].compute(&samples[3]);
+        let counts = spiking.spike_counts(&samples[3]);
+        for (h, &c) in hidden.iter().zip(&counts[0]) {
+            assert!((h / spiking.scales()[0] - c as f64 / 2000.0).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn raster() {
+        let spiking = SpikingNetwork::from_mlp(&mlp(), &[vec![1.0, 0.0], vec![-0.5, 1.0]]).unwrap();
+        let raster = spiking.raster(&[0.3, 0.4]);
+        assert_eq!((raster.steps(), raster.sizes()), (100, &[3][..]));
+        assert_eq!(vec![raster.counts(0)], spiking.spike_counts(&[0.3, 0.4]));
+        let rates = raster.firing_rates(0);
+        assert_eq!(rates[1], raster.counts(0)[1] as f64 / 100.0);
+
+        // the neurons receive constant currents of 0.25 and 0.5
+        let layer = |weights| FeedforwardLayer::from_matrices(Matrix::from_vec(2, 1, weights), vec![0.0, 0.0],
+                                                              Activation::Relu);
+        let output = FeedforwardLayer::new(2, 1, Activation::Identity);
+        let mlp = Mlp::from_layers(vec![layer(vec![1.0, 2.0]), output.clone()]);
+        let raster = SpikingNetwork::from_mlp(&mlp, &[vec![0.5]]).unwrap().with_steps(20).raster(&[0.25]);
+        assert_eq!(raster.spikes()[..2], [Spike { step: 1, layer: 0, neuron: 1 },
+                                         Spike { step: 3, layer: 0, neuron: 0 }]);
+        assert!(raster.to_csv().starts_with("step,layer,neuron\n1,0,1\n3,0,0\n"));
+        let isi = raster.isi(0, 0).unwrap();
+        assert_eq!((isi.count, isi.mean, isi.std_dev, isi.cv), (4, 4.0, 0.0, 0.0));
+        assert_eq!(raster.isi(0, 1).unwrap().mean, 2.0);
+        assert!(raster.synchrony(0, 1).unwrap() < 1.0);
+
+        // identical neurons are fully synchronous
+        let mlp = Mlp::from_layers(vec![layer(vec![1.0, 1.0]), output]);
+        let raster = SpikingNetwork::from_mlp(&mlp, &[vec![0.5]]).unwrap().with_steps(20).raster(&[0.15]);
+        assert!((raster.synchrony(0, 1).unwrap() - 1.0).abs() < 1e-12);
+        let silent = SpikingNetwork::from_mlp(&mlp, &[vec![0.5]]).unwrap().raster(&[0.0]);
+        assert_eq!((silent.isi(0, 0), silent.synchrony(0, 5)), (None, None));
+    }
+
+    #[test]
+    fn invalid() {
+        let sigmoid = Mlp::from_layers(vec![
+            FeedforwardLayer::new(2, 2, Activation::Sigmoid),
+            FeedforwardLayer::new(2, 1, Activation::Identity)
+        ]);
+        assert_eq!(SpikingNetwork::from_mlp(&sigmoid, &[vec![0.0, 0.0]]).err(), Some(ConversionError::Activation(0)));
+        assert_eq!(SpikingNetwork::from_mlp(&mlp(), &[]).err(), Some(ConversionError::NoSamples));
+    }
+}
@@ -0,0 +1,752 @@
+//! Epoch-based training loops.
+//!
+//! A `Trainer` holds a network, a training method and a dataset of
+//! `(input, target)` pairs, and trains the network on the whole dataset
+//! repeatedly, one epoch after the other. At the end of each epoch, a
+//! `Callback` is notified with the loss of the network over the dataset,
+//! and decides whether to continue.
+
+use std::cmp::Ordering;
+
+use num::{Float, one, zero};
+
+use rand::{Rng, thread_rng};
+use rand::distributions::{Gamma, IndependentSample};
+
+use {Compute, Method, SupervisedTrain, BackpropTrain};
+use loss::{Loss, SquaredError, WithLoss};
+use training::{LearningRate, Schedule};
+use util::Ensemble;
+
+/// A trait for objects notified at the end of each epoch by a `Trainer`.
+///
+/// It is implemented by closures taking the epoch number (starting at `0`)
+/// and the loss, which never stop the training.
+pub trait Callback<F: Float, N> {
+    /// Called at the end of epoch `epoch` (starting at `0`), with the loss
+    /// of the network over the training dataset.
+    ///
+    /// Returns whether the training should continue.
+    fn on_epoch(&mut self, epoch: usize, loss: F, network: &mut N) -> bool;
+}
+
+impl<F: Float, N, C: FnMut(usize, F)> Callback<F, N> for C {
+    fn on_epoch(&mut self, epoch: usize, loss: F, _network: &mut N) -> bool {
+        self(epoch, loss);
+        true
+    }
+}
+
+/// Computes the mean over given samples of the squared error between the
+/// outputs of `network` and the targets.
+///
+/// Unlike `SquaredError`, the squared errors are not halved.
+pub fn mean_squared_error<F, N>(network: &N, data: &[(Vec<F>, Vec<F>)]) -> F
+    where F: Float,
+          N: Compute<F>
+{
+    let total = data.iter().fold(zero::<F>(), |s, (input, target)| {
+        let output = network.compute(input);
+        output.iter().enumerate().fold(s, |s, (j, &o)| {
+            let e = o - target.get(j).cloned().unwrap_or(zero());
+            s + e * e
+        })
+    });
+    total / F::from(data.len().max(1)).unwrap()
+}
+
+/// Computes the mean over given samples of the value of `loss` between the
+/// outputs of `network` and the targets.
+pub fn mean_loss<F, N, L>(network: &N, loss: &L, data: &[(Vec<F>, Vec<F>)]) -> F
+    where F: Float,
+          N: Compute<F>,
+          L: Loss<F>
+{
+    let total = data.iter().fold(zero::<F>(), |s, (input, target)| {
+        s + loss.value(&network.compute(input), target)
+    });
+    total / F::from(data.len().max(1)).unwrap()
+}
+
+// a callback adjusting the method at the beginning of each epoch
+type ScheduleFn<M> = Box<dyn Fn(&mut M, usize)>;
+
+/// A training loop over a dataset.
+///
+/// The network is switched to training mode (see `Compute::set_training(..)`)
+/// during the epochs, and back to evaluation mode to compute the loss and
+/// notify the callback.
+///
+/// The loss reported to the callback is the mean of `L` over the dataset,
+/// `SquaredError` by default, which is the loss minimized by the training
+/// of the layers. See `with_loss(..)` to minimize another one.
+pub struct Trainer<F: Float, N, M: Method, L = SquaredError> {
+    network: N,
+    method: M,
+    loss: L,
+    data: Vec<(Vec<F>, Vec<F>)>,
+    shuffle: bool,
+    mixup: Option<F>,
+    mining: Option<(F, F)>,
+    hardness: Vec<F>,
+    unlabeled: Option<(Vec<Vec<F>>, F)>,
+    pseudo_labels: Vec<(Vec<F>, Vec<F>)>,
+    epochs: usize,
+    schedule: Option<ScheduleFn<M>>
+}
+
+impl<F, N, M> Trainer<F, N, M>
+    where F: Float,
+          N: Compute<F> + SupervisedTrain<F, M>,
+          M: Method
+{
+    /// Creates a trainer of `network` using `method` on given samples.
+    ///
+    /// The samples are shuffled at the beginning of each epoch.
+    pub fn new(network: N, method: M, data: Vec<(Vec<F>, Vec<F>)>) -> Trainer<F, N, M> {
+        Trainer {
+            network,
+            method,
+            loss: SquaredError,
+            data,
+            shuffle: true,
+            mixup: None,
+            mining: None,
+            hardness: Vec::new(),
+            unlabeled: None,
+            pseudo_labels: Vec::new(),
+            epochs: 0,
+            schedule: None
+        }
+    }
+
+    /// Makes this trainer minimize and report `loss` instead of the squared
+    /// error, by wrapping the network in a `WithLoss`.
+    pub fn with_loss<L>(self, loss: L) -> Trainer<F, WithLoss<F, N, L>, M, L>
+        where N: BackpropTrain<F, M>,
+              L: Loss<F> + Clone
+    {
+        Trainer {
+            network: WithLoss::new(self.network, loss.clone()),
+            method: self.method,
+            loss,
+            data: self.data,
+            shuffle: self.shuffle,
+            mixup: self.mixup,
+            mining: self.mining,
+            hardness: self.hardness,
+            unlabeled: self.unlabeled,
+            pseudo_labels: self.pseudo_labels,
+            epochs: self.epochs,
+            schedule: self.schedule
+        }
+    }
+}
+
+impl<F, N, M, L> Trainer<F, N, M, L>
+    where F: Float,
+          N: Compute<F> + SupervisedTrain<F, M>,
+          M: Method,
+          L: Loss<F>
+{
+    /// Makes this trainer present the samples always in the same order.
+    pub fn without_shuffling(mut self) -> Trainer<F, N, M, L> {
+        self.shuffle = false;
+        self
+    }
+
+    /// Makes this trainer train on mixup samples: each sample is replaced by
+    /// a convex combination `l * a + (1 - l) * b` of its input and target
+    /// with those of another sample picked at random, `l` being drawn from
+    /// a Beta distribution with both parameters equal to `alpha`.
+    ///
+    /// A small `alpha` (like `0.2`) keeps most combinations close to the
+    /// original samples. The loss is still reported on the original samples.
+    ///
+    /// Panics if `alpha` is not positive.
+    pub fn with_mixup(mut self, alpha: F) -> Trainer<F, N, M, L> {
+        assert!(alpha > zero(), "The mixup parameter must be positive.");
+        self.mixup = Some(alpha);
+        self
+    }
+
+    /// Makes this trainer oversample the hardest samples: the loss of each
+    /// sample is tracked as an exponential moving average with given
+    /// `decay`, and the `fraction` of the samples with the highest average is
+    /// presented once more in each epoch.
+    ///
+    /// Useful on imbalanced datasets, where the rare samples stay hard longer.
+    /// The loss is still reported on the dataset as is.
+    ///
+    /// Panics if `fraction` is not in `(0, 1]` or `decay` not in `[0, 1)`.
+    pub fn with_hard_mining(mut self, fraction: F, decay: F) -> Trainer<F, N, M, L> {
+        assert!(fraction > zero() && fraction <= one(), "The fraction of hard samples must be in (0, 1].");
+        assert!(decay >= zero() && decay < one(), "The decay must be in [0, 1).");
+        self.mining = Some((fraction, decay));
+        self
+    }
+
+    /// Makes this trainer also learn from unlabeled samples, by
+    /// pseudo-labeling: at the end of each epoch, each of the `inputs` whose
+    /// highest output reaches `threshold` is labeled with a one-hot target of
+    /// this output, and the labeled samples are trained on along with the
+    /// dataset in the next epoch.
+    ///
+    /// It is meant for classifiers with a softmax output. The loss is still
+    /// reported on the dataset only.
+    ///
+    /// Panics if `threshold` is not positive.
+    pub fn with_unlabeled(mut self, inputs: Vec<Vec<F>>, threshold: F) -> Trainer<F, N, M, L> {
+        assert!(threshold > zero(), "The confidence threshold must be positive.");
+        self.unlabeled = Some((inputs, threshold));
+        self
+    }
+
+    /// Makes this trainer adjust the learning rate of the method at the
+    /// beginning of each epoch, according to `schedule`.
+    ///
+    /// The current rate of the method is used as initial rate. Should the
+    /// schedule give an invalid rate (like `0.0` once an exponential decay
+    /// underflows), the previous rate is kept.
+    pub fn with_schedule(mut self, schedule: Schedule<F>) -> Trainer<F, N, M, L>
+        where M: LearningRate<F>,
+              F: 'static
+    {
+        let initial = self.method.rate();
+        self.schedule = Some(Box::new(move |method: &mut M, epoch| {
+            let _ = schedule.apply(method, initial, epoch);
+        }));
+        self
+    }
+
+    /// The network being trained.
+    pub fn network(&self) -> &N {
+        &self.network
+    }
+
+    /// Mutable access to the network being trained.
+    pub fn network_mut(&mut self) -> &mut N {
+        &mut self.network
+    }
+
+    /// Consumes the trainer, returning the trained network.
+    pub fn into_network(self) -> N {
+        self.network
+    }
+
+    /// Mutable access to the training method, for example to adjust its
+    /// learning rate between runs.
+    pub fn method_mut(&mut self) -> &mut M {
+        &mut self.method
+    }
+
+    /// The number of epochs run so far.
+    pub fn epochs(&self) -> usize {
+        self.epochs
+    }
+
+    /// The moving average of the loss of each sample, tracked when hard
+    /// example mining is enabled, and empty before the first epoch.
+    pub fn hardness(&self) -> &[F] {
+        &self.hardness
+    }
+
+    /// The unlabeled samples labeled at the end of the last epoch, with their
+    /// pseudo-labels.
+    pub fn pseudo_labels(&self) -> &[(Vec<F>, Vec<F>)] {
+        &self.pseudo_labels
+    }
+
+    /// Runs at most `epochs` epochs of training, stopping earlier if
+    /// `callback` asks so.
+    ///
+    /// The epochs are numbered from `0` across the successive runs. Returns
+    /// the number of epochs run.
+    pub fn run<C: Callback<F, N>>(&mut self, epochs: usize, callback: &mut C) -> usize {
+        let mut rng = thread_rng();
+        for run in 0..epochs {
+            let epoch = self.epochs;
+            self.epochs += 1;
+            if let Some(ref schedule) = self.schedule {
+                schedule(&mut self.method, epoch);
+            }
+            let labeled = self.data.len() + self.pseudo_labels.len();
+            let mut presented = (0..labeled).chain(self.hardest()).collect::<Vec<_>>();
+            if self.shuffle {
+                rng.shuffle(&mut presented);
+            }
+            self.network.set_training(true);
+            let mixing = self.mixup.map(|alpha| Gamma::new(alpha.to_f64().unwrap(), 1.0));
+            for &i in &presented {
+                let (input, target) = if i < self.data.len() {
+                    &self.data[i]
+                } else {
+                    &self.pseudo_labels[i - self.data.len()]
+                };
+                match mixing {
+                    Some(ref gamma) => {
+                        let (ref other_input, ref other_target) = self.data[rng.gen_range(0, self.data.len())];
+                        let (a, b) = (gamma.ind_sample(&mut rng), gamma.ind_sample(&mut rng));
+                        let l = F::from(a / (a + b)).unwrap();
+                        self.network.supervised_train(&self.method,
+                                                      &mix(input, other_input, l),
+                                                      &mix(target, other_target, l));
+                    },
+                    None => self.network.supervised_train(&self.method, input, target)
+                }
+            }
+            self.network.set_training(false);
+            let loss = match self.mining {
+                Some((_, decay)) => self.update_hardness(decay),
+                None => mean_loss(&self.network, &self.loss, &self.data)
+            };
+            self.label_unlabeled();
+            if !callback.on_epoch(epoch, loss, &mut self.network) {
+                return run + 1;
+            }
+        }
+        epochs
+    }
+
+    // the indices of the hardest samples, to be presented once more
+    fn hardest(&self) -> Vec<usize> {
+        let fraction = match self.mining {
+            Some((fraction, _)) => fraction,
+            None => return Vec::new()
+        };
+        let mut indices = (0..self.hardness.len()).collect::<Vec<_>>();
+        indices.sort_by(|&a, &b| {
+            self.hardness[b].partial_cmp(&self.hardness[a]).unwrap_or(Ordering::Equal)
+        });
+        let count = (fraction * F::from(indices.len()).unwrap()).ceil();
+        indices.truncate(count.to_usize().unwrap_or(0));
+        indices
+    }
+
+    // labels the unlabeled samples for which the network is confident enough
+    fn label_unlabeled(&mut self) {
+        if let Some((ref inputs, threshold)) = self.unlabeled {
+            let network = &self.network;
+            self.pseudo_labels = inputs.iter().filter_map(|input| {
+                let output = network.compute(input);
+                let best = (0..output.len()).fold(None, |best: Option<usize>, j| match best {
+                    Some(b) if output[b] >= output[j] => Some(b),
+                    _ => Some(j)
+                });
+                best.filter(|&b| output[b] >= threshold).map(|b| {
+                    let target = (0..output.len()).map(|j| if j == b { one() } else { zero() }).collect();
+                    (input.clone(), target)
+                })
+            }).collect();
+        }
+    }
+
+    // updates the moving averages of the loss of the samples, returning the
+    // mean loss
+    fn update_hardness(&mut self, decay: F) -> F {
+        let losses = self.data.iter().map(|(input, target)| {
+            self.loss.value(&self.network.compute(input), target)
+        }).collect::<Vec<_>>();
+        if self.hardness.len() == losses.len() {
+            for (h, &l) in self.hardness.iter_mut().zip(&losses) {
+                *h = decay * *h + (one::<F>() - decay) * l;
+            }
+        } else {
+            self.hardness = losses.clone();
+        }
+        losses.iter().fold(zero::<F>(), |s, &l| s + l) / F::from(losses.len().max(1)).unwrap()
+    }
+}
+
+// the convex combination l * a + (1 - l) * b, missing values being 0
+fn mix<F: Float>(a: &[F], b: &[F], l: F) -> Vec<F> {
+    (0..a.len().max(b.len())).map(|i| {
+        let (x, y) = (a.get(i).cloned().unwrap_or(zero()), b.get(i).cloned().unwrap_or(zero()));
+        l * x + (one::<F>() - l) * y
+    }).collect()
+}
+
+/// A callback stopping the training when the loss on a held-out validation
+/// set stops improving.
+///
+/// The network is snapshotted each time its validation loss improves. When
+/// it has not improved for `patience` epochs, the best snapshot is restored
+/// into the trainer and the training is stopped.
+///
+/// The validation loss is the mean of `L` over the validation set,
+/// `SquaredError` by default. See `with_loss(..)` to monitor the loss the
+/// trainer reports, when it is not the default one.
+pub struct EarlyStopping<F: Float, N, L = SquaredError> {
+    validation: Vec<(Vec<F>, Vec<F>)>,
+    loss: L,
+    patience: usize,
+    best: Option<(F, N)>,
+    waiting: usize
+}
+
+impl<F: Float, N: Clone> EarlyStopping<F, N> {
+    /// Creates an early stopping callback evaluating the network on given
+    /// validation samples, and waiting `patience` epochs for an improvement.
+    pub fn new(validation: Vec<(Vec<F>, Vec<F>)>, patience: usize) -> EarlyStopping<F, N> {
+        EarlyStopping {
+            validation,
+            loss: SquaredError,
+            patience,
+            best: None,
+            waiting: 0
+        }
+    }
+}
+
+impl<F: Float, N: Clone, L: Loss<F>> EarlyStopping<F, N, L> {
+    /// Evaluates the network with given loss instead.
+    pub fn with_loss<L2: Loss<F>>(self, loss: L2) -> EarlyStopping<F, N, L2> {
+        EarlyStopping {
+            validation: self.validation,
+            loss,
+            patience: self.patience,
+            best: self.best,
+            waiting: self.waiting
+        }
+    }
+
+    /// The best validation loss so far, and the network achieving it.
+    pub fn best(&self) -> Option<(F, &N)> {
+        self.best.as_ref().map(|&(loss, ref network)| (loss, network))
+    }
+}
+
+impl<F, N, L> Callback<F, N> for EarlyStopping<F, N, L>
+    where F: Float,
+          N: Compute<F> + Clone,
+          L: Loss<F>
+{
+    fn on_epoch(&mut self, _epoch: usize, _loss: F, network: &mut N) -> bool {
+        let loss = mean_loss(network, &self.loss, &self.validation);
+        if self.best.as_ref().map(|&(best, _)| loss < best).unwrap_or(true) {
+            self.best = Some((loss, network.clone()));
+            self.waiting = 0;
+            return true;
+        }
+        self.waiting += 1;
+        if self.waiting < self.patience {
+            return true;
+        }
+        if let Some((_, ref best)) = self.best {
+            *network = best.clone();
+        }
+        false
+    }
+}
+
+/// A callback taking snapshots of the network during the training, for
+/// snapshot ensembles.
+///
+/// A snapshot is taken at the end of every `every` epochs. Along with a
+/// `Schedule::Cosine(every, ..)`, the snapshots are taken at the minima of the
+/// learning rate, just before each restart, when the network has settled in
+/// a new local minimum. The ensemble of the snapshots, averaging their
+/// outputs, usually generalizes better than the last network alone, at the
+/// cost of a single training run.
+pub struct Snapshots<N> {
+    every: usize,
+    snapshots: Vec<N>
+}
+
+impl<N: Clone> Snapshots<N> {
+    /// Creates a callback taking a snapshot of the network every `every`
+    /// epochs.
+    ///
+    /// Panics if `every` is `0`.
+    pub fn new(every: usize) -> Snapshots<N> {
+        assert!(every > 0, "The snapshots must be at least one epoch apart.");
+        Snapshots {
+            every,
+            snapshots: Vec::new()
+        }
+    }
+
+    /// The snapshots taken so far.
+    pub fn snapshots(&self) -> &[N] {
+        &self.snapshots
+    }
+
+    /// Consumes the callback, returning the ensemble of the snapshots.
+    ///
+    /// Panics if no snapshot was taken.
+    pub fn into_ensemble(self) -> Ensemble<N> {
+        Ensemble::new(self.snapshots)
+    }
+}
+
+impl<F: Float, N: Clone> Callback<F, N> for Snapshots<N> {
+    fn on_epoch(&mut self, epoch: usize, _loss: F, network: &mut N) -> bool {
+        if epoch % self.every == self.every - 1 {
+            self.snapshots.push(network.clone());
+        }
+        true
+    }
+}
+
+/*
+ * Single training steps
+ */
+
+/// One step of backpropagation training, split into its phases for custom
+/// training loops.
+///
+/// A step starts with `forward(..)`, which computes and keeps the output of
+/// the network. The gradient of the objective with regard to this output is
+/// then given either by a `Loss` with `loss(..)`, or directly with
+/// `backward(..)` (for example when it comes from another network, as for
+/// the generator of a GAN). Finally, `apply()` updates the network.
+pub struct TrainStep<'a, F: Float + 'a, N: 'a, M: 'a> {
+    network: &'a mut N,
+    rule: &'a M,
+    input: Vec<F>,
+    output: Vec<F>,
+    gradient: Vec<F>
+}
+
+impl<'a, F, N, M> TrainStep<'a, F, N, M>
+    where F: Float,
+          N: BackpropTrain<F, M> + Compute<F>,
+          M: Method
+{
+    /// Starts a training step of `network` with given method, computing its
+    /// output for `input`.
+    pub fn forward(network: &'a mut N, rule: &'a M, input: &[F]) -> TrainStep<'a, F, N, M> {
+        let output = network.compute(input);
+        let gradient = vec![zero(); output.len()];
+        TrainStep {
+            network,
+            rule,
+            input: input.to_owned(),
+            output,
+            gradient
+        }
+    }
+
+    /// The input of this step.
+    pub fn input(&self) -> &[F] {
+        &self.input
+    }
+
+    /// The output of the network for the input of this step.
+    pub fn output(&self) -> &[F] {
+        &self.output
+    }
+
+    /// Computes the value of `loss` for given target, and sets its gradient
+    /// as the gradient of the step.
+    pub fn loss<L: Loss<F>>(&mut self, loss: &L, target: &[F]) -> F {
+        self.gradient = loss.gradient(&self.output, target);
+        loss.value(&self.output, target)
+    }
+
+    /// Sets the gradient of the objective with regard to the output of the
+    /// network, to be minimized by the step.
+    pub fn backward(&mut self, gradient: &[F]) {
+        self.gradient = (0..self.output.len()).map(|j| {
+            gradient.get(j).cloned().unwrap_or(zero())
+        }).collect();
+    }
+
+    /// The gradient of the objective with regard to the output.
+    pub fn gradient(&self) -> &[F] {
+        &self.gradient
+    }
+
+    /// Updates the network, ending the step.
+    ///
+    /// Returns the gradient of the objective with regard to the input, which
+    /// can be given to the step of a network feeding this one.
+    pub fn apply(self) -> Vec<F> {
+        let target = self.output.iter().zip(&self.gradient).map(|(&y, &g)| y - g).collect::<Vec<_>>();
+        let returned = self.network.backprop_train(self.rule, &self.input, &target);
+        self.input.iter().zip(returned).map(|(&x, r)| x - r).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {Compute, FeedforwardLayer, Matrix, SupervisedTrain};
+    use activations::{Activation, identity, softmax};
+    use loss::{CrossEntropy, Huber, SquaredError};
+    use training::{GradientDescent, LearningRate, Schedule};
+    use util::Chain;
+
+    use super::{Callback, EarlyStopping, Snapshots, Trainer, TrainStep, mean_loss, mean_squared_error, mix};
+
+    struct StopBelow(f64);
+
+    impl<N> Callback<f64, N> for StopBelow {
+        fn on_epoch(&mut self, _epoch: usize, loss: f64, _network: &mut N) -> bool {
+            loss >= self.0
+        }
+    }
+
+    #[test]
+    fn epochs() {
+        let data = vec![(vec![1.0, 0.0], vec![1.0]), (vec![0.0, 1.0], vec![-1.0]),
+                        (vec![1.0, 1.0], vec![0.0])];
+        let layer = FeedforwardLayer::new(2, 1, identity());
+        let mut trainer = Trainer::new(layer, GradientDescent::new(0.1).unwrap(), data);
+        let mut losses = Vec::new();
+        assert_eq!(trainer.run(20, &mut |_, loss| losses.push(loss)), 20);
+        assert!(losses[19] < losses[0]);
+        let epochs = trainer.run(1000, &mut StopBelow(1e-6));
+        assert!(epochs < 1000);
+        let layer = trainer.into_network();
+        assert!((layer.weights()[(0, 0)] - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn loss() {
+        let data = vec![(vec![1.0f64], vec![1.0]), (vec![-1.0], vec![0.0])];
+        let layer = FeedforwardLayer::new(1, 1, Activation::Identity);
+        let mut trainer = Trainer::new(layer.clone(), GradientDescent::new(0.1).unwrap(), data.clone());
+        let mut reported = 0.0;
+        trainer.run(1, &mut |_, loss| reported = loss);
+        assert!((reported - mean_squared_error(trainer.network(), &data) / 2.0).abs() < 1e-12);
+
+        let mut trainer = Trainer::new(layer, GradientDescent::new(0.1).unwrap(), data.clone())
+            .with_loss(Huber(0.5));
+        let mut losses = Vec::new();
+        trainer.run(20, &mut |_, loss| losses.push(loss));
+        assert_eq!(losses[19], mean_loss(trainer.network(), &Huber(0.5), &data));
+        assert!(losses[19] < losses[0]);
+    }
+
+    #[test]
+    fn early_stopping() {
+        // the validation set contradicts the training set
+        let data = vec![(vec![1.0f64], vec![1.0])];
+        let validation = vec![(vec![1.0], vec![0.2])];
+        let layer = FeedforwardLayer::new(1, 1, Activation::Identity).without_biases();
+        let mut trainer = Trainer::new(layer, GradientDescent::new(0.05).unwrap(), data);
+        let mut stopping = EarlyStopping::new(validation.clone(), 3);
+        let epochs = trainer.run(100, &mut stopping);
+        assert!(epochs < 100);
+        let (best, _) = stopping.best().unwrap();
+        assert_eq!(mean_loss(trainer.network(), &SquaredError, &validation), best);
+        assert!((trainer.network().weights()[(0, 0)] - 0.2).abs() < 0.1);
+
+        // the monitored loss can be changed
+        let layer = FeedforwardLayer::new(1, 1, Activation::Identity).without_biases();
+        let mut trainer = Trainer::new(layer, GradientDescent::new(0.05).unwrap(), vec![(vec![1.0f64], vec![1.0])])
+            .with_loss(Huber(0.1));
+        let mut stopping = EarlyStopping::new(validation.clone(), 3).with_loss(Huber(0.1));
+        assert!(trainer.run(100, &mut stopping) < 100);
+        let (best, _) = stopping.best().unwrap();
+        assert_eq!(mean_loss(trainer.network(), &Huber(0.1), &validation), best);
+    }
+
+    #[test]
+    fn mixup() {
+        assert_eq!(mix(&[1.0, 0.0], &[0.0, 1.0, 2.0], 0.25), [0.25, 0.75, 1.5]);
+        // the combinations of samples of a linear function stay on it
+        let data = vec![(vec![1.0f64], vec![2.0]), (vec![-1.0], vec![-2.0]), (vec![0.5], vec![1.0])];
+        let layer = FeedforwardLayer::new(1, 1, Activation::Identity);
+        let mut trainer = Trainer::new(layer, GradientDescent::new(0.1).unwrap(), data).with_mixup(0.4);
+        trainer.run(300, &mut |_, _| {});
+        let layer = trainer.into_network();
+        assert!((layer.weights()[(0, 0)] - 2.0).abs() < 1e-3 && layer.biases()[0].abs() < 1e-3);
+    }
+
+    #[test]
+    fn hard_mining() {
+        // the rare sample is presented once more in each epoch, pulling the
+        // output towards it
+        let data = vec![(vec![1.0f64], vec![0.0]), (vec![1.0], vec![0.0]),
+                        (vec![1.0], vec![0.0]), (vec![1.0], vec![1.0])];
+        let layer = FeedforwardLayer::new(1, 1, Activation::Identity).without_biases();
+        let mut trainer = Trainer::new(layer.clone(), GradientDescent::new(0.02).unwrap(), data.clone())
+            .with_hard_mining(0.25, 0.5);
+        let mut reported = 0.0;
+        trainer.run(300, &mut |_, loss| reported = loss);
+        assert_eq!(reported, mean_loss(trainer.network(), &SquaredError, &data));
+        let hardness = trainer.hardness();
+        assert_eq!(hardness.len(), 4);
+        assert!(hardness[3] > hardness[0] && hardness[3] > hardness[2]);
+        let weight = trainer.network().weights()[(0, 0)];
+        assert!((weight - 0.4).abs() < 0.05);
+
+        let mut trainer = Trainer::new(layer, GradientDescent::new(0.02).unwrap(), data);
+        trainer.run(300, &mut |_, _| {});
+        assert!(trainer.hardness().is_empty());
+        assert!((trainer.network().weights()[(0, 0)] - 0.25).abs() < 0.05);
+    }
+
+    #[test]
+    fn pseudo_labels() {
+        let data = vec![(vec![1.0f64], vec![1.0, 0.0]), (vec![-1.0], vec![0.0, 1.0])];
+        let unlabeled = vec![vec![2.0], vec![0.0], vec![-3.0]];
+        let layer = FeedforwardLayer::new(1, 2, softmax()).without_biases();
+        let mut trainer = Trainer::new(layer, GradientDescent::new(0.5).unwrap(), data.clone())
+            .with_loss(CrossEntropy)
+            .with_unlabeled(unlabeled, 0.9);
+        let mut reported = 0.0;
+        trainer.run(50, &mut |_, loss| reported = loss);
+        assert_eq!(reported, mean_loss(trainer.network(), &CrossEntropy, &data));
+        // the ambiguous sample is left unlabeled
+        let labels = trainer.pseudo_labels();
+        assert_eq!(labels.len(), 2);
+        assert_eq!(labels[0], (vec![2.0], vec![1.0, 0.0]));
+        assert_eq!(labels[1], (vec![-3.0], vec![0.0, 1.0]));
+    }
+
+    #[test]
+    fn schedule() {
+        let data = vec![(vec![1.0f64], vec![1.0])];
+        let layer = FeedforwardLayer::new(1, 1, identity());
+        let mut trainer = Trainer::new(layer, GradientDescent::new(0.4).unwrap(), data)
+            .with_schedule(Schedule::StepDecay(2, 0.5));
+        trainer.run(3, &mut |_, _| {});
+        assert_eq!(trainer.method_mut().rate(), 0.2);
+        trainer.run(2, &mut |_, _| {});
+        assert_eq!(trainer.epochs(), 5);
+        assert_eq!(trainer.method_mut().rate(), 0.1);
+    }
+
+    #[test]
+    fn snapshots() {
+        let data = vec![(vec![1.0f64], vec![1.0]), (vec![-1.0], vec![0.0])];
+        let layer = FeedforwardLayer::new(1, 1, Activation::Identity);
+        let mut trainer = Trainer::new(layer, GradientDescent::new(0.2).unwrap(), data)
+            .with_schedule(Schedule::Cosine(5, 0.01));
+        let mut snapshots = Snapshots::new(5);
+        trainer.run(12, &mut snapshots);
+        assert_eq!(snapshots.snapshots().len(), 2);
+        let (first, second) = (snapshots.snapshots()[0].clone(), snapshots.snapshots()[1].clone());
+        let ensemble = snapshots.into_ensemble();
+        let mean = (first.compute(&[0.5])[0] + second.compute(&[0.5])[0]) / 2.0;
+        assert!((ensemble.compute(&[0.5])[0] - mean).abs() < 1e-12);
+    }
+
+    #[test]
+    fn train_step() {
+        let first = FeedforwardLayer::from_matrices(Matrix::from_vec(2, 2, vec![0.5f64, -1.0, 0.25, 1.0]),
+                                                    vec![0.0, 0.1], Activation::Sigmoid);
+        let second = FeedforwardLayer::from_matrices(Matrix::from_vec(1, 2, vec![1.0, -0.5]),
+                                                     vec![0.2], Activation::Identity);
+        let mut chain = Chain::new(first.clone(), second.clone());
+        let (mut first, mut second) = (first, second);
+        let rule = GradientDescent::new(0.1).unwrap();
+        let (input, target) = ([0.3, -0.7], [1.0]);
+
+        // a manual backpropagation through the two layers
+        let hidden = first.compute(&input);
+        let mut step = TrainStep::forward(&mut second, &rule, &hidden);
+        let loss = step.loss(&SquaredError, &target);
+        assert_eq!(loss, 0.5 * (step.output()[0] - 1.0).powi(2));
+        let hidden_gradient = step.apply();
+        let mut step = TrainStep::forward(&mut first, &rule, &input);
+        step.backward(&hidden_gradient);
+        step.apply();
+
+        chain.supervised_train(&rule, &input, &target);
+        let (a, b) = (chain.compute(&input), second.compute(&first.compute(&input)));
+        assert!((a[0] - b[0]).abs() < 1e-12);
+    }
+}
@@ -3,10 +3,163 @@
 //! These types describe the parameters of each learning that can be
 //! tune by the user.
 
-use num::Float;
+use std::cmp::min;
+use std::error::Error;
+use std::fmt;
+
+use num::{Float, one, zero};
 
 use Method;
 
+/// A trait for training methods descending the gradient of the error, which
+/// can update any set of parameters given their gradient.
+pub trait GradientMethod<F: Float>: Method {
+    /// Updates `params` given the gradient of the error with regard to
+    /// them, and the state kept for them since the previous updates.
+    fn update(&self, params: &mut [F], gradient: &[F], state: &mut OptimizerState<F>);
+
+    /// The L1 and L2 regularization coefficients of this method, none by
+    /// default, see `Regularized`.
+    fn weight_decay(&self) -> (F, F) {
+        (zero(), zero())
+    }
+
+    /// Adds L1 and L2 regularization of the weights to this method, see
+    /// `Regularized`.
+    ///
+    /// Fails if the coefficients are not finite and non-negative.
+    fn regularized(self, l1: F, l2: F) -> Result<Regularized<F, Self>, HyperparameterError>
+        where Self: Sized
+    {
+        Regularized::new(self, l1, l2)
+    }
+
+    /// Adds the gradient of the L1 and L2 regularization terms
+    /// `l1 * |w| + l2 / 2 * w^2` to the gradient of given weights.
+    ///
+    /// Layers call it on their weights only, not on their biases.
+    fn regularize(&self, weights: &[F], gradient: &mut [F]) {
+        let (l1, l2) = self.weight_decay();
+        if l1 == zero() && l2 == zero() { return; }
+        for (&w, g) in weights.iter().zip(gradient.iter_mut()) {
+            let sign = if w > zero() { one() } else if w < zero() { -one::<F>() } else { zero() };
+            *g = *g + l1 * sign + l2 * w;
+        }
+    }
+}
+
+/// The per-parameter state an optimization method keeps between training
+/// steps, like the velocities of a gradient descent with momentum.
+///
+/// Trainable layers hold one such state for each set of parameters they
+/// pass to `GradientMethod::update(..)`. It is empty until a method needing
+/// it is used, and should be reset when switching methods.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OptimizerState<F: Float> {
+    steps: usize,
+    buffers: Vec<Vec<F>>
+}
+
+impl<F: Float> OptimizerState<F> {
+    /// Creates a new empty state.
+    pub fn new() -> OptimizerState<F> {
+        OptimizerState {
+            steps: 0,
+            buffers: Vec::new()
+        }
+    }
+
+    /// The number of updates done so far.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Counts a new update, and returns the number of updates done so far
+    /// including this one.
+    pub fn next_step(&mut self) -> usize {
+        self.steps += 1;
+        self.steps
+    }
+
+    /// Returns `count` buffers of `len` values each, which are set to `0.0`
+    /// the first time they are requested.
+    pub fn buffers(&mut self, count: usize, len: usize) -> &mut [Vec<F>] {
+        if self.buffers.len() < count {
+            self.buffers.resize(count, Vec::new());
+        }
+        for buffer in &mut self.buffers[..count] {
+            buffer.resize(len, zero());
+        }
+        &mut self.buffers[..count]
+    }
+
+    /// Clears the state, as if no update was done.
+    pub fn reset(&mut self) {
+        self.steps = 0;
+        self.buffers.clear();
+    }
+}
+
+impl<F: Float> Default for OptimizerState<F> {
+    fn default() -> OptimizerState<F> {
+        OptimizerState::new()
+    }
+}
+
+/*
+ * Validation of the hyperparameters
+ */
+
+/// An invalid hyperparameter given to a training method.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HyperparameterError {
+    /// The learning rate is not finite and positive.
+    Rate,
+    /// The named decay factor (like a momentum) is not in the range `[0, 1)`.
+    Decay(&'static str),
+    /// The value avoiding divisions by zero is not finite and positive.
+    Epsilon,
+    /// The regularization coefficients are not finite and non-negative.
+    Regularization,
+    /// The truncation length of a backpropagation through time is `0`.
+    Truncation
+}
+
+impl fmt::Display for HyperparameterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            HyperparameterError::Rate => write!(f, "the learning rate must be finite and positive"),
+            HyperparameterError::Decay(name) => write!(f, "{} must be in the range [0, 1)", name),
+            HyperparameterError::Epsilon => write!(f, "epsilon must be finite and positive"),
+            HyperparameterError::Regularization =>
+                write!(f, "the regularization coefficients must be finite and non-negative"),
+            HyperparameterError::Truncation => write!(f, "the truncation length must be positive")
+        }
+    }
+}
+
+impl Error for HyperparameterError {}
+
+fn check_rate<F: Float>(rate: F) -> Result<(), HyperparameterError> {
+    if rate.is_finite() && rate > zero() { Ok(()) } else { Err(HyperparameterError::Rate) }
+}
+
+fn check_decay<F: Float>(value: F, name: &'static str) -> Result<(), HyperparameterError> {
+    if value >= zero() && value < one() { Ok(()) } else { Err(HyperparameterError::Decay(name)) }
+}
+
+fn check_epsilon<F: Float>(epsilon: F) -> Result<(), HyperparameterError> {
+    if epsilon.is_finite() && epsilon > zero() { Ok(()) } else { Err(HyperparameterError::Epsilon) }
+}
+
+fn check_regularization<F: Float>(l1: F, l2: F) -> Result<(), HyperparameterError> {
+    if l1.is_finite() && l1 >= zero() && l2.is_finite() && l2 >= zero() {
+        Ok(())
+    } else {
+        Err(HyperparameterError::Regularization)
+    }
+}
+
 /// The gradient descend approach, consisting on finding a minimum of the
 /// error by going down its gradient.
 pub struct GradientDescent<F: Float> {
@@ -14,11 +167,100 @@ pub struct GradientDescent<F: Float> {
     ///
     /// A very small value will make the training slow, but a too big one
     /// will make it unstable.
-    pub rate: F
+    rate: F
+}
+
+impl<F: Float> GradientDescent<F> {
+    /// Creates a gradient descent with given learning rate.
+    ///
+    /// Fails if the rate is not finite and positive.
+    pub fn new(rate: F) -> Result<GradientDescent<F>, HyperparameterError> {
+        check_rate(rate)?;
+        Ok(GradientDescent { rate })
+    }
 }
 
 impl<F: Float> Method for GradientDescent<F> {}
 
+impl<F: Float> GradientMethod<F> for GradientDescent<F> {
+    fn update(&self, params: &mut [F], gradient: &[F], state: &mut OptimizerState<F>) {
+        state.next_step();
+        for (p, &g) in params.iter_mut().zip(gradient) {
+            *p = *p - self.rate * g;
+        }
+    }
+}
+
+/// A gradient descent with momentum: each parameter keeps a velocity
+/// accumulating its past gradients, which smoothes and accelerates the
+/// descent.
+///
+/// ```text
+/// v = momentum * v + gradient
+/// p = p - rate * v
+/// ```
+///
+/// With Nesterov accelerated gradient, the step `rate * (gradient +
+/// momentum * v)` is used instead, which anticipates the next position of
+/// the parameter.
+pub struct Momentum<F: Float> {
+    rate: F,
+    momentum: F,
+    nesterov: bool
+}
+
+impl<F: Float> Momentum<F> {
+    /// Creates a gradient descent with given learning rate and momentum
+    /// factor (`0.9` is a classic value).
+    ///
+    /// Fails if the rate is not finite and positive, or if the momentum is
+    /// not in the range `[0, 1)`.
+    pub fn new(rate: F, momentum: F) -> Result<Momentum<F>, HyperparameterError> {
+        check_rate(rate)?;
+        check_decay(momentum, "the momentum")?;
+        Ok(Momentum {
+            rate,
+            momentum,
+            nesterov: false
+        })
+    }
+
+    /// Creates a gradient descent with given learning rate and momentum
+    /// factor, using Nesterov accelerated gradient.
+    ///
+    /// Fails like `new(..)`.
+    pub fn nesterov(rate: F, momentum: F) -> Result<Momentum<F>, HyperparameterError> {
+        Ok(Momentum {
+            nesterov: true,
+            ..Momentum::new(rate, momentum)?
+        })
+    }
+
+    /// The momentum factor.
+    pub fn momentum(&self) -> F {
+        self.momentum
+    }
+
+    /// Whether Nesterov accelerated gradient is used.
+    pub fn is_nesterov(&self) -> bool {
+        self.nesterov
+    }
+}
+
+impl<F: Float> Method for Momentum<F> {}
+
+impl<F: Float> GradientMethod<F> for Momentum<F> {
+    fn update(&self, params: &mut [F], gradient: &[F], state: &mut OptimizerState<F>) {
+        state.next_step();
+        let velocities = &mut state.buffers(1, params.len())[0];
+        for ((p, v), &g) in params.iter_mut().zip(velocities.iter_mut()).zip(gradient) {
+            *v = self.momentum * *v + g;
+            let step = if self.nesterov { g + self.momentum * *v } else { *v };
+            *p = *p - self.rate * step;
+        }
+    }
+}
+
 /// The perceptron rule, a classic learning rule for one-layered
 /// feedforward networks.
 pub struct PerceptronRule<F: Float> {
@@ -26,7 +268,738 @@ pub struct PerceptronRule<F: Float> {
     ///
     /// A very small value will make the training slow, but a too big one
     /// will make it unstable.
-    pub rate: F
+    rate: F
+}
+
+impl<F: Float> PerceptronRule<F> {
+    /// Creates a perceptron rule with given learning rate.
+    ///
+    /// Fails if the rate is not finite and positive.
+    pub fn new(rate: F) -> Result<PerceptronRule<F>, HyperparameterError> {
+        check_rate(rate)?;
+        Ok(PerceptronRule { rate })
+    }
+}
+
+impl<F: Float> Method for PerceptronRule<F> {}
+
+/// The delta rule (or Widrow-Hoff rule, or least mean squares rule), a
+/// classic learning rule for one-layered feedforward networks.
+///
+/// Unlike the perceptron rule, the error is computed on the linear output
+/// of the layer, before its activation function. It thus performs a
+/// gradient descent on the squared error of this linear output, like an
+/// ADALINE does.
+pub struct DeltaRule<F: Float> {
+    /// The learning rate associated with this delta rule.
+    ///
+    /// A very small value will make the training slow, but a too big one
+    /// will make it unstable.
+    rate: F
+}
+
+impl<F: Float> DeltaRule<F> {
+    /// Creates a delta rule with given learning rate.
+    ///
+    /// Fails if the rate is not finite and positive.
+    pub fn new(rate: F) -> Result<DeltaRule<F>, HyperparameterError> {
+        check_rate(rate)?;
+        Ok(DeltaRule { rate })
+    }
+}
+
+impl<F: Float> Method for DeltaRule<F> {}
+
+/// A reward-modulated Hebbian rule (or three-factor rule), for unsupervised
+/// training driven by a reward signal, like in reinforcement learning.
+///
+/// Each weight keeps an eligibility trace of the correlations between its
+/// input `x_i` and output `y_j`, and is changed proportionally to this trace
+/// and to the current reward:
+///
+/// ```text
+/// e_ji = eligibility_decay * e_ji + y_j * x_i
+/// w_ji = w_ji + rate * reward * e_ji
+/// ```
+///
+/// The traces are kept by the trained layer between training steps in an
+/// `EligibilityTraces`, apart from the state of the gradient methods, and
+/// cleared by its `reset_traces()` (typically at the start of each
+/// episode). The reward received for each step is given to the rule before
+/// training with `set_reward(..)`.
+pub struct RewardHebbian<F: Float> {
+    rate: F,
+    eligibility_decay: F,
+    reward: F
+}
+
+impl<F: Float> RewardHebbian<F> {
+    /// Creates a reward-modulated Hebbian rule, with a null reward.
+    ///
+    /// Fails if the rate is not finite and positive, or if the decay is
+    /// not in the range `[0, 1)`.
+    pub fn new(rate: F, eligibility_decay: F) -> Result<RewardHebbian<F>, HyperparameterError> {
+        check_rate(rate)?;
+        check_decay(eligibility_decay, "the eligibility decay")?;
+        Ok(RewardHebbian {
+            rate,
+            eligibility_decay,
+            reward: zero()
+        })
+    }
+
+    /// The decay factor of the eligibility traces, in the range `[0, 1)`
+    /// (`0.0` making only the last step eligible).
+    pub fn eligibility_decay(&self) -> F {
+        self.eligibility_decay
+    }
+
+    /// The reward received for the current step.
+    pub fn reward(&self) -> F {
+        self.reward
+    }
+
+    /// Sets the reward received for the current step.
+    pub fn with_reward(mut self, reward: F) -> RewardHebbian<F> {
+        self.set_reward(reward);
+        self
+    }
+
+    /// Changes the reward received for the current step.
+    pub fn set_reward(&mut self, reward: F) {
+        self.reward = reward;
+    }
+
+    /// Updates the eligibility traces of the parameters with given
+    /// correlations, and then the parameters.
+    pub fn update(&self, params: &mut [F], correlations: &[F], traces: &mut EligibilityTraces<F>) {
+        traces.traces.resize(params.len(), zero());
+        for ((p, e), &c) in params.iter_mut().zip(traces.traces.iter_mut()).zip(correlations) {
+            *e = self.eligibility_decay * *e + c;
+            *p = *p + self.rate * self.reward * *e;
+        }
+    }
+}
+
+impl<F: Float> Method for RewardHebbian<F> {}
+
+/// The eligibility traces a `RewardHebbian` rule keeps for a set of
+/// parameters between training steps.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EligibilityTraces<F: Float> {
+    traces: Vec<F>
+}
+
+impl<F: Float> EligibilityTraces<F> {
+    /// Creates new empty traces.
+    pub fn new() -> EligibilityTraces<F> {
+        EligibilityTraces {
+            traces: Vec::new()
+        }
+    }
+
+    /// The traces of the parameters, empty until the first update.
+    pub fn traces(&self) -> &[F] {
+        &self.traces
+    }
+
+    /// Clears the traces, as if no update was done.
+    pub fn reset(&mut self) {
+        self.traces.clear();
+    }
 }
 
-impl<F: Float> Method for PerceptronRule<F> {}
\ No newline at end of file
+impl<F: Float> Default for EligibilityTraces<F> {
+    fn default() -> EligibilityTraces<F> {
+        EligibilityTraces::new()
+    }
+}
+
+/// The Adam optimizer, a gradient descent adapting the step of each
+/// parameter using running averages of its gradient and squared gradient.
+///
+/// ```text
+/// m = beta1 * m + (1 - beta1) * gradient
+/// v = beta2 * v + (1 - beta2) * gradient^2
+/// p = p - rate * m' / (sqrt(v') + epsilon)
+/// ```
+///
+/// where `m'` and `v'` are `m` and `v` corrected for their initialization
+/// to `0.0`.
+pub struct Adam<F: Float> {
+    rate: F,
+    beta1: F,
+    beta2: F,
+    epsilon: F
+}
+
+impl<F: Float> Adam<F> {
+    /// Creates an Adam optimizer with given learning rate (`0.001` is a
+    /// classic value), and classic values for the other parameters.
+    ///
+    /// Fails if the rate is not finite and positive.
+    pub fn new(rate: F) -> Result<Adam<F>, HyperparameterError> {
+        check_rate(rate)?;
+        Ok(Adam {
+            rate,
+            beta1: F::from(0.9).unwrap(),
+            beta2: F::from(0.999).unwrap(),
+            epsilon: F::from(1e-8).unwrap()
+        })
+    }
+
+    /// The decay rate of the average of the gradients, `0.9` by default.
+    pub fn beta1(&self) -> F {
+        self.beta1
+    }
+
+    /// The decay rate of the average of the squared gradients, `0.999` by
+    /// default.
+    pub fn beta2(&self) -> F {
+        self.beta2
+    }
+
+    /// The small value avoiding divisions by zero, `1e-8` by default.
+    pub fn epsilon(&self) -> F {
+        self.epsilon
+    }
+}
+
+impl<F: Float> Adam<F> {
+    /// Sets the decay rates of the averages of the gradients and of the
+    /// squared gradients.
+    ///
+    /// Fails if they are not in the range `[0, 1)`.
+    pub fn with_betas(mut self, beta1: F, beta2: F) -> Result<Adam<F>, HyperparameterError> {
+        check_decay(beta1, "beta1")?;
+        check_decay(beta2, "beta2")?;
+        self.beta1 = beta1;
+        self.beta2 = beta2;
+        Ok(self)
+    }
+
+    /// Sets the value avoiding divisions by zero.
+    ///
+    /// Fails if it is not finite and positive.
+    pub fn with_epsilon(mut self, epsilon: F) -> Result<Adam<F>, HyperparameterError> {
+        check_epsilon(epsilon)?;
+        self.epsilon = epsilon;
+        Ok(self)
+    }
+}
+
+impl<F: Float> Method for Adam<F> {}
+
+impl<F: Float> GradientMethod<F> for Adam<F> {
+    fn update(&self, params: &mut [F], gradient: &[F], state: &mut OptimizerState<F>) {
+        let t = state.next_step() as i32;
+        let correction1 = one::<F>() - self.beta1.powi(t);
+        let correction2 = one::<F>() - self.beta2.powi(t);
+        let (first, second) = state.buffers(2, params.len()).split_at_mut(1);
+        let (m, v) = (&mut first[0], &mut second[0]);
+        for i in 0..min(params.len(), gradient.len()) {
+            let g = gradient[i];
+            m[i] = self.beta1 * m[i] + (one::<F>() - self.beta1) * g;
+            v[i] = self.beta2 * v[i] + (one::<F>() - self.beta2) * g * g;
+            let (m_hat, v_hat) = (m[i] / correction1, v[i] / correction2);
+            params[i] = params[i] - self.rate * m_hat / (v_hat.sqrt() + self.epsilon);
+        }
+    }
+}
+
+/// The Adagrad optimizer, a gradient descent dividing the step of each
+/// parameter by the norm of all its past gradients.
+///
+/// ```text
+/// G = G + gradient^2
+/// p = p - rate * gradient / (sqrt(G) + epsilon)
+/// ```
+///
+/// Rarely updated parameters thus keep larger steps, which suits sparse
+/// inputs, but all the steps shrink over time.
+pub struct Adagrad<F: Float> {
+    rate: F,
+    epsilon: F
+}
+
+impl<F: Float> Adagrad<F> {
+    /// Creates an Adagrad optimizer with given learning rate (`0.01` is a
+    /// classic value).
+    ///
+    /// Fails if the rate is not finite and positive.
+    pub fn new(rate: F) -> Result<Adagrad<F>, HyperparameterError> {
+        check_rate(rate)?;
+        Ok(Adagrad {
+            rate,
+            epsilon: F::from(1e-8).unwrap()
+        })
+    }
+
+    /// The small value avoiding divisions by zero, `1e-8` by default.
+    pub fn epsilon(&self) -> F {
+        self.epsilon
+    }
+}
+
+impl<F: Float> Adagrad<F> {
+    /// Sets the value avoiding divisions by zero.
+    ///
+    /// Fails if it is not finite and positive.
+    pub fn with_epsilon(mut self, epsilon: F) -> Result<Adagrad<F>, HyperparameterError> {
+        check_epsilon(epsilon)?;
+        self.epsilon = epsilon;
+        Ok(self)
+    }
+}
+
+impl<F: Float> Method for Adagrad<F> {}
+
+impl<F: Float> GradientMethod<F> for Adagrad<F> {
+    fn update(&self, params: &mut [F], gradient: &[F], state: &mut OptimizerState<F>) {
+        state.next_step();
+        let squares = &mut state.buffers(1, params.len())[0];
+        for i in 0..min(params.len(), gradient.len()) {
+            let g = gradient[i];
+            squares[i] = squares[i] + g * g;
+            params[i] = params[i] - self.rate * g / (squares[i].sqrt() + self.epsilon);
+        }
+    }
+}
+
+/// The Adadelta optimizer, a variant of Adagrad using running averages
+/// instead of sums, and needing no learning rate.
+///
+/// ```text
+/// G = rho * G + (1 - rho) * gradient^2
+/// d = - sqrt(D + epsilon) / sqrt(G + epsilon) * gradient
+/// D = rho * D + (1 - rho) * d^2
+/// p = p + d
+/// ```
+pub struct Adadelta<F: Float> {
+    rho: F,
+    epsilon: F
+}
+
+impl<F: Float> Adadelta<F> {
+    /// Creates an Adadelta optimizer with classic parameters.
+    pub fn new() -> Adadelta<F> {
+        Adadelta {
+            rho: F::from(0.95).unwrap(),
+            epsilon: F::from(1e-6).unwrap()
+        }
+    }
+
+    /// The decay rate of the running averages, `0.95` by default.
+    pub fn rho(&self) -> F {
+        self.rho
+    }
+
+    /// The small value avoiding divisions by zero, which also sets the size
+    /// of the first steps, `1e-6` by default.
+    pub fn epsilon(&self) -> F {
+        self.epsilon
+    }
+}
+
+impl<F: Float> Default for Adadelta<F> {
+    fn default() -> Adadelta<F> {
+        Adadelta::new()
+    }
+}
+
+impl<F: Float> Adadelta<F> {
+    /// Sets the decay rate of the running averages.
+    ///
+    /// Fails if it is not in the range `[0, 1)`.
+    pub fn with_rho(mut self, rho: F) -> Result<Adadelta<F>, HyperparameterError> {
+        check_decay(rho, "rho")?;
+        self.rho = rho;
+        Ok(self)
+    }
+
+    /// Sets the value avoiding divisions by zero, which also sets the size
+    /// of the first steps.
+    ///
+    /// Fails if it is not finite and positive.
+    pub fn with_epsilon(mut self, epsilon: F) -> Result<Adadelta<F>, HyperparameterError> {
+        check_epsilon(epsilon)?;
+        self.epsilon = epsilon;
+        Ok(self)
+    }
+}
+
+impl<F: Float> Method for Adadelta<F> {}
+
+impl<F: Float> GradientMethod<F> for Adadelta<F> {
+    fn update(&self, params: &mut [F], gradient: &[F], state: &mut OptimizerState<F>) {
+        state.next_step();
+        let (first, second) = state.buffers(2, params.len()).split_at_mut(1);
+        let (squares, deltas) = (&mut first[0], &mut second[0]);
+        for i in 0..min(params.len(), gradient.len()) {
+            let g = gradient[i];
+            squares[i] = self.rho * squares[i] + (one::<F>() - self.rho) * g * g;
+            let d = - (deltas[i] + self.epsilon).sqrt() / (squares[i] + self.epsilon).sqrt() * g;
+            deltas[i] = self.rho * deltas[i] + (one::<F>() - self.rho) * d * d;
+            params[i] = params[i] + d;
+        }
+    }
+}
+
+/*
+ * Regularization
+ */
+
+/// A gradient method with L1 and L2 regularization of the weights.
+///
+/// The updates are those of the wrapped method, with the gradient of the
+/// regularization terms `l1 * |w| + l2 / 2 * w^2` added to the gradient of
+/// the weights (but not of the biases). It is usually created with
+/// `GradientMethod::regularized(..)`.
+pub struct Regularized<F: Float, M> {
+    method: M,
+    l1: F,
+    l2: F
+}
+
+impl<F: Float, M: GradientMethod<F>> Regularized<F, M> {
+    /// Wraps given method, with given L1 and L2 coefficients.
+    ///
+    /// Fails if the coefficients are not finite and non-negative.
+    pub fn new(method: M, l1: F, l2: F) -> Result<Regularized<F, M>, HyperparameterError> {
+        check_regularization(l1, l2)?;
+        Ok(Regularized {
+            method,
+            l1,
+            l2
+        })
+    }
+
+    /// The wrapped method.
+    pub fn method(&self) -> &M {
+        &self.method
+    }
+}
+
+impl<F: Float, M: Method> Method for Regularized<F, M> {}
+
+impl<F: Float, M: GradientMethod<F>> GradientMethod<F> for Regularized<F, M> {
+    fn weight_decay(&self) -> (F, F) {
+        let (l1, l2) = self.method.weight_decay();
+        (self.l1 + l1, self.l2 + l2)
+    }
+
+    fn update(&self, params: &mut [F], gradient: &[F], state: &mut OptimizerState<F>) {
+        self.method.update(params, gradient, state);
+    }
+}
+
+/*
+ * Backpropagation through time
+ */
+
+/// Backpropagation through time, the training of recurrent layers on
+/// sequences of inputs and targets.
+///
+/// The layer is unrolled over `truncation` steps of the sequence at a time:
+/// the gradients of the squared error of these steps are accumulated back
+/// through them, and then applied using `method`. The state is carried over
+/// to the next steps, but the gradients are not.
+pub struct Bptt<M> {
+    method: M,
+    truncation: usize
+}
+
+impl<M> Bptt<M> {
+    /// Creates a backpropagation through time using given gradient method
+    /// and truncation length.
+    ///
+    /// Fails if the truncation length is `0`.
+    pub fn new(method: M, truncation: usize) -> Result<Bptt<M>, HyperparameterError> {
+        if truncation == 0 {
+            return Err(HyperparameterError::Truncation);
+        }
+        Ok(Bptt {
+            method,
+            truncation
+        })
+    }
+
+    /// The gradient method applying the accumulated gradients.
+    pub fn method(&self) -> &M {
+        &self.method
+    }
+
+    /// The number of steps unrolled before each update.
+    pub fn truncation(&self) -> usize {
+        self.truncation
+    }
+}
+
+impl<M: Method> Method for Bptt<M> {}
+
+/*
+ * Learning rates
+ */
+
+/// A trait for training methods having a learning rate.
+pub trait LearningRate<F: Float>: Method {
+    /// The current learning rate.
+    fn rate(&self) -> F;
+    /// Changes the learning rate.
+    ///
+    /// Fails, leaving the rate unchanged, if the new rate is not finite and
+    /// positive.
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError>;
+}
+
+impl<F: Float> LearningRate<F> for GradientDescent<F> {
+    fn rate(&self) -> F { self.rate }
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError> {
+        check_rate(rate)?;
+        self.rate = rate;
+        Ok(())
+    }
+}
+
+impl<F: Float> LearningRate<F> for Momentum<F> {
+    fn rate(&self) -> F { self.rate }
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError> {
+        check_rate(rate)?;
+        self.rate = rate;
+        Ok(())
+    }
+}
+
+impl<F: Float> LearningRate<F> for PerceptronRule<F> {
+    fn rate(&self) -> F { self.rate }
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError> {
+        check_rate(rate)?;
+        self.rate = rate;
+        Ok(())
+    }
+}
+
+impl<F: Float> LearningRate<F> for DeltaRule<F> {
+    fn rate(&self) -> F { self.rate }
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError> {
+        check_rate(rate)?;
+        self.rate = rate;
+        Ok(())
+    }
+}
+
+impl<F: Float> LearningRate<F> for RewardHebbian<F> {
+    fn rate(&self) -> F { self.rate }
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError> {
+        check_rate(rate)?;
+        self.rate = rate;
+        Ok(())
+    }
+}
+
+impl<F: Float> LearningRate<F> for Adam<F> {
+    fn rate(&self) -> F { self.rate }
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError> {
+        check_rate(rate)?;
+        self.rate = rate;
+        Ok(())
+    }
+}
+
+impl<F: Float> LearningRate<F> for Adagrad<F> {
+    fn rate(&self) -> F { self.rate }
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError> {
+        check_rate(rate)?;
+        self.rate = rate;
+        Ok(())
+    }
+}
+
+impl<F: Float, M: LearningRate<F>> LearningRate<F> for Regularized<F, M> {
+    fn rate(&self) -> F { self.method.rate() }
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError> {
+        self.method.set_rate(rate)
+    }
+}
+
+impl<F: Float, M: LearningRate<F>> LearningRate<F> for Bptt<M> {
+    fn rate(&self) -> F { self.method.rate() }
+    fn set_rate(&mut self, rate: F) -> Result<(), HyperparameterError> {
+        self.method.set_rate(rate)
+    }
+}
+
+/// A schedule of the learning rate, giving its value at each step (usually
+/// an epoch) from its initial value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Schedule<F: Float> {
+    /// The rate never changes.
+    Constant,
+    /// The rate is multiplied by the given factor every given number of
+    /// steps.
+    StepDecay(usize, F),
+    /// The rate is multiplied by the given factor at each step.
+    Exponential(F),
+    /// The rate follows a half cosine from its initial value down to the
+    /// given minimum over the given number of steps, and then restarts.
+    Cosine(usize, F),
+    /// The rate at step `t` is `initial / (1 + decay * t)`.
+    InverseTime(F)
+}
+
+impl<F: Float> Schedule<F> {
+    /// The learning rate at given step, starting at `0`.
+    pub fn rate(&self, initial: F, step: usize) -> F {
+        match *self {
+            Schedule::Constant => initial,
+            Schedule::StepDecay(every, factor) => initial * factor.powi((step / every.max(1)) as i32),
+            Schedule::Exponential(factor) => initial * factor.powi(step as i32),
+            Schedule::Cosine(period, min) => {
+                let period = period.max(1);
+                let progress = F::from(step % period).unwrap() / F::from(period).unwrap();
+                let cos = (F::from(::std::f64::consts::PI).unwrap() * progress).cos();
+                min + (initial - min) * (one::<F>() + cos) / F::from(2).unwrap()
+            },
+            Schedule::InverseTime(decay) => initial / (one::<F>() + decay * F::from(step).unwrap())
+        }
+    }
+
+    /// Sets the rate of `method` to its value at given step.
+    ///
+    /// Fails, leaving the rate unchanged, if this value is not a valid
+    /// learning rate.
+    pub fn apply<M: LearningRate<F>>(&self, method: &mut M, initial: F, step: usize)
+        -> Result<(), HyperparameterError>
+    {
+        method.set_rate(self.rate(initial, step))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GradientDescent, Momentum, PerceptronRule, Adam, Adagrad, Adadelta, GradientMethod, OptimizerState, Schedule};
+    use super::{HyperparameterError, LearningRate};
+
+    #[test]
+    fn momentum() {
+        let mut params = [1.0f64, -1.0];
+        let mut state = OptimizerState::new();
+        let rule = Momentum::new(0.1, 0.5).unwrap();
+        rule.update(&mut params, &[1.0, 2.0], &mut state);
+        assert_eq!(params, [0.9, -1.2]);
+        // v = 0.5 * [1, 2] + [1, 2]
+        rule.update(&mut params, &[1.0, 2.0], &mut state);
+        assert!((params[0] - 0.75).abs() < 1e-12 && (params[1] + 1.5).abs() < 1e-12);
+        assert_eq!(state.steps(), 2);
+
+        let mut params = [0.0f64];
+        let mut state = OptimizerState::new();
+        let rule = Momentum::nesterov(1.0, 0.5).unwrap();
+        rule.update(&mut params, &[1.0], &mut state);
+        assert_eq!(params, [-1.5]);
+        state.reset();
+        rule.update(&mut params, &[1.0], &mut state);
+        assert_eq!(params, [-3.0]);
+    }
+
+    #[test]
+    fn adam() {
+        let mut params = [1.0f64, 1.0];
+        let mut state = OptimizerState::new();
+        let rule = Adam::new(0.1).unwrap();
+        // the first steps have the size of the learning rate, whatever the gradient
+        rule.update(&mut params, &[100.0, -0.01], &mut state);
+        assert!((params[0] - 0.9).abs() < 1e-6 && (params[1] - 1.1).abs() < 1e-5);
+        // minimizing x^2
+        let mut x = [3.0f64];
+        let mut state = OptimizerState::new();
+        for _ in 0..500 {
+            let g = [2.0 * x[0]];
+            rule.update(&mut x, &g, &mut state);
+        }
+        assert!(x[0].abs() < 0.05);
+    }
+
+    #[test]
+    fn adagrad_adadelta() {
+        let mut params = [0.0f64, 0.0];
+        let mut state = OptimizerState::new();
+        let rule = Adagrad::new(0.5).unwrap();
+        rule.update(&mut params, &[4.0, 0.0], &mut state);
+        rule.update(&mut params, &[3.0, 1.0], &mut state);
+        // 0.5 * (4/4 + 3/5) and 0.5 * 1/1
+        assert!((params[0] + 0.8).abs() < 1e-6 && (params[1] + 0.5).abs() < 1e-6);
+
+        // minimizing x^2
+        let mut x = [3.0f64];
+        let mut state = OptimizerState::new();
+        let rule = Adadelta { rho: 0.9, epsilon: 1e-2 };
+        for _ in 0..500 {
+            let g = [2.0 * x[0]];
+            rule.update(&mut x, &g, &mut state);
+        }
+        assert!(x[0].abs() < 0.1);
+    }
+
+    #[test]
+    fn schedules() {
+        assert_eq!(Schedule::Constant.rate(0.5f64, 10), 0.5);
+        assert_eq!(Schedule::StepDecay(3, 0.5).rate(1.0f64, 7), 0.25);
+        assert_eq!(Schedule::Exponential(0.5).rate(1.0f64, 3), 0.125);
+        assert_eq!(Schedule::InverseTime(0.5).rate(1.0f64, 2), 0.5);
+        let cosine = Schedule::Cosine(10, 0.1);
+        assert_eq!(cosine.rate(1.0f64, 0), 1.0);
+        assert!((cosine.rate(1.0f64, 5) - 0.55).abs() < 1e-12);
+        assert_eq!(cosine.rate(1.0f64, 10), 1.0);
+    }
+
+    #[test]
+    fn regularization() {
+        let rule = GradientDescent::new(0.5f64).unwrap().regularized(0.25, 1.0).unwrap();
+        let mut gradient = [0.0, 1.0, 0.0];
+        rule.regularize(&[2.0, -1.0, 0.0], &mut gradient);
+        assert_eq!(gradient, [2.25, -0.25, 0.0]);
+        let mut gradient = [1.0];
+        Adam::new(0.1f64).unwrap().regularize(&[5.0], &mut gradient);
+        assert_eq!(gradient, [1.0]);
+        // nested regularizations add up
+        let rule = Adam::new(0.1f64).unwrap().regularized(0.5, 0.0).unwrap().regularized(0.0, 0.1).unwrap();
+        assert_eq!(rule.weight_decay(), (0.5, 0.1));
+        assert_eq!(rule.method().method().rate(), 0.1);
+    }
+
+    #[test]
+    fn hyperparameters() {
+        let adam = Adam::new(0.01f64).unwrap().with_betas(0.8, 0.99).unwrap().with_epsilon(1e-6).unwrap();
+        assert_eq!((adam.beta1(), adam.beta2(), adam.epsilon()), (0.8, 0.99, 1e-6));
+        let adadelta = Adadelta::<f64>::new().with_rho(0.9).unwrap().with_epsilon(1e-4).unwrap();
+        assert_eq!((adadelta.rho(), adadelta.epsilon()), (0.9, 1e-4));
+        assert_eq!(Adagrad::new(0.1f64).unwrap().with_epsilon(1e-3).unwrap().epsilon(), 1e-3);
+        assert_eq!(PerceptronRule::new(0.5f64).unwrap().rate(), 0.5);
+    }
+
+    #[test]
+    fn invalid_rate() {
+        assert_eq!(GradientDescent::new(f64::NAN).err(), Some(HyperparameterError::Rate));
+        let mut rule = Adam::new(0.1f64).unwrap();
+        assert_eq!(rule.set_rate(0.0), Err(HyperparameterError::Rate));
+        assert_eq!(rule.rate(), 0.1);
+        assert_eq!(Schedule::Exponential(0.0).apply(&mut rule, 0.5, 1), Err(HyperparameterError::Rate));
+        assert_eq!(Schedule::Exponential(0.5).apply(&mut rule, 0.5, 1), Ok(()));
+        assert_eq!(rule.rate(), 0.25);
+    }
+
+    #[test]
+    fn invalid_momentum() {
+        assert_eq!(Momentum::new(0.1f64, 1.0).err(), Some(HyperparameterError::Decay("the momentum")));
+        assert!(Adam::new(0.1f64).unwrap().with_betas(0.9, -0.1).is_err());
+        assert_eq!(Adagrad::new(0.1f64).unwrap().with_epsilon(f64::INFINITY).err(), Some(HyperparameterError::Epsilon));
+    }
+
+    #[test]
+    fn invalid_regularization() {
+        let rule = Adam::new(0.1f64).unwrap().regularized(-1.0, 0.0);
+        assert_eq!(rule.err(), Some(HyperparameterError::Regularization));
+        assert_eq!(HyperparameterError::Rate.to_string(), "the learning rate must be finite and positive");
+    }
+}
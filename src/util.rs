@@ -1,12 +1,21 @@
 //! A set of utility method to combine networks.
 
+use std::cmp::min;
 use std::iter::repeat;
+use std::ops::Range;
 use std::marker::PhantomData;
 
-use num::{Float, zero};
+use num::{Float, one, zero};
 
-use Compute;
+use rand::{random, thread_rng};
+use rand::distributions::{IndependentSample, Normal};
+
+use {Compute, Differentiable, StatefulCompute};
 use {Method, UnsupervisedTrain, SupervisedTrain, BackpropTrain};
+use activations::Activation;
+use export::{Symbolic, number};
+use robustness::IntervalCompute;
+use training::{GradientMethod, OptimizerState};
 
 /*
  * Chaining
@@ -14,6 +23,7 @@ use {Method, UnsupervisedTrain, SupervisedTrain, BackpropTrain};
 
 /// An adapter tha chains two networks, linking the first's ouput to
 /// the second's input.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Chain<F, A, B> where A: Compute<F>, B: Compute<F> {
     _marker: PhantomData<F>,
     first: A,
@@ -27,6 +37,27 @@ impl<F, A, B> Chain<F, A, B>
     pub fn new(first: A, second: B) -> Chain<F, A, B> {
         Chain { _marker: PhantomData, first: first, second: second }
     }
+
+    /// The first network of the chain.
+    pub fn first(&self) -> &A {
+        &self.first
+    }
+
+    /// Mutable access to the first network of the chain, like to reset its
+    /// state.
+    pub fn first_mut(&mut self) -> &mut A {
+        &mut self.first
+    }
+
+    /// The second network of the chain.
+    pub fn second(&self) -> &B {
+        &self.second
+    }
+
+    /// Mutable access to the second network of the chain.
+    pub fn second_mut(&mut self) -> &mut B {
+        &mut self.second
+    }
 }
 
 impl<F, A, B> Compute<F> for Chain<F, A, B>
@@ -43,6 +74,21 @@ impl<F, A, B> Compute<F> for Chain<F, A, B>
     fn output_size(&self) -> usize {
         self.second.output_size()
     }
+
+    fn set_training(&mut self, training: bool) {
+        self.first.set_training(training);
+        self.second.set_training(training);
+    }
+}
+
+impl<F, A, B> Differentiable<F> for Chain<F, A, B>
+    where F: Float, A: Differentiable<F>, B: Differentiable<F>
+{
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let mid_input = self.first.compute(input);
+        let mid_gradient = self.second.input_gradient(&mid_input, output_gradient);
+        self.first.input_gradient(input, &mid_gradient)
+    }
 }
 
 /// The backpropagation training on a chain is computed this way:
@@ -51,6 +97,10 @@ impl<F, A, B> Compute<F> for Chain<F, A, B>
 /// - use the output of this layer to train the second with the target
 /// - use the output of the training of the second layer as a target
 ///   to train the first
+///
+/// Stateful networks (see `StatefulCompute`) compute the output of their
+/// next step without changing their state, and advance it when trained: each
+/// training step of the chain is thus exactly one step of each of them.
 impl<F, A, B, M> BackpropTrain<F, M> for Chain<F, A, B>
     where F: Float,
           A: BackpropTrain<F, M> + Compute<F>,
@@ -64,6 +114,21 @@ impl<F, A, B, M> BackpropTrain<F, M> for Chain<F, A, B>
     }
 }
 
+/// A chain whose first network is stateful steps it, and computes the
+/// second one on its output.
+impl<F, A, B> StatefulCompute<F> for Chain<F, A, B>
+    where F: Float, A: StatefulCompute<F>, B: Compute<F>
+{
+    fn step(&mut self, input: &[F]) -> Vec<F> {
+        let mid_input = self.first.step(input);
+        self.second.compute(&mid_input)
+    }
+
+    fn reset_state(&mut self) {
+        self.first.reset_state();
+    }
+}
+
 /// The supervised training on a chain is computed the same way as the
 /// backprop training, simply discarding its output.
 impl<F, A, B, M> SupervisedTrain<F, M> for Chain<F, A, B>
@@ -77,12 +142,134 @@ impl<F, A, B, M> SupervisedTrain<F, M> for Chain<F, A, B>
     }
 }
 
+impl<F, A, B> Symbolic<F> for Chain<F, A, B>
+    where F: Float,
+          A: Symbolic<F> + Compute<F>,
+          B: Symbolic<F> + Compute<F>
+{
+    fn symbolic(&self, inputs: &[String]) -> Vec<String> {
+        self.second.symbolic(&self.first.symbolic(inputs))
+    }
+}
+
+impl<F, A, B> IntervalCompute<F> for Chain<F, A, B>
+    where F: Float,
+          A: IntervalCompute<F> + Compute<F>,
+          B: IntervalCompute<F> + Compute<F>
+{
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        let (mid_lower, mid_upper) = self.first.compute_bounds(lower, upper);
+        self.second.compute_bounds(&mid_lower, &mid_upper)
+    }
+}
+
+/*
+ * Sequential
+ */
+
+/// A network that can be trained by backpropagation using method `M`.
+///
+/// This trait is implemented by all such networks, and exists so that they
+/// can be stored as `Box<dyn Layer<F, M>>`, see `Sequential`.
+pub trait Layer<F: Float, M: Method>: Compute<F> + BackpropTrain<F, M> {}
+
+impl<F: Float, M: Method, T: Compute<F> + BackpropTrain<F, M>> Layer<F, M> for T {}
+
+/// A stack of networks of any types, the output of each being the input of
+/// the next one.
+///
+/// It computes and trains like nested `Chain`s, but its layers are boxed, so
+/// it can be built at runtime (for example from a configuration file). All
+/// its layers must be trainable with the same method `M`.
+pub struct Sequential<F: Float, M: Method> {
+    layers: Vec<Box<dyn Layer<F, M>>>
+}
+
+impl<F: Float, M: Method> Sequential<F, M> {
+    /// Creates an empty stack, computing the identity.
+    pub fn new() -> Sequential<F, M> {
+        Sequential { layers: Vec::new() }
+    }
+
+    /// Adds a layer on top of the stack.
+    pub fn push<L: Layer<F, M> + 'static>(&mut self, layer: L) {
+        self.layers.push(Box::new(layer));
+    }
+
+    /// Adds a layer on top of the stack, returning it.
+    pub fn with<L: Layer<F, M> + 'static>(mut self, layer: L) -> Sequential<F, M> {
+        self.push(layer);
+        self
+    }
+
+    /// The number of layers.
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Whether the stack has no layers.
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// The layer at given index, `0` being the input layer.
+    pub fn layer(&self, i: usize) -> &dyn Layer<F, M> {
+        &*self.layers[i]
+    }
+}
+
+impl<F: Float, M: Method> Default for Sequential<F, M> {
+    fn default() -> Sequential<F, M> {
+        Sequential::new()
+    }
+}
+
+impl<F: Float, M: Method> Compute<F> for Sequential<F, M> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.layers.iter().fold(input.to_owned(), |x, layer| layer.compute(&x))
+    }
+
+    fn input_size(&self) -> usize {
+        self.layers.first().map(|l| l.input_size()).unwrap_or(0)
+    }
+
+    fn output_size(&self) -> usize {
+        self.layers.last().map(|l| l.output_size()).unwrap_or(0)
+    }
+
+    fn set_training(&mut self, training: bool) {
+        for layer in &mut self.layers {
+            layer.set_training(training);
+        }
+    }
+}
+
+impl<F: Float, M: Method> BackpropTrain<F, M> for Sequential<F, M> {
+    fn backprop_train(&mut self, rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let mut inputs = vec![input.to_owned()];
+        for layer in &self.layers[..self.layers.len().saturating_sub(1)] {
+            let next = layer.compute(inputs.last().unwrap());
+            inputs.push(next);
+        }
+        self.layers.iter_mut().zip(inputs).rev().fold(target.to_owned(), |target, (layer, input)| {
+            layer.backprop_train(rule, &input, &target)
+        })
+    }
+}
+
+impl<F: Float, M: Method> SupervisedTrain<F, M> for Sequential<F, M> {
+    fn supervised_train(&mut self, rule: &M, input: &[F], target: &[F]) {
+        self.backprop_train(rule, input, target);
+    }
+}
+
 /*
  * Parallelizing
  */
 
 /// An adapter that feeds the same input to two networks, and concatenate
 /// their outputs into its output.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Parallel<F, A, B> {
     _marker: PhantomData<F>,
     first: A,
@@ -114,6 +301,25 @@ impl<F, A, B> Compute<F> for Parallel<F, A, B>
     fn output_size(&self) -> usize {
         self.first.output_size() + self.second.output_size()
     }
+
+    fn set_training(&mut self, training: bool) {
+        self.first.set_training(training);
+        self.second.set_training(training);
+    }
+}
+
+impl<F, A, B> Differentiable<F> for Parallel<F, A, B>
+    where F: Float, A: Differentiable<F>, B: Differentiable<F>
+{
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let n = ::std::cmp::min(self.first.output_size(), output_gradient.len());
+        let mut gradient = self.first.input_gradient(input, &output_gradient[..n]);
+        let second = self.second.input_gradient(input, &output_gradient[n..]);
+        for (g, s) in gradient.iter_mut().zip(second) {
+            *g = *g + s;
+        }
+        gradient
+    }
 }
 
 impl<F, A, B, M> UnsupervisedTrain<F, M> for Parallel<F, A, B>
@@ -146,91 +352,1535 @@ impl<F, A, B, M> SupervisedTrain<F, M> for Parallel<F, A, B>
     }
 }
 
+impl<F, A, B> Symbolic<F> for Parallel<F, A, B>
+    where F: Float,
+          A: Symbolic<F> + Compute<F>,
+          B: Symbolic<F> + Compute<F>
+{
+    fn symbolic(&self, inputs: &[String]) -> Vec<String> {
+        let mut v = self.first.symbolic(inputs);
+        v.extend(self.second.symbolic(inputs));
+        v
+    }
+}
+
+impl<F, A, B> IntervalCompute<F> for Parallel<F, A, B>
+    where F: Float,
+          A: IntervalCompute<F> + Compute<F>,
+          B: IntervalCompute<F> + Compute<F>
+{
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        let (mut l, mut u) = self.first.compute_bounds(lower, upper);
+        let (l2, u2) = self.second.compute_bounds(lower, upper);
+        l.extend(l2);
+        u.extend(u2);
+        (l, u)
+    }
+}
+
 /*
- * Fixed output
+ * Splitting
  */
 
-/// A network that returns a fixed output, whatever the input is.
-pub struct FixedOutput<F: Float> {
-    output: Vec<F>
+/// An adapter that splits its input between two networks, and concatenates
+/// their outputs into its output.
+///
+/// The first network receives as many inputs as its `input_size()`, and the
+/// second one the remaining inputs. Unlike `Parallel`, this allows to
+/// process heterogeneous blocks of inputs with different networks.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Split<F, A, B> {
+    _marker: PhantomData<F>,
+    first: A,
+    second: B
 }
 
-impl<F: Float> FixedOutput<F> {
-    /// Creates a new fixed output network that will always return the
-    /// values provided as `output`.
-    pub fn new(output: &[F]) -> FixedOutput<F> {
-        FixedOutput { output: output.to_owned() }
+impl<F, A, B> Split<F, A, B>
+    where F: Float, A: Compute<F>, B: Compute<F>
+{
+    /// Splits the input between the two given networks.
+    pub fn new(first: A, second: B) -> Split<F, A, B> {
+        Split { _marker: PhantomData, first, second }
+    }
+
+    // the inputs of the first network, padded with zeros, and of the second
+    fn split_input(&self, input: &[F]) -> (Vec<F>, Vec<F>) {
+        let n = self.first.input_size();
+        let mut first = input[..min(n, input.len())].to_owned();
+        first.resize(n, zero());
+        let second = if input.len() > n { input[n..].to_owned() } else { Vec::new() };
+        (first, second)
     }
 }
 
-impl<F: Float> Compute<F> for FixedOutput<F> {
-    fn compute(&self, _input: &[F]) -> Vec<F> {
-        self.output.clone()
+impl<F, A, B> Compute<F> for Split<F, A, B>
+    where F: Float, A: Compute<F>, B: Compute<F>
+{
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let (first, second) = self.split_input(input);
+        let mut v = self.first.compute(&first);
+        v.extend(self.second.compute(&second));
+        v
     }
 
     fn input_size(&self) -> usize {
-        0
+        self.first.input_size() + self.second.input_size()
     }
 
     fn output_size(&self) -> usize {
-        self.output.len()
+        self.first.output_size() + self.second.output_size()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.first.set_training(training);
+        self.second.set_training(training);
     }
 }
 
-/// A network that simply returns its input
-pub struct Identity {
-    size: usize
+impl<F, A, B> Differentiable<F> for Split<F, A, B>
+    where F: Float, A: Differentiable<F>, B: Differentiable<F>
+{
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let (first, second) = self.split_input(input);
+        let n = min(self.first.output_size(), output_gradient.len());
+        let mut gradient = self.first.input_gradient(&first, &output_gradient[..n]);
+        gradient.resize(first.len(), zero());
+        gradient.extend(self.second.input_gradient(&second, &output_gradient[n..]));
+        gradient.truncate(input.len());
+        gradient
+    }
 }
 
-impl Identity {
-    /// Creates a new identity network of given size
-    pub fn new(size: usize) -> Identity {
-        Identity {
-            size: size
-        }
+impl<F, A, B, M> BackpropTrain<F, M> for Split<F, A, B>
+    where F: Float,
+          A: BackpropTrain<F, M> + Compute<F>,
+          B: BackpropTrain<F, M> + Compute<F>,
+          M: Method
+{
+    fn backprop_train(&mut self, rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let (first, second) = self.split_input(input);
+        let n = min(self.first.output_size(), target.len());
+        let mut returned = self.first.backprop_train(rule, &first, &target[..n]);
+        returned.resize(first.len(), zero());
+        returned.extend(self.second.backprop_train(rule, &second, &target[n..]));
+        returned.truncate(input.len());
+        returned
     }
 }
 
-impl<F: Float> Compute<F> for Identity {
+impl<F, A, B, M> SupervisedTrain<F, M> for Split<F, A, B>
+    where F: Float,
+          A: SupervisedTrain<F, M> + Compute<F>,
+          B: SupervisedTrain<F, M> + Compute<F>,
+          M: Method
+{
+    fn supervised_train(&mut self, rule: &M, input: &[F], target: &[F]) {
+        let (first, second) = self.split_input(input);
+        let n = min(self.first.output_size(), target.len());
+        self.first.supervised_train(rule, &first, &target[..n]);
+        self.second.supervised_train(rule, &second, &target[n..]);
+    }
+}
+
+impl<F, A, B> IntervalCompute<F> for Split<F, A, B>
+    where F: Float,
+          A: IntervalCompute<F> + Compute<F>,
+          B: IntervalCompute<F> + Compute<F>
+{
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        let ((lower1, lower2), (upper1, upper2)) = (self.split_input(lower), self.split_input(upper));
+        let (mut l, mut u) = self.first.compute_bounds(&lower1, &upper1);
+        let (l2, u2) = self.second.compute_bounds(&lower2, &upper2);
+        l.extend(l2);
+        u.extend(u2);
+        (l, u)
+    }
+}
+
+/*
+ * Merging
+ */
+
+/// An adapter that feeds the same input to two networks, and sums their
+/// outputs, weighted by a factor for each network.
+///
+/// Averaging the outputs of several networks gives an ensemble, whose
+/// predictions are usually better than the ones of its members. More than
+/// two networks can be merged by nesting `Merge`s, with suitable weights.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Merge<F, A, B> {
+    first: A,
+    second: B,
+    weights: (F, F)
+}
+
+impl<F, A, B> Merge<F, A, B>
+    where F: Float, A: Compute<F>, B: Compute<F>
+{
+    /// Merges two networks by summing their outputs.
+    pub fn sum(first: A, second: B) -> Merge<F, A, B> {
+        Merge::weighted(first, second, one(), one())
+    }
+
+    /// Merges two networks by averaging their outputs.
+    pub fn average(first: A, second: B) -> Merge<F, A, B> {
+        let half = F::from(0.5).unwrap();
+        Merge::weighted(first, second, half, half)
+    }
+
+    /// Merges two networks, computing `w1 * first(X) + w2 * second(X)`.
+    pub fn weighted(first: A, second: B, w1: F, w2: F) -> Merge<F, A, B> {
+        Merge { first, second, weights: (w1, w2) }
+    }
+
+    /// The weights of the two networks.
+    pub fn weights(&self) -> (F, F) {
+        self.weights
+    }
+
+    fn combine(&self, a: Vec<F>, b: Vec<F>) -> Vec<F> {
+        let (w1, w2) = self.weights;
+        (0..a.len().max(b.len())).map(|i| {
+            w1 * a.get(i).cloned().unwrap_or(zero()) + w2 * b.get(i).cloned().unwrap_or(zero())
+        }).collect()
+    }
+}
+
+impl<F, A, B> Compute<F> for Merge<F, A, B>
+    where F: Float, A: Compute<F>, B: Compute<F>
+{
     fn compute(&self, input: &[F]) -> Vec<F> {
-        let mut out = input.to_owned();
-        out.truncate(self.size);
-        let outsize = out.len();
-        if outsize < self.size { out.extend(repeat(zero::<F>()).take(self.size - outsize)); }
-        out
+        self.combine(self.first.compute(input), self.second.compute(input))
     }
 
     fn input_size(&self) -> usize {
-        self.size
+        ::std::cmp::max(self.first.input_size(), self.second.input_size())
     }
 
     fn output_size(&self) -> usize {
-        self.size
+        ::std::cmp::max(self.first.output_size(), self.second.output_size())
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.first.set_training(training);
+        self.second.set_training(training);
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::{Identity, Chain, Parallel};
+impl<F, A, B> Differentiable<F> for Merge<F, A, B>
+    where F: Float, A: Differentiable<F>, B: Differentiable<F>
+{
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let (w1, w2) = self.weights;
+        let first = output_gradient.iter().map(|&g| w1 * g).collect::<Vec<_>>();
+        let second = output_gradient.iter().map(|&g| w2 * g).collect::<Vec<_>>();
+        let mut gradient = self.first.input_gradient(input, &first);
+        for (g, s) in gradient.iter_mut().zip(self.second.input_gradient(input, &second)) {
+            *g = *g + s;
+        }
+        gradient
+    }
+}
 
-    use Compute;
+/// The backpropagation training on a merge trains each network with the
+/// error of the merged output scaled by its weight, and returns the sum of
+/// the errors of both networks to the previous layer.
+impl<F, A, B, M> BackpropTrain<F, M> for Merge<F, A, B>
+    where F: Float,
+          A: BackpropTrain<F, M> + Compute<F>,
+          B: BackpropTrain<F, M> + Compute<F>,
+          M: Method
+{
+    fn backprop_train(&mut self, rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let (w1, w2) = self.weights;
+        let (a, b) = (self.first.compute(input), self.second.compute(input));
+        let out = self.combine(a.clone(), b.clone());
+        let errors = out.iter().enumerate().map(|(i, &o)| {
+            o - target.get(i).cloned().unwrap_or(zero())
+        }).collect::<Vec<_>>();
+        let first_target = a.iter().zip(&errors).map(|(&y, &e)| y - w1 * e).collect::<Vec<_>>();
+        let second_target = b.iter().zip(&errors).map(|(&y, &e)| y - w2 * e).collect::<Vec<_>>();
+        let first = self.first.backprop_train(rule, input, &first_target);
+        let second = self.second.backprop_train(rule, input, &second_target);
+        // both returned values are the input minus an error
+        first.iter().zip(second).enumerate().map(|(i, (&r1, r2))| {
+            r1 + r2 - input.get(i).cloned().unwrap_or(zero())
+        }).collect()
+    }
+}
 
-    #[test]
-    fn identity() {
-        let id = Identity::new(4);
-        assert_eq!(id.compute(&[1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]), [1.0f32, 2.0, 3.0, 4.0]);
-        assert_eq!(id.compute(&[1.0f32, 2.0]), [1.0f32, 2.0, 0.0, 0.0]);
+impl<F, A, B, M> SupervisedTrain<F, M> for Merge<F, A, B>
+    where F: Float,
+          A: BackpropTrain<F, M> + Compute<F>,
+          B: BackpropTrain<F, M> + Compute<F>,
+          M: Method
+{
+    fn supervised_train(&mut self, rule: &M, input: &[F], target: &[F]) {
+        self.backprop_train(rule, input, target);
     }
+}
 
-    #[test]
-    fn chain() {
-        let ch = Chain::new(Identity::new(4), Identity::new(6));
-        assert_eq!(ch.compute(&[1.0f32, 2.0, 3.0]), [1.0f32, 2.0, 3.0, 0.0, 0.0, 0.0])
+impl<F, A, B> IntervalCompute<F> for Merge<F, A, B>
+    where F: Float,
+          A: IntervalCompute<F> + Compute<F>,
+          B: IntervalCompute<F> + Compute<F>
+{
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        let (w1, w2) = self.weights;
+        let scale = |w: F, (l, u): (Vec<F>, Vec<F>)| -> (Vec<F>, Vec<F>) {
+            let scaled = (l.iter().map(|&x| w * x).collect(), u.iter().map(|&x| w * x).collect());
+            if w >= zero() { scaled } else { (scaled.1, scaled.0) }
+        };
+        let (l1, u1) = scale(w1, self.first.compute_bounds(lower, upper));
+        let (l2, u2) = scale(w2, self.second.compute_bounds(lower, upper));
+        let add = |a: &[F], b: &[F]| (0..a.len().max(b.len())).map(|i| {
+            a.get(i).cloned().unwrap_or(zero()) + b.get(i).cloned().unwrap_or(zero())
+        }).collect::<Vec<_>>();
+        (add(&l1, &l2), add(&u1, &u2))
     }
+}
 
-    #[test]
-    fn parallel() {
-        let ch = Parallel::new(Identity::new(4), Identity::new(2));
-        assert_eq!(ch.compute(&[1.0f32, 2.0, 3.0]), [1.0f32, 2.0, 3.0, 0.0, 1.0, 2.0])
+/*
+ * Mixture
+ */
+
+/// An adapter that feeds the same input to two networks, and mixes their
+/// outputs with trainable coefficients.
+///
+/// Each output `j` is the convex combination `a_j * first_j(X) + (1 - a_j) *
+/// second_j(X)`, where `a_j = sigmoid(l_j)` and the logits `l_j` are trained
+/// along with the two networks. The mixture starts as an average.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mixture<F: Float, A, B> {
+    first: A,
+    second: B,
+    logits: Vec<F>,
+    logits_state: OptimizerState<F>
+}
+
+impl<F, A, B> Mixture<F, A, B>
+    where F: Float, A: Compute<F>, B: Compute<F>
+{
+    /// Mixes the two given networks, with coefficients initially `0.5`.
+    pub fn new(first: A, second: B) -> Mixture<F, A, B> {
+        let outputs = ::std::cmp::max(first.output_size(), second.output_size());
+        Mixture {
+            first,
+            second,
+            logits: vec![zero(); outputs],
+            logits_state: OptimizerState::new()
+        }
+    }
+
+    /// The coefficients of the first network for each output, those of the
+    /// second network being `1.0` minus them.
+    pub fn coefficients(&self) -> Vec<F> {
+        self.logits.iter().map(|&l| Activation::Sigmoid.value(l)).collect()
+    }
+
+    /// Clears the state kept by the training method for the coefficients.
+    pub fn reset_optimizer(&mut self) {
+        self.logits_state.reset();
+    }
+
+    fn mix(&self, a: &[F], b: &[F]) -> Vec<F> {
+        self.logits.iter().enumerate().map(|(j, &l)| {
+            let alpha = Activation::Sigmoid.value(l);
+            alpha * a.get(j).cloned().unwrap_or(zero())
+                + (one::<F>() - alpha) * b.get(j).cloned().unwrap_or(zero())
+        }).collect()
+    }
+}
+
+impl<F, A, B> Compute<F> for Mixture<F, A, B>
+    where F: Float, A: Compute<F>, B: Compute<F>
+{
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.mix(&self.first.compute(input), &self.second.compute(input))
     }
-}
\ No newline at end of file
+
+    fn input_size(&self) -> usize {
+        ::std::cmp::max(self.first.input_size(), self.second.input_size())
+    }
+
+    fn output_size(&self) -> usize {
+        self.logits.len()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.first.set_training(training);
+        self.second.set_training(training);
+    }
+}
+
+impl<F, A, B> Differentiable<F> for Mixture<F, A, B>
+    where F: Float, A: Differentiable<F>, B: Differentiable<F>
+{
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let alphas = self.coefficients();
+        let first = output_gradient.iter().zip(&alphas).map(|(&g, &a)| a * g).collect::<Vec<_>>();
+        let second = output_gradient.iter().zip(&alphas).map(|(&g, &a)| (one::<F>() - a) * g)
+                                    .collect::<Vec<_>>();
+        let mut gradient = self.first.input_gradient(input, &first);
+        for (g, s) in gradient.iter_mut().zip(self.second.input_gradient(input, &second)) {
+            *g = *g + s;
+        }
+        gradient
+    }
+}
+
+/// The backpropagation training on a mixture trains each network with the
+/// error of the mixed output scaled by its coefficient, updates the
+/// coefficients, and returns the sum of the errors of both networks to the
+/// previous layer.
+impl<F, A, B, M> BackpropTrain<F, M> for Mixture<F, A, B>
+    where F: Float,
+          A: BackpropTrain<F, M> + Compute<F>,
+          B: BackpropTrain<F, M> + Compute<F>,
+          M: GradientMethod<F>
+{
+    fn backprop_train(&mut self, rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let (a, b) = (self.first.compute(input), self.second.compute(input));
+        let out = self.mix(&a, &b);
+        let alphas = self.coefficients();
+        let value = |v: &[F], j: usize| v.get(j).cloned().unwrap_or(zero());
+        let errors = out.iter().enumerate().map(|(j, &o)| o - value(target, j)).collect::<Vec<_>>();
+        let first_target = (0..a.len()).map(|j| a[j] - value(&alphas, j) * value(&errors, j))
+                                       .collect::<Vec<_>>();
+        let second_target = (0..b.len()).map(|j| {
+            b[j] - (one::<F>() - value(&alphas, j)) * value(&errors, j)
+        }).collect::<Vec<_>>();
+        let logits_gradient = (0..self.logits.len()).map(|j| {
+            errors[j] * (value(&a, j) - value(&b, j)) * alphas[j] * (one::<F>() - alphas[j])
+        }).collect::<Vec<_>>();
+        let first = self.first.backprop_train(rule, input, &first_target);
+        let second = self.second.backprop_train(rule, input, &second_target);
+        rule.update(&mut self.logits, &logits_gradient, &mut self.logits_state);
+        first.iter().zip(second).enumerate().map(|(i, (&r1, r2))| r1 + r2 - value(input, i)).collect()
+    }
+}
+
+impl<F, A, B, M> SupervisedTrain<F, M> for Mixture<F, A, B>
+    where F: Float,
+          A: BackpropTrain<F, M> + Compute<F>,
+          B: BackpropTrain<F, M> + Compute<F>,
+          M: GradientMethod<F>
+{
+    fn supervised_train(&mut self, rule: &M, input: &[F], target: &[F]) {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+/*
+ * Ensemble
+ */
+
+/// A network averaging the outputs of several networks fed the same input,
+/// such as the snapshots taken by `trainer::Snapshots`.
+///
+/// An ensemble is meant for inference: its members are trained separately.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ensemble<N> {
+    members: Vec<N>
+}
+
+impl<N> Ensemble<N> {
+    /// Creates an ensemble of the given networks.
+    ///
+    /// Panics if `members` is empty.
+    pub fn new(members: Vec<N>) -> Ensemble<N> {
+        assert!(!members.is_empty(), "An ensemble needs at least one member.");
+        Ensemble { members }
+    }
+
+    /// The members of the ensemble.
+    pub fn members(&self) -> &[N] {
+        &self.members
+    }
+
+    /// Consumes the ensemble, returning its members.
+    pub fn into_members(self) -> Vec<N> {
+        self.members
+    }
+}
+
+impl<F: Float, N: Compute<F>> Compute<F> for Ensemble<N> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let mut sum = vec![zero::<F>(); Compute::<F>::output_size(self)];
+        for member in &self.members {
+            for (s, y) in sum.iter_mut().zip(member.compute(input)) {
+                *s = *s + y;
+            }
+        }
+        let count = F::from(self.members.len()).unwrap();
+        sum.into_iter().map(|s| s / count).collect()
+    }
+
+    fn input_size(&self) -> usize {
+        self.members.iter().map(|m| Compute::<F>::input_size(m)).max().unwrap_or(0)
+    }
+
+    fn output_size(&self) -> usize {
+        self.members.iter().map(|m| Compute::<F>::output_size(m)).max().unwrap_or(0)
+    }
+
+    fn set_training(&mut self, training: bool) {
+        for member in &mut self.members {
+            member.set_training(training);
+        }
+    }
+}
+
+/*
+ * Fixed output
+ */
+
+/// A network that returns a fixed output, whatever the input is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixedOutput<F: Float> {
+    output: Vec<F>
+}
+
+impl<F: Float> FixedOutput<F> {
+    /// Creates a new fixed output network that will always return the
+    /// values provided as `output`.
+    pub fn new(output: &[F]) -> FixedOutput<F> {
+        FixedOutput { output: output.to_owned() }
+    }
+}
+
+impl<F: Float> Compute<F> for FixedOutput<F> {
+    fn compute(&self, _input: &[F]) -> Vec<F> {
+        self.output.clone()
+    }
+
+    fn input_size(&self) -> usize {
+        0
+    }
+
+    fn output_size(&self) -> usize {
+        self.output.len()
+    }
+}
+
+impl<F: Float> Symbolic<F> for FixedOutput<F> {
+    fn symbolic(&self, _inputs: &[String]) -> Vec<String> {
+        self.output.iter().map(|&x| number(x)).collect()
+    }
+}
+
+impl<F: Float> IntervalCompute<F> for FixedOutput<F> {
+    fn compute_bounds(&self, _lower: &[F], _upper: &[F]) -> (Vec<F>, Vec<F>) {
+        (self.output.clone(), self.output.clone())
+    }
+}
+
+impl<F: Float> Differentiable<F> for FixedOutput<F> {
+    fn input_gradient(&self, input: &[F], _output_gradient: &[F]) -> Vec<F> {
+        vec![zero(); input.len()]
+    }
+}
+
+/// A network that simply returns its input
+#[derive(Clone, Debug, PartialEq)]
+pub struct Identity {
+    size: usize
+}
+
+impl Identity {
+    /// Creates a new identity network of given size
+    pub fn new(size: usize) -> Identity {
+        Identity {
+            size: size
+        }
+    }
+}
+
+impl<F: Float> Compute<F> for Identity {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let mut out = input.to_owned();
+        out.truncate(self.size);
+        let outsize = out.len();
+        if outsize < self.size { out.extend(repeat(zero::<F>()).take(self.size - outsize)); }
+        out
+    }
+
+    fn input_size(&self) -> usize {
+        self.size
+    }
+
+    fn output_size(&self) -> usize {
+        self.size
+    }
+}
+
+impl<F: Float> Differentiable<F> for Identity {
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        (0..input.len()).map(|i| {
+            if i < self.size { output_gradient.get(i).cloned().unwrap_or(zero()) } else { zero() }
+        }).collect()
+    }
+}
+
+impl<F: Float> Symbolic<F> for Identity {
+    fn symbolic(&self, inputs: &[String]) -> Vec<String> {
+        (0..self.size).map(|i| inputs.get(i).cloned().unwrap_or("0".to_owned())).collect()
+    }
+}
+
+impl<F: Float> IntervalCompute<F> for Identity {
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        (self.compute(lower), self.compute(upper))
+    }
+}
+
+/*
+ * Closures
+ */
+
+/// A network computing a given function, with declared input and output
+/// sizes.
+///
+/// This allows to put arbitrary preprocessing or postprocessing steps in a
+/// `Chain`. The function is not differentiated, nor trained.
+#[derive(Clone)]
+pub struct FnCompute<F, G> {
+    _marker: PhantomData<F>,
+    inputs: usize,
+    outputs: usize,
+    function: G
+}
+
+impl<F: Float, G: Fn(&[F]) -> Vec<F>> FnCompute<F, G> {
+    /// Wraps `function`, which takes `inputs` values and returns `outputs`
+    /// values.
+    pub fn new(inputs: usize, outputs: usize, function: G) -> FnCompute<F, G> {
+        FnCompute {
+            _marker: PhantomData,
+            inputs,
+            outputs,
+            function
+        }
+    }
+}
+
+impl<F: Float, G: Fn(&[F]) -> Vec<F>> Compute<F> for FnCompute<F, G> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let mut input = input.to_owned();
+        input.resize(self.inputs, zero());
+        let mut output = (self.function)(&input);
+        output.resize(self.outputs, zero());
+        output
+    }
+
+    fn input_size(&self) -> usize {
+        self.inputs
+    }
+
+    fn output_size(&self) -> usize {
+        self.outputs
+    }
+}
+
+/*
+ * Residual
+ */
+
+/// An adapter adding its input to the output of a network, which must have
+/// as many outputs as inputs: it computes `X + f(X)`.
+///
+/// Such skip connections let the gradients flow directly to the first layers
+/// of deep chains, which makes them much easier to train.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Residual<F, A> {
+    _marker: PhantomData<F>,
+    inner: A
+}
+
+impl<F: Float, A: Compute<F>> Residual<F, A> {
+    /// Wraps given network with a skip connection.
+    pub fn new(inner: A) -> Residual<F, A> {
+        assert!(inner.input_size() == inner.output_size(),
+                "The inner network of a residual must have as many outputs as inputs.");
+        Residual { _marker: PhantomData, inner }
+    }
+
+    /// The wrapped network.
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+impl<F: Float, A: Compute<F>> Compute<F> for Residual<F, A> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let mut out = self.inner.compute(input);
+        for (o, &x) in out.iter_mut().zip(input) {
+            *o = *o + x;
+        }
+        out
+    }
+
+    fn input_size(&self) -> usize {
+        self.inner.input_size()
+    }
+
+    fn output_size(&self) -> usize {
+        self.inner.output_size()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.inner.set_training(training);
+    }
+}
+
+impl<F: Float, A: Differentiable<F>> Differentiable<F> for Residual<F, A> {
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let mut gradient = self.inner.input_gradient(input, output_gradient);
+        for (g, &o) in gradient.iter_mut().zip(output_gradient) {
+            *g = *g + o;
+        }
+        gradient
+    }
+}
+
+/// The backpropagation training on a residual trains the inner network
+/// towards `T - X`, and the error returned to the previous layer is the sum
+/// of the errors through the inner network and through the skip connection.
+impl<F, A, M> BackpropTrain<F, M> for Residual<F, A>
+    where F: Float,
+          A: BackpropTrain<F, M> + Compute<F>,
+          M: Method
+{
+    fn backprop_train(&mut self, rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let out = self.compute(input);
+        let inner_target = (0..out.len()).map(|i| {
+            target.get(i).cloned().unwrap_or(zero()) - input.get(i).cloned().unwrap_or(zero())
+        }).collect::<Vec<_>>();
+        let mut returned = self.inner.backprop_train(rule, input, &inner_target);
+        for (i, r) in returned.iter_mut().enumerate().take(out.len()) {
+            *r = *r - (out[i] - target.get(i).cloned().unwrap_or(zero()));
+        }
+        returned
+    }
+}
+
+impl<F, A, M> SupervisedTrain<F, M> for Residual<F, A>
+    where F: Float,
+          A: BackpropTrain<F, M> + Compute<F>,
+          M: Method
+{
+    fn supervised_train(&mut self, rule: &M, input: &[F], target: &[F]) {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+impl<F: Float, A: IntervalCompute<F>> IntervalCompute<F> for Residual<F, A> {
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        let (mut out_lower, mut out_upper) = self.inner.compute_bounds(lower, upper);
+        for (o, &l) in out_lower.iter_mut().zip(lower) {
+            *o = *o + l;
+        }
+        for (o, &u) in out_upper.iter_mut().zip(upper) {
+            *o = *o + u;
+        }
+        (out_lower, out_upper)
+    }
+}
+
+/*
+ * Slicing
+ */
+
+/// A network that picks some of its inputs, given by their indices, and
+/// returns them in the given order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Slice {
+    inputs: usize,
+    indices: Vec<usize>
+}
+
+impl Slice {
+    /// Creates a new slice of an input of size `inputs`, returning the
+    /// values at `indices`.
+    pub fn new(inputs: usize, indices: Vec<usize>) -> Slice {
+        assert!(indices.iter().all(|&i| i < inputs), "The indices must be smaller than the input size.");
+        Slice {
+            inputs,
+            indices
+        }
+    }
+
+    /// Creates a new slice of an input of size `inputs`, returning the
+    /// values in given range.
+    pub fn range(inputs: usize, range: Range<usize>) -> Slice {
+        Slice::new(inputs, range.collect())
+    }
+
+    /// The indices of the inputs returned by this slice.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+}
+
+impl<F: Float> Compute<F> for Slice {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.indices.iter().map(|&i| input.get(i).cloned().unwrap_or(zero())).collect()
+    }
+
+    fn input_size(&self) -> usize {
+        self.inputs
+    }
+
+    fn output_size(&self) -> usize {
+        self.indices.len()
+    }
+}
+
+impl<F: Float> Differentiable<F> for Slice {
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let mut gradient = vec![zero(); input.len()];
+        for (&i, &g) in self.indices.iter().zip(output_gradient) {
+            if i < gradient.len() { gradient[i] = gradient[i] + g; }
+        }
+        gradient
+    }
+}
+
+/// The backpropagation through a slice routes the errors back to the
+/// selected inputs, the other ones getting no error.
+impl<F: Float, M: Method> BackpropTrain<F, M> for Slice {
+    fn backprop_train(&mut self, _rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let out = self.compute(input);
+        let errors = out.iter().enumerate().map(|(k, &o)| {
+            o - target.get(k).cloned().unwrap_or(zero())
+        }).collect::<Vec<_>>();
+        let gradient = self.input_gradient(input, &errors);
+        input.iter().zip(gradient).map(|(&x, g)| x - g).collect()
+    }
+}
+
+impl<F: Float> Symbolic<F> for Slice {
+    fn symbolic(&self, inputs: &[String]) -> Vec<String> {
+        self.indices.iter().map(|&i| inputs.get(i).cloned().unwrap_or("0".to_owned())).collect()
+    }
+}
+
+impl<F: Float> IntervalCompute<F> for Slice {
+    fn compute_bounds(&self, lower: &[F], upper: &[F]) -> (Vec<F>, Vec<F>) {
+        (self.compute(lower), self.compute(upper))
+    }
+}
+
+/*
+ * Recurrence
+ */
+
+/// An adapter feeding the last output of a network back to its input, which
+/// makes a simple recurrent network out of any network.
+///
+/// The inner network receives the input followed by its own previous output,
+/// which is the state of the recurrence, initially all `0.0`. It thus needs
+/// at least as many inputs as outputs, the remaining ones being the inputs
+/// of the recurrent network.
+///
+/// The state is advanced by `StatefulCompute::step(..)` and by each training
+/// step, which trains the inner network on the input and the current state
+/// before advancing it. The training only goes back one step in time.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Recurrent<F: Float, N> {
+    inner: N,
+    inputs: usize,
+    state: Vec<F>
+}
+
+impl<F: Float, N: Compute<F>> Recurrent<F, N> {
+    /// Wraps given network, with a null initial state.
+    pub fn new(inner: N) -> Recurrent<F, N> {
+        let outputs = inner.output_size();
+        assert!(inner.input_size() >= outputs,
+                "The inner network of a recurrent needs at least as many inputs as outputs.");
+        Recurrent {
+            inputs: inner.input_size() - outputs,
+            inner,
+            state: vec![zero(); outputs]
+        }
+    }
+
+    /// The wrapped network.
+    pub fn inner(&self) -> &N {
+        &self.inner
+    }
+
+    /// The current state, which is the last output.
+    pub fn state(&self) -> &[F] {
+        &self.state
+    }
+
+    // the input of the inner network for given input and the current state
+    fn full_input(&self, input: &[F]) -> Vec<F> {
+        let mut full = input[..min(self.inputs, input.len())].to_owned();
+        full.resize(self.inputs, zero());
+        full.extend_from_slice(&self.state);
+        full
+    }
+}
+
+impl<F: Float, N: Compute<F>> Compute<F> for Recurrent<F, N> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.inner.compute(&self.full_input(input))
+    }
+
+    fn input_size(&self) -> usize {
+        self.inputs
+    }
+
+    fn output_size(&self) -> usize {
+        self.inner.output_size()
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.inner.set_training(training);
+    }
+}
+
+impl<F: Float, N: Compute<F>> StatefulCompute<F> for Recurrent<F, N> {
+    fn step(&mut self, input: &[F]) -> Vec<F> {
+        let out = self.compute(input);
+        self.state = out.clone();
+        out
+    }
+
+    fn reset_state(&mut self) {
+        for s in &mut self.state {
+            *s = zero();
+        }
+    }
+}
+
+impl<F, N, M> BackpropTrain<F, M> for Recurrent<F, N>
+    where F: Float,
+          N: BackpropTrain<F, M> + Compute<F>,
+          M: Method
+{
+    fn backprop_train(&mut self, rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let full = self.full_input(input);
+        let out = self.inner.compute(&full);
+        let mut returned = self.inner.backprop_train(rule, &full, target);
+        self.state = out;
+        returned.truncate(min(self.inputs, input.len()));
+        returned
+    }
+}
+
+impl<F, N, M> SupervisedTrain<F, M> for Recurrent<F, N>
+    where F: Float,
+          N: BackpropTrain<F, M> + Compute<F>,
+          M: Method
+{
+    fn supervised_train(&mut self, rule: &M, input: &[F], target: &[F]) {
+        self.backprop_train(rule, input, target);
+    }
+}
+
+/*
+ * Time delay
+ */
+
+/// A network buffering its last inputs, so that feedforward networks can be
+/// applied to a sliding window of a time series.
+///
+/// Each input is a frame of `size` values, and the output is the
+/// concatenation of the last `frames` frames, the oldest first. Frames not
+/// received yet are all `0.0`.
+///
+/// The frames are buffered by `StatefulCompute::step(..)` and by each
+/// training step, `compute(..)` giving the window the next step would
+/// output. Training returns the target of the current frame only, the
+/// previous ones being in the past.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeDelay<F: Float> {
+    size: usize,
+    frames: usize,
+    buffer: Vec<F>
+}
+
+impl<F: Float> TimeDelay<F> {
+    /// Creates a time delay of `frames` frames of `size` values each.
+    pub fn new(size: usize, frames: usize) -> TimeDelay<F> {
+        assert!(frames > 0, "A time delay needs at least one frame.");
+        TimeDelay {
+            size,
+            frames,
+            buffer: vec![zero(); size * frames]
+        }
+    }
+
+    /// The number of buffered frames.
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Clears the buffered frames, typically before a new time series.
+    pub fn reset(&mut self) {
+        for v in &mut self.buffer {
+            *v = zero();
+        }
+    }
+}
+
+impl<F: Float> Compute<F> for TimeDelay<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.buffer[self.size..].iter()
+                                .cloned()
+                                .chain(input.iter().cloned().chain(repeat(zero())).take(self.size))
+                                .collect()
+    }
+
+    fn input_size(&self) -> usize {
+        self.size
+    }
+
+    fn output_size(&self) -> usize {
+        self.size * self.frames
+    }
+}
+
+impl<F: Float> StatefulCompute<F> for TimeDelay<F> {
+    fn step(&mut self, input: &[F]) -> Vec<F> {
+        self.buffer = self.compute(input);
+        self.buffer.clone()
+    }
+
+    fn reset_state(&mut self) {
+        self.reset();
+    }
+}
+
+impl<F: Float, M: Method> BackpropTrain<F, M> for TimeDelay<F> {
+    fn backprop_train(&mut self, _rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        self.step(input);
+        let current = (self.frames - 1) * self.size;
+        (current..current + self.size).map(|i| target.get(i).cloned().unwrap_or(zero())).collect()
+    }
+}
+
+/*
+ * Dropout
+ */
+
+/// A network that randomly drops some of its inputs while training, a
+/// regularization preventing the following layers from relying too much on
+/// any single input.
+///
+/// In training mode, each input is set to `0.0` with probability `rate`, and
+/// otherwise returned unchanged. In evaluation mode, all the inputs are
+/// scaled by `1 - rate`, their expected value during training.
+///
+/// The mask of dropped inputs is drawn when switching to training mode and
+/// after each training step, and used by `compute(..)` until then: the
+/// computation stays deterministic, and chains training this network after
+/// computing its output go back through the same inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dropout<F: Float> {
+    size: usize,
+    rate: F,
+    mask: Option<Vec<bool>>
+}
+
+impl<F: Float> Dropout<F> {
+    /// Creates a new dropout of `size` inputs, each of them being dropped
+    /// with probability `rate` while training.
+    pub fn new(size: usize, rate: F) -> Dropout<F> {
+        assert!(rate >= zero() && rate < one(), "The dropout rate must be in [0, 1).");
+        Dropout {
+            size,
+            rate,
+            mask: None
+        }
+    }
+
+    /// The probability of each input to be dropped while training.
+    pub fn rate(&self) -> F {
+        self.rate
+    }
+
+    /// Whether this network is in training mode.
+    pub fn is_training(&self) -> bool {
+        self.mask.is_some()
+    }
+
+    fn draw_mask(&self) -> Vec<bool> {
+        let rate = self.rate.to_f64().unwrap();
+        (0..self.size).map(|_| random::<f64>() >= rate).collect()
+    }
+
+    fn apply(&self, values: &[F], mask: Option<&[bool]>) -> Vec<F> {
+        (0..self.size).map(|i| {
+            let x = values.get(i).cloned().unwrap_or(zero());
+            match mask {
+                Some(mask) => if mask[i] { x } else { zero() },
+                None => x * (one::<F>() - self.rate)
+            }
+        }).collect()
+    }
+}
+
+impl<F: Float> Compute<F> for Dropout<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        self.apply(input, self.mask.as_ref().map(|m| &m[..]))
+    }
+
+    fn input_size(&self) -> usize {
+        self.size
+    }
+
+    fn output_size(&self) -> usize {
+        self.size
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.mask = if training { Some(self.draw_mask()) } else { None };
+    }
+}
+
+impl<F: Float> Differentiable<F> for Dropout<F> {
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        let mut gradient = self.apply(output_gradient, self.mask.as_ref().map(|m| &m[..]));
+        gradient.resize(input.len(), zero());
+        gradient
+    }
+}
+
+impl<F: Float, M: Method> BackpropTrain<F, M> for Dropout<F> {
+    fn backprop_train(&mut self, _rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        let gradient = {
+            let mask = self.mask.as_ref().map(|m| &m[..]);
+            let out = self.apply(input, mask);
+            let errors = out.iter()
+                            .enumerate()
+                            .map(|(j, o)| *o - target.get(j).cloned().unwrap_or(zero()))
+                            .collect::<Vec<_>>();
+            self.apply(&errors, mask)
+        };
+        // a new mask for the next step
+        if self.mask.is_some() {
+            self.mask = Some(self.draw_mask());
+        }
+        input.iter().enumerate().map(|(i, &x)| x - gradient.get(i).cloned().unwrap_or(zero())).collect()
+    }
+}
+
+/*
+ * Gaussian noise
+ */
+
+/// A network that adds Gaussian noise to its inputs while training, a
+/// regularization making the following layers robust to small perturbations.
+///
+/// In training mode, a value drawn from a normal distribution centered on
+/// `0.0` with given standard deviation is added to each input. In evaluation
+/// mode, the inputs are returned unchanged.
+///
+/// As for `Dropout`, the noise is drawn when entering training mode and after
+/// each training step, so that the computations in between, and the training
+/// step that follows them, all see the same noise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GaussianNoise<F: Float> {
+    size: usize,
+    std_dev: F,
+    noise: Option<Vec<F>>
+}
+
+impl<F: Float> GaussianNoise<F> {
+    /// Creates a new noise of `size` inputs, with given standard deviation.
+    pub fn new(size: usize, std_dev: F) -> GaussianNoise<F> {
+        GaussianNoise {
+            size,
+            std_dev,
+            noise: None
+        }
+    }
+
+    /// The standard deviation of the noise.
+    pub fn std_dev(&self) -> F {
+        self.std_dev
+    }
+
+    /// Whether this network is in training mode.
+    pub fn is_training(&self) -> bool {
+        self.noise.is_some()
+    }
+
+    fn draw_noise(&self) -> Vec<F> {
+        let normal = Normal::new(0.0, self.std_dev.to_f64().unwrap());
+        let mut rng = thread_rng();
+        (0..self.size).map(|_| F::from(normal.ind_sample(&mut rng)).unwrap()).collect()
+    }
+}
+
+impl<F: Float> Compute<F> for GaussianNoise<F> {
+    fn compute(&self, input: &[F]) -> Vec<F> {
+        let mut out = Identity::new(self.size).compute(input);
+        if let Some(ref noise) = self.noise {
+            for (x, &n) in out.iter_mut().zip(noise) {
+                *x = *x + n;
+            }
+        }
+        out
+    }
+
+    fn input_size(&self) -> usize {
+        self.size
+    }
+
+    fn output_size(&self) -> usize {
+        self.size
+    }
+
+    fn set_training(&mut self, training: bool) {
+        self.noise = if training { Some(self.draw_noise()) } else { None };
+    }
+}
+
+impl<F: Float> Differentiable<F> for GaussianNoise<F> {
+    fn input_gradient(&self, input: &[F], output_gradient: &[F]) -> Vec<F> {
+        Identity::new(self.size).input_gradient(input, output_gradient)
+    }
+}
+
+impl<F: Float, M: Method> BackpropTrain<F, M> for GaussianNoise<F> {
+    fn backprop_train(&mut self, _rule: &M, input: &[F], target: &[F]) -> Vec<F> {
+        // the noise being additive, the gradient goes through unchanged, as
+        // long as the output is computed with the noise the next layers saw
+        let out = self.compute(input);
+        // a new noise for the next step
+        if self.noise.is_some() {
+            self.noise = Some(self.draw_noise());
+        }
+        input.iter().enumerate().map(|(i, &x)| {
+            x - (out.get(i).cloned().unwrap_or(zero()) - target.get(i).cloned().unwrap_or(zero()))
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Identity, Chain, FnCompute, Merge, Mixture, Parallel, Recurrent, Residual, Sequential, Slice, Split, TimeDelay, Dropout, GaussianNoise};
+    use super::{Ensemble, FixedOutput};
+
+    use {Compute, Differentiable, BackpropTrain, FeedforwardLayer, MaxoutLayer, Matrix, StatefulCompute, SupervisedTrain};
+    use activations::Activation;
+    use training::GradientDescent;
+
+    #[test]
+    fn identity() {
+        let id = Identity::new(4);
+        assert_eq!(id.compute(&[1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0]), [1.0f32, 2.0, 3.0, 4.0]);
+        assert_eq!(id.compute(&[1.0f32, 2.0]), [1.0f32, 2.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn chain() {
+        let ch = Chain::new(Identity::new(4), Identity::new(6));
+        assert_eq!(ch.compute(&[1.0f32, 2.0, 3.0]), [1.0f32, 2.0, 3.0, 0.0, 0.0, 0.0])
+    }
+
+    #[test]
+    fn parallel() {
+        let ch = Parallel::new(Identity::new(4), Identity::new(2));
+        assert_eq!(ch.compute(&[1.0f32, 2.0, 3.0]), [1.0f32, 2.0, 3.0, 0.0, 1.0, 2.0])
+    }
+
+    #[test]
+    fn slice() {
+        let sl = Slice::new(4, vec![3, 0, 3]);
+        assert_eq!(sl.compute(&[1.0f32, 2.0, 3.0, 4.0]), [4.0f32, 1.0, 4.0]);
+        assert_eq!(sl.compute(&[1.0f32, 2.0]), [0.0f32, 1.0, 0.0]);
+
+        let mut sl = Slice::range(4, 1..3);
+        assert_eq!(sl.indices(), [1, 2]);
+        let returned = sl.backprop_train(&GradientDescent::new(1.0).unwrap(), &[1.0f64, 2.0, 3.0, 4.0], &[1.0, 1.0]);
+        assert_eq!(returned, [1.0, 1.0, 1.0, 4.0]);
+        // a layer wired to a part of the input can be trained
+        let layer = FeedforwardLayer::new(2, 1, Activation::Identity);
+        let mut net = Chain::new(Slice::range(4, 2..4), layer);
+        for _ in 0..100 {
+            net.supervised_train(&GradientDescent::new(0.1).unwrap(), &[5.0f64, 5.0, 1.0, 0.0], &[2.0]);
+        }
+        assert!((net.compute(&[0.0, 0.0, 1.0, 0.0])[0] - 2.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn dropout() {
+        let mut dropout = Dropout::new(1000, 0.25f64);
+        let input = vec![1.0; 1000];
+        assert!(dropout.compute(&input).iter().all(|&x| x == 0.75));
+        let mut chain = Chain::new(Identity::new(1000), dropout.clone());
+        chain.set_training(true);
+        let out = chain.compute(&input);
+        assert!(out.iter().all(|&x| x == 0.0 || x == 1.0));
+        let dropped = out.iter().filter(|&&x| x == 0.0).count();
+        assert!(dropped > 150 && dropped < 350);
+        // the training goes back through the inputs kept by the last computation
+        dropout.set_training(true);
+        let out = dropout.compute(&input);
+        assert_eq!(dropout.compute(&input), out);
+        let returned = BackpropTrain::<f64, GradientDescent<f64>>::backprop_train(
+            &mut dropout, &GradientDescent::new(1.0).unwrap(), &input, &vec![2.0; 1000]);
+        for (o, r) in out.iter().zip(returned) {
+            assert_eq!(r, if *o == 0.0 { 1.0 } else { 2.0 });
+        }
+        // and then draws a new mask
+        assert!(dropout.compute(&input) != out);
+    }
+
+    #[test]
+    fn gaussian_noise() {
+        let mut noise = GaussianNoise::new(1000, 0.5f64);
+        let input = vec![1.0; 1000];
+        assert_eq!(noise.compute(&input), input);
+        noise.set_training(true);
+        let out = noise.compute(&input);
+        let mean = out.iter().fold(0.0, |s, x| s + x) / 1000.0;
+        let var = out.iter().fold(0.0, |s, x| s + (x - mean) * (x - mean)) / 1000.0;
+        assert!((mean - 1.0).abs() < 0.1 && (var - 0.25).abs() < 0.05);
+        // the training goes back through the noise of the last computation
+        assert_eq!(noise.compute(&input), out);
+        let target = out.iter().map(|o| o - 0.5).collect::<Vec<_>>();
+        let returned = BackpropTrain::<f64, GradientDescent<f64>>::backprop_train(
+            &mut noise, &GradientDescent::new(1.0).unwrap(), &input, &target);
+        assert!(returned.iter().all(|r| (r - 0.5).abs() < 1e-12));
+        // and then draws a new noise
+        assert!(noise.compute(&input) != out);
+        noise.set_training(false);
+        assert_eq!(noise.compute(&input), input);
+    }
+
+    #[test]
+    fn sequential() {
+        let mut acc = 0;
+        let mut random = move || { acc += 1; ((acc * 7) % 5) as f64 / 5.0 - 0.4 };
+        let first = FeedforwardLayer::new_from(3, 4, Activation::Sigmoid, &mut random);
+        let second = MaxoutLayer::new_from(4, 2, 2, &mut random);
+        let mut chain = Chain::new(first.clone(), second.clone());
+        let mut sequential = Sequential::new().with(first).with(Dropout::new(4, 0.0));
+        sequential.push(second);
+        sequential.push(Dropout::new(2, 0.0));
+        assert_eq!(sequential.len(), 4);
+        assert_eq!((sequential.input_size(), sequential.output_size()), (3, 2));
+        let input = [0.5, -1.0, 0.25];
+        let rule = GradientDescent::new(0.1).unwrap();
+        assert_eq!(sequential.compute(&input), chain.compute(&input));
+        for _ in 0..10 {
+            sequential.supervised_train(&rule, &input, &[1.0, 0.0]);
+            chain.supervised_train(&rule, &input, &[1.0, 0.0]);
+        }
+        assert_eq!(sequential.compute(&input), chain.compute(&input));
+
+        let boxed: Box<dyn Compute<f64>> = Box::new(sequential);
+        assert_eq!(Chain::new(boxed, Identity::new(2)).compute(&input), chain.compute(&input));
+    }
+
+    #[test]
+    fn residual() {
+        let layer = FeedforwardLayer::from_matrices(Matrix::from_vec(2, 2, vec![0.5f64, -1.0, 0.25, 0.0]),
+                                                    vec![0.0, 1.0], Activation::Sigmoid);
+        let mut residual = Residual::new(layer.clone());
+        let input = [0.4, -0.8];
+        let inner = layer.compute(&input);
+        assert_eq!(residual.compute(&input), [inner[0] + 0.4, inner[1] - 0.8]);
+        let gradient = residual.input_gradient(&input, &[1.0, -2.0]);
+        let inner_gradient = layer.input_gradient(&input, &[1.0, -2.0]);
+        assert_eq!(gradient, [inner_gradient[0] + 1.0, inner_gradient[1] - 2.0]);
+
+        // the returned error is the input gradient of the squared error
+        let target = [1.0, 0.0];
+        let out = residual.compute(&input);
+        let errors = [out[0] - target[0], out[1] - target[1]];
+        let gradient = residual.input_gradient(&input, &errors);
+        let returned = residual.backprop_train(&GradientDescent::new(0.1).unwrap(), &input, &target);
+        for i in 0..2 {
+            assert!((returned[i] - (input[i] - gradient[i])).abs() < 1e-12);
+        }
+        for _ in 0..200 {
+            residual.supervised_train(&GradientDescent::new(0.5).unwrap(), &input, &target);
+        }
+        assert!(residual.compute(&input).iter().zip(&target).all(|(o, t)| (o - t).abs() < 0.05));
+    }
+
+    #[test]
+    fn merge() {
+        let first = FeedforwardLayer::from_matrices(Matrix::from_vec(2, 2, vec![1.0f64, 0.0, 0.5, -1.0]),
+                                                    vec![0.0, 0.0], Activation::Identity);
+        let second = FeedforwardLayer::from_matrices(Matrix::from_vec(2, 2, vec![0.0f64, 2.0, 1.0, 1.0]),
+                                                     vec![1.0, 0.0], Activation::Sigmoid);
+        let input = [0.5, -0.5];
+        let (a, b) = (first.compute(&input), second.compute(&input));
+        let sum = Merge::sum(first.clone(), second.clone());
+        assert_eq!(sum.compute(&input), [a[0] + b[0], a[1] + b[1]]);
+        let mut average = Merge::average(first, second);
+        assert_eq!(average.compute(&input), [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0]);
+
+        // the returned error is the input gradient of the squared error
+        let target = [1.0, 0.0];
+        let out = average.compute(&input);
+        let gradient = average.input_gradient(&input, &[out[0] - target[0], out[1] - target[1]]);
+        let returned = average.backprop_train(&GradientDescent::new(0.1).unwrap(), &input, &target);
+        for i in 0..2 {
+            assert!((returned[i] - (input[i] - gradient[i])).abs() < 1e-12);
+        }
+        for _ in 0..200 {
+            average.supervised_train(&GradientDescent::new(0.5).unwrap(), &input, &target);
+        }
+        assert!(average.compute(&input).iter().zip(&target).all(|(o, t)| (o - t).abs() < 0.01));
+    }
+
+    #[test]
+    fn mixture() {
+        let constant = |v: f64| FeedforwardLayer::from_matrices(Matrix::from_vec(1, 1, vec![0.0]), vec![v],
+                                                                Activation::Identity);
+        let mut mixture = Mixture::new(constant(1.0), constant(-1.0));
+        assert_eq!(mixture.coefficients(), [0.5]);
+        assert_eq!(mixture.compute(&[0.0]), [0.0]);
+        let gradient = mixture.input_gradient(&[0.0], &[1.0]);
+        assert_eq!(gradient, [0.0]);
+
+        // the members and the coefficient are trained together
+        for _ in 0..500 {
+            mixture.supervised_train(&GradientDescent::new(0.5).unwrap(), &[1.0], &[0.8]);
+        }
+        assert!((mixture.compute(&[1.0])[0] - 0.8).abs() < 0.01);
+        assert!(mixture.coefficients()[0] > 0.5);
+    }
+
+    #[test]
+    fn ensemble() {
+        let ensemble = Ensemble::new(vec![FixedOutput::new(&[1.0f64, 2.0]), FixedOutput::new(&[3.0, -2.0]),
+                                          FixedOutput::new(&[2.0, 3.0])]);
+        assert_eq!(ensemble.output_size(), 2);
+        assert_eq!(ensemble.compute(&[]), [2.0, 1.0]);
+        assert_eq!(ensemble.into_members().len(), 3);
+    }
+
+    #[test]
+    fn split() {
+        let first = FeedforwardLayer::from_matrices(Matrix::from_vec(1, 2, vec![1.0f64, 1.0]),
+                                                    vec![0.0], Activation::Identity);
+        let second = FeedforwardLayer::from_matrices(Matrix::from_vec(2, 1, vec![2.0, -1.0]),
+                                                     vec![0.0, 0.0], Activation::Identity);
+        let mut split = Split::new(first, second);
+        assert_eq!((split.input_size(), split.output_size()), (3, 3));
+        assert_eq!(split.compute(&[1.0, 2.0, 3.0]), [3.0, 6.0, -3.0]);
+        assert_eq!(split.input_gradient(&[1.0, 2.0, 3.0], &[1.0, 1.0, 0.0]), [1.0, 1.0, 2.0]);
+        assert_eq!(split.compute(&[1.0]), [1.0, 0.0, 0.0]);
+        for _ in 0..100 {
+            split.supervised_train(&GradientDescent::new(0.1).unwrap(), &[1.0, 0.0, 1.0], &[0.0, 1.0, 1.0]);
+        }
+        let out = split.compute(&[1.0, 0.0, 1.0]);
+        assert!(out.iter().zip(&[0.0, 1.0, 1.0]).all(|(o, t)| (o - t).abs() < 0.01));
+        // the second network only sees the last input
+        assert_eq!(split.compute(&[5.0, 5.0, 0.0])[1..], split.compute(&[0.0, 0.0, 0.0])[1..]);
+    }
+
+    #[test]
+    fn fn_compute() {
+        let sum = FnCompute::new(3, 1, |x: &[f32]| vec![x.iter().fold(0.0, |s, v| s + v)]);
+        assert_eq!((sum.input_size(), sum.output_size()), (3, 1));
+        assert_eq!(sum.compute(&[1.0, 2.0, 3.0, 4.0]), [6.0]);
+        let chain = Chain::new(Identity::new(2), FnCompute::new(2, 2, |x: &[f32]| vec![x[1], x[0], x[0]]));
+        assert_eq!(chain.compute(&[1.0]), [0.0, 1.0]);
+    }
+
+    #[test]
+    fn recurrent() {
+        // an accumulator: y(t) = x(t) + y(t-1)
+        let inner = FeedforwardLayer::from_matrices(Matrix::from_vec(1, 2, vec![1.0f64, 1.0]),
+                                                    vec![0.0], Activation::Identity);
+        let mut accumulator = Recurrent::new(inner);
+        assert_eq!((accumulator.input_size(), accumulator.output_size()), (1, 1));
+        assert_eq!(accumulator.step(&[1.0]), [1.0]);
+        assert_eq!(accumulator.step(&[2.0]), [3.0]);
+        assert_eq!(accumulator.state(), [3.0]);
+        // computing does not change the state
+        assert_eq!(accumulator.compute(&[1.0]), [4.0]);
+        assert_eq!(accumulator.compute(&[1.0]), [4.0]);
+        accumulator.reset_state();
+        assert_eq!(accumulator.step(&[2.0]), [2.0]);
+
+        // a training step is a step, even after computing the same input
+        let state = accumulator.compute(&[1.0]);
+        accumulator.backprop_train(&GradientDescent::new(0.01).unwrap(), &[1.0], &state);
+        assert_eq!(accumulator.state(), [3.0]);
+        let identity = FeedforwardLayer::from_matrices(Matrix::from_vec(1, 1, vec![1.0]), vec![0.0], Activation::Identity);
+        let mut chain = Chain::new(accumulator.clone(), identity);
+        chain.backprop_train(&GradientDescent::new(0.01).unwrap(), &[1.0], &[4.0]);
+        assert_eq!(chain.first().state(), [4.0]);
+
+        // learn y(t) = x(t) - y(t-1) / 2 from sequences
+        let inner = FeedforwardLayer::new(2, 1, Activation::Identity).without_biases();
+        let mut recurrent = Recurrent::new(inner);
+        let rule = GradientDescent::new(0.05).unwrap();
+        for _ in 0..200 {
+            let mut previous = 0.0f64;
+            recurrent.reset_state();
+            for &x in &[1.0, 0.5, -1.0, 2.0] {
+                let target = x - previous / 2.0;
+                recurrent.supervised_train(&rule, &[x], &[target]);
+                previous = target;
+            }
+        }
+        let weights = recurrent.inner().weights();
+        assert!((weights[(0, 0)] - 1.0).abs() < 0.01 && (weights[(0, 1)] + 0.5).abs() < 0.01);
+    }
+
+    #[test]
+    fn time_delay() {
+        let mut delay = TimeDelay::new(2, 3);
+        assert_eq!((delay.input_size(), delay.output_size()), (2, 6));
+        assert_eq!(delay.step(&[1.0f64, 2.0]), [0.0, 0.0, 0.0, 0.0, 1.0, 2.0]);
+        assert_eq!(delay.step(&[3.0]), [0.0, 0.0, 1.0, 2.0, 3.0, 0.0]);
+        // computing gives the next window without buffering the frame
+        assert_eq!(delay.compute(&[4.0, 5.0, 6.0]), [1.0, 2.0, 3.0, 0.0, 4.0, 5.0]);
+        assert_eq!(delay.step(&[4.0, 5.0, 6.0]), [1.0, 2.0, 3.0, 0.0, 4.0, 5.0]);
+        delay.reset();
+        assert_eq!(delay.compute_sequence(&[vec![1.0, 1.0], vec![2.0, 2.0]]),
+                   [vec![0.0, 0.0, 0.0, 0.0, 1.0, 1.0], vec![0.0, 0.0, 1.0, 1.0, 2.0, 2.0]]);
+
+        // a moving average over 3 frames, trained through a chain
+        let average = FeedforwardLayer::new(3, 1, Activation::Identity).without_biases();
+        let mut network = Chain::new(TimeDelay::new(1, 3), average);
+        let rule = GradientDescent::new(0.05).unwrap();
+        let series = (0..30).map(|i| ((i * 7) % 11) as f64 / 11.0).collect::<Vec<_>>();
+        for _ in 0..100 {
+            network.first_mut().reset();
+            for t in 0..series.len() {
+                let window = &series[t.saturating_sub(2)..t+1];
+                let target = window.iter().sum::<f64>() / 3.0;
+                network.backprop_train(&rule, &[series[t]], &[target]);
+            }
+        }
+        assert!(network.second().weights().as_slice().iter().all(|&w| (w - 1.0 / 3.0).abs() < 0.01));
+        network.reset_state();
+        assert!((network.step(&[0.9])[0] - 0.3).abs() < 0.01);
+    }
+
+    #[test]
+    fn time_delay_repeated_frames() {
+        // identical frames in a row are each buffered
+        let mut delay = TimeDelay::new(1, 3);
+        let rule = GradientDescent::new(0.1).unwrap();
+        for _ in 0..3 {
+            let window = delay.compute(&[1.0f64]);
+            assert_eq!(delay.backprop_train(&rule, &[1.0], &window), [1.0]);
+        }
+        assert_eq!(delay.compute(&[0.0]), [1.0, 1.0, 0.0]);
+
+        // and so through a chain
+        let mut chain = Chain::new(TimeDelay::new(1, 2), Identity::new(2));
+        assert_eq!(chain.step(&[2.0f64]), [0.0, 2.0]);
+        assert_eq!(chain.step(&[2.0]), [2.0, 2.0]);
+        assert_eq!(chain.compute(&[3.0]), [2.0, 3.0]);
+        chain.reset_state();
+        assert_eq!(chain.compute(&[3.0]), [0.0, 3.0]);
+    }
+}